@@ -0,0 +1,71 @@
+//! Count the allocations per request for many small responses, reading each body
+//! with `read_to_vec()`, and with `read_to_buffer()` on an agent with a buffer pool.
+//!
+//! cargo run --example body_buffers --features test-support
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ureq::config::Config;
+use ureq::http::Response;
+use ureq::test_support::TestServer;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const REQUESTS: usize = 10_000;
+const URI: &str = "http://example.test/tiny";
+
+fn main() -> Result<(), ureq::Error> {
+    let server = TestServer::new().route("/tiny", |_| Response::new(vec![b'x'; 512]));
+
+    let agent = server.agent(Config::default());
+    let per_request = count(|| {
+        let body = agent.get(URI).call()?.body_mut().read_to_vec()?;
+        Ok(body.len())
+    })?;
+    println!(
+        "read_to_vec():    {:.2} allocations per request",
+        per_request
+    );
+
+    let agent = server.agent(Config::builder().body_buffer_pool(1).build());
+    let per_request = count(|| {
+        let body = agent.get(URI).call()?.body_mut().read_to_buffer()?;
+        Ok(body.len())
+    })?;
+    println!(
+        "read_to_buffer(): {:.2} allocations per request",
+        per_request
+    );
+
+    Ok(())
+}
+
+/// Average allocations of `request`, after a first request to warm up the pools.
+fn count(request: impl Fn() -> Result<usize, ureq::Error>) -> Result<f64, ureq::Error> {
+    request()?;
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..REQUESTS {
+        assert_eq!(request()?, 512);
+    }
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+
+    Ok((after - before) as f64 / REQUESTS as f64)
+}
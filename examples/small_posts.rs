@@ -0,0 +1,98 @@
+//! Time small POST requests to a local server when the request head and body are
+//! written separately, copied into one write, or sent in one vectored write.
+//!
+//! cargo run --release --example small_posts
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Instant;
+
+use ureq::config::Config;
+use ureq::Agent;
+
+const REQUESTS: u32 = 5_000;
+const SIZES: [usize; 4] = [64, 512, 2048, 4096];
+
+fn main() -> Result<(), ureq::Error> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let uri = format!("http://{}/post", listener.local_addr()?);
+    thread::spawn(move || serve(listener));
+
+    let modes: [(&str, usize); 3] = [
+        // Head and body in two writes.
+        ("separate", 0),
+        // Body copied to the output buffer, one write. The default.
+        ("copied", 4096),
+        // Head and body in one writev, body not copied.
+        ("vectored", 1),
+    ];
+
+    print!("{:>6}", "body");
+    for (name, _) in modes {
+        print!(" {:>12}", name);
+    }
+    println!();
+
+    for size in SIZES {
+        let body = vec![b'x'; size];
+        print!("{:>6}", size);
+        for (_, max_coalesce) in modes {
+            let config = Config::builder()
+                .max_coalesce_body_size(max_coalesce)
+                .build();
+            let micros = time(&Agent::new_with_config(config), &uri, &body)?;
+            print!(" {:>9.1} µs", micros);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Average microseconds per request, after a first request to open the connection.
+fn time(agent: &Agent, uri: &str, body: &[u8]) -> Result<f64, ureq::Error> {
+    agent.post(uri).send(body)?;
+
+    let start = Instant::now();
+    for _ in 0..REQUESTS {
+        agent.post(uri).send(body)?;
+    }
+
+    Ok(start.elapsed().as_secs_f64() * 1_000_000.0 / REQUESTS as f64)
+}
+
+fn serve(listener: TcpListener) {
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || handle(stream));
+    }
+}
+
+/// Answer each request on the connection with an empty 200, until the client hangs up.
+fn handle(stream: TcpStream) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    loop {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        io::copy(&mut (&mut reader).take(length), &mut io::sink())?;
+        writer.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")?;
+    }
+}
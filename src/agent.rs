@@ -1,17 +1,25 @@
 use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::io;
 use std::sync::Arc;
 
-use http::{Method, Request, Response, Uri};
+use http::uri::Authority;
+use http::{Method, Request, Response};
 
-use crate::body::Body;
+use crate::body::{Body, BufferPool};
+use crate::byte_count::{ByteCounter, ByteCounts};
 use crate::config::{AgentScope, Config, ConfigBuilder, HttpCrateScope, RequestLevelConfig};
+use crate::host_limit::HostLimiter;
 use crate::middleware::MiddlewareNext;
-use crate::pool::ConnectionPool;
+use crate::pool::{ConnectionPool, PoolStats};
+use crate::rate_limit::RateLimiter;
 use crate::resolver::{DefaultResolver, Resolver};
+use crate::run::{can_pipeline, run_pipeline};
 use crate::send_body::AsSendBody;
-use crate::transport::{Connector, DefaultConnector};
-use crate::{Error, RequestBuilder, SendBody};
+use crate::transport::{ChainedConnector, Connector, DefaultConnector, LazyBuffers};
+use crate::transport::{StreamConnector, StreamResolver, StreamTransport};
+use crate::tunnel::ConnectBuilder;
+use crate::{Error, IntoUri, RequestBuilder, SendBody};
 use crate::{WithBody, WithoutBody};
 
 /// Agents keep state between requests.
@@ -86,6 +94,10 @@ pub struct Agent {
     pub(crate) config: Arc<Config>,
     pub(crate) pool: Arc<ConnectionPool>,
     pub(crate) resolver: Arc<dyn Resolver>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) host_limiter: Option<Arc<HostLimiter>>,
+    pub(crate) byte_counter: Option<Arc<ByteCounter>>,
+    pub(crate) buffer_pool: Option<Arc<BufferPool>>,
 
     #[cfg(feature = "cookies")]
     pub(crate) jar: Arc<crate::cookies::SharedCookieJar>,
@@ -121,18 +133,102 @@ impl Agent {
     ///
     /// _This is low level API that isn't for regular use of ureq._
     pub fn with_parts(config: Config, connector: impl Connector, resolver: impl Resolver) -> Self {
-        let pool = Arc::new(ConnectionPool::new(connector, &config));
+        let byte_counter = config.count_bytes.then(|| Arc::new(ByteCounter::default()));
+        let pool = Arc::new(ConnectionPool::new(
+            connector,
+            &config,
+            byte_counter.clone(),
+        ));
+        let rate_limiter = config
+            .max_requests_per_second
+            .and_then(RateLimiter::new)
+            .map(Arc::new);
+        let host_limiter = config
+            .max_concurrent_requests_per_host
+            .and_then(|max| HostLimiter::new(max, config.concurrency_slow_start))
+            .map(Arc::new);
+        let buffer_pool = BufferPool::new(config.body_buffer_pool).map(Arc::new);
 
         Agent {
             config: Arc::new(config),
             pool,
             resolver: Arc::new(resolver),
+            rate_limiter,
+            host_limiter,
+            byte_counter,
+            buffer_pool,
 
             #[cfg(feature = "cookies")]
             jar: Arc::new(crate::cookies::SharedCookieJar::new()),
         }
     }
 
+    /// Total bytes sent and received by this agent and its clones.
+    ///
+    /// Requires [`ConfigBuilder::count_bytes()`], otherwise the counts are always zero.
+    /// The counts are of the HTTP/1.1 requests and responses, heads as well as bodies,
+    /// as written to and read from the connections. That is before TLS encryption and
+    /// before any decompression of response bodies.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .count_bytes(true)
+    ///     .build()
+    ///     .into();
+    ///
+    /// agent.get("http://httpbin.org/bytes/100").call()?.body_mut().read_to_vec()?;
+    ///
+    /// let counts = agent.byte_counts();
+    /// assert!(counts.received() > 100);
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn byte_counts(&self) -> ByteCounts {
+        self.byte_counter
+            .as_ref()
+            .map(|c| c.counts())
+            .unwrap_or_default()
+    }
+
+    /// Reset the byte counts to zero, returning the counts before.
+    ///
+    /// See [`Agent::byte_counts()`].
+    pub fn reset_byte_counts(&self) -> ByteCounts {
+        self.byte_counter
+            .as_ref()
+            .map(|c| c.take())
+            .unwrap_or_default()
+    }
+
+    /// Idle and in-use connections of this agent and its clones, in total and per host.
+    ///
+    /// A connection is in use from when a request takes it, until the response body is
+    /// read to the end and the connection is returned to the pool, or until it is closed.
+    /// A count of in-use connections that keeps growing usually means response bodies
+    /// that are neither read nor dropped.
+    ///
+    /// This only briefly holds the lock of the pool, and is cheap enough to poll.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// let agent = Agent::new_with_defaults();
+    ///
+    /// let mut res = agent.get("http://httpbin.org/get").call()?;
+    /// assert_eq!(agent.pool_stats().in_use(), 1);
+    ///
+    /// res.body_mut().read_to_vec()?;
+    /// let stats = agent.pool_stats();
+    /// assert_eq!(stats.in_use(), 0);
+    /// assert_eq!(stats.idle(), 1);
+    /// assert_eq!(stats.hosts()[0].host(), "httpbin.org");
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
     /// Access the shared cookie jar.
     ///
     /// Used to persist and manipulate the cookies. The jar is shared between
@@ -164,6 +260,40 @@ impl Agent {
         self.jar.lock()
     }
 
+    /// Remove all cookies from the shared cookie jar.
+    ///
+    /// Affects all clones of the same [`Agent`]. Deadlocks if the current thread
+    /// holds the [`cookie_jar_lock()`](Agent::cookie_jar_lock).
+    #[cfg(feature = "cookies")]
+    pub fn clear_cookies(&self) {
+        self.jar.lock().clear();
+    }
+
+    /// Replace the cookies of the shared cookie jar with another store.
+    ///
+    /// Returns the cookies the agent had before, which can be swapped back in later.
+    /// Affects all clones of the same [`Agent`]. Deadlocks if the current thread
+    /// holds the [`cookie_jar_lock()`](Agent::cookie_jar_lock).
+    ///
+    /// ```
+    /// use ureq::CookieStore;
+    ///
+    /// let agent = ureq::agent();
+    ///
+    /// // Start a new session, keeping the cookies of the previous one.
+    /// let previous = agent.set_cookie_store(CookieStore::new());
+    ///
+    /// // Back to the previous session.
+    /// agent.set_cookie_store(previous);
+    /// ```
+    #[cfg(feature = "cookies")]
+    pub fn set_cookie_store(
+        &self,
+        store: crate::cookies::CookieStore,
+    ) -> crate::cookies::CookieStore {
+        self.jar.replace(store)
+    }
+
     /// Run a [`http::Request<impl AsSendBody>`].
     ///
     /// Used to execute http crate [`http::Request`] directly on this agent.
@@ -192,6 +322,184 @@ impl Agent {
         self.run_via_middleware(request, body)
     }
 
+    /// Run a request on a stream that is already connected.
+    ///
+    /// No name resolution or connecting is done, the request is sent on `stream` (such as
+    /// a [`TcpStream`](std::net::TcpStream) or a `UnixStream`), and the response is read
+    /// from it. For an `https` uri, TLS is done on top of the stream as usual. Proxy
+    /// settings are ignored, since the stream already leads where it leads.
+    ///
+    /// The agent config, cookies and middleware are used as for any other request, but
+    /// the connection is not pooled. Redirects are followed as long as they can use
+    /// the same connection, any new connection fails with [`Error::ConnectionFailed`].
+    ///
+    /// ureq has no way of setting timeouts on the stream itself. When needed, set them
+    /// before, such as with
+    /// [`TcpStream::set_read_timeout()`](std::net::TcpStream::set_read_timeout).
+    ///
+    /// ```no_run
+    /// use std::net::TcpStream;
+    /// use ureq::Agent;
+    ///
+    /// let agent = Agent::new_with_defaults();
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:8080")?;
+    /// let request = http::Request::get("http://my.service/status").body(())?;
+    ///
+    /// let body = agent.run_on_stream(stream, request)?
+    ///     .body_mut()
+    ///     .read_to_string()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn run_on_stream<S>(
+        &self,
+        stream: S,
+        request: Request<impl AsSendBody>,
+    ) -> Result<Response<Body>, Error>
+    where
+        S: io::Read + io::Write + Send + Sync + 'static,
+    {
+        let mut config = (*self.config).clone();
+        config.proxy = None;
+
+        let buffers = LazyBuffers::new(config.input_buffer_size, config.output_buffer_size);
+        let transport = StreamTransport::new(stream, buffers);
+
+        // The default connector leaves a chained transport be, except for TLS.
+        let connector = ChainedConnector::new([
+            StreamConnector::new(transport).boxed(),
+            DefaultConnector::default().boxed(),
+        ]);
+
+        let agent = Agent {
+            pool: Arc::new(ConnectionPool::new(
+                connector,
+                &config,
+                self.byte_counter.clone(),
+            )),
+            config: Arc::new(config),
+            resolver: Arc::new(StreamResolver),
+            rate_limiter: self.rate_limiter.clone(),
+            host_limiter: self.host_limiter.clone(),
+            byte_counter: self.byte_counter.clone(),
+            buffer_pool: self.buffer_pool.clone(),
+
+            #[cfg(feature = "cookies")]
+            jar: self.jar.clone(),
+        };
+
+        agent.run(request)
+    }
+
+    /// Make a `CONNECT` request using this agent, to open a tunnel via a proxy.
+    ///
+    /// The target is an authority, such as `"example.test:22"`. See [`ConnectBuilder`].
+    #[must_use]
+    pub fn connect<T>(&self, target: T) -> ConnectBuilder
+    where
+        Authority: TryFrom<T>,
+        <Authority as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        ConnectBuilder::new(self.clone(), target)
+    }
+
+    /// Make a request using this agent, with a method decided at runtime.
+    ///
+    /// The builder always takes a body. Whether that body is sent is decided by the
+    /// method, like for the typed constructors such as [`Agent::post()`].
+    ///
+    /// * `POST`, `PUT` and `PATCH` are as from [`Agent::post()`], where
+    ///   [`send_empty()`](RequestBuilder::send_empty) sends `Content-Length: 0`.
+    /// * Any other method is as from [`Agent::get()`] followed by
+    ///   [`force_send_body()`](RequestBuilder::force_send_body), meaning a body given
+    ///   to [`send()`](RequestBuilder::send) is sent despite the method.
+    ///
+    /// For either, sending [`SendBody::none()`] is the same as
+    /// [`call()`](RequestBuilder::call), without body or framing header.
+    ///
+    /// Extension methods, such as `PURGE`, are not supported and fail with
+    /// [`Error::Protocol`](crate::Error::Protocol).
+    ///
+    /// ```
+    /// use ureq::http::Method;
+    /// use ureq::{Agent, SendBody};
+    ///
+    /// let agent = Agent::new_with_defaults();
+    ///
+    /// let method: Method = "PUT".parse()?;
+    /// agent.request(method, "http://httpbin.org/put").send("data")?;
+    ///
+    /// agent
+    ///     .request(Method::GET, "http://httpbin.org/get")
+    ///     .send(SendBody::none())?;
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn request<T: IntoUri>(&self, method: Method, uri: T) -> RequestBuilder<WithBody> {
+        match method {
+            Method::POST | Method::PUT | Method::PATCH => {
+                RequestBuilder::<WithBody>::new(self.clone(), method, uri)
+            }
+            _ => RequestBuilder::<WithoutBody>::new(self.clone(), method, uri).force_send_body(),
+        }
+    }
+
+    /// Run several requests pipelined on one connection.
+    ///
+    /// With HTTP/1.1 pipelining, all requests are sent before the first response is
+    /// read, which saves waiting for each response before sending the next request.
+    /// The results are in the order of the requests.
+    ///
+    /// Only `GET` and `HEAD` requests to the same host are pipelined, and only when the
+    /// agent has no [middleware](crate::config::ConfigBuilder::middleware), no
+    /// [credentials](crate::config::ConfigBuilder::auth_credentials) and the requests no
+    /// request level configuration. Otherwise, and for whatever is left once something
+    /// goes wrong on the pipelined connection, such as a redirect to follow, the requests
+    /// are run one by one like [`Agent::run()`].
+    ///
+    /// Response bodies of pipelined requests are read into memory, since the next
+    /// response comes after the body. A body larger than
+    /// [`max_response_size`](crate::config::ConfigBuilder::max_response_size), or 10MB
+    /// when that is not set, ends the pipelining.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// let agent: Agent = Agent::new_with_defaults();
+    ///
+    /// let requests = vec![
+    ///     http::Request::get("http://httpbin.org/get").body(())?,
+    ///     http::Request::get("http://httpbin.org/bytes/100").body(())?,
+    /// ];
+    ///
+    /// for result in agent.pipeline(requests) {
+    ///     let mut response = result?;
+    ///     let body = response.body_mut().read_to_vec()?;
+    ///     println!("{} bytes", body.len());
+    /// }
+    /// # Ok::<(), ureq::Error>(())
+    /// ```
+    pub fn pipeline(
+        &self,
+        requests: impl IntoIterator<Item = Request<()>>,
+    ) -> Vec<Result<Response<Body>, Error>> {
+        let requests: Vec<Request<()>> = requests.into_iter().collect();
+
+        let mut results = if can_pipeline(&self.config, &requests) {
+            run_pipeline(self, &requests)
+        } else {
+            Vec::new()
+        };
+
+        for request in requests.into_iter().skip(results.len()) {
+            results.push(self.run(request));
+        }
+
+        results
+    }
+
     pub(crate) fn run_via_middleware(
         &self,
         request: Request<()>,
@@ -240,11 +548,7 @@ macro_rules! mk_method {
             $(
                 #[doc = concat!("Make a ", stringify!($m), " request using this agent.")]
                 #[must_use]
-                pub fn $f<T>(&self, uri: T) -> RequestBuilder<$b>
-                where
-                    Uri: TryFrom<T>,
-                    <Uri as TryFrom<T>>::Error: Into<http::Error>,
-                {
+                pub fn $f<T: IntoUri>(&self, uri: T) -> RequestBuilder<$b> {
                     RequestBuilder::<$b>::new(self.clone(), Method::$m, uri)
                 }
             )*
@@ -259,7 +563,6 @@ mk_method!(
     (delete, DELETE, WithoutBody),
     (head, HEAD, WithoutBody),
     (options, OPTIONS, WithoutBody),
-    (connect, CONNECT, WithoutBody),
     (patch, PATCH, WithBody),
     (trace, TRACE, WithoutBody)
 );
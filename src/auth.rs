@@ -0,0 +1,473 @@
+//! Answering `401 Unauthorized` challenges with Basic or Digest auth.
+
+use std::fmt;
+#[cfg(feature = "digest-auth")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "digest-auth")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use http::{HeaderMap, HeaderValue, Method, Uri};
+#[cfg(feature = "digest-auth")]
+use md5::{Digest, Md5};
+
+/// Username and password to answer authentication challenges.
+///
+/// When a server responds `401 Unauthorized` with a `WWW-Authenticate` header
+/// asking for `Basic` or `Digest` auth, ureq retries the request once with an
+/// `Authorization` header computed from these credentials. Digest requires the
+/// **digest-auth** feature.
+///
+/// See [`ConfigBuilder::auth_credentials()`](crate::config::ConfigBuilder::auth_credentials).
+#[derive(Clone)]
+pub struct Credentials(Arc<Inner>);
+
+struct Inner {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    /// Creates credentials from username and password.
+    pub fn new(username: &str, password: &str) -> Self {
+        Credentials(Arc::new(Inner {
+            username: username.to_string(),
+            password: password.to_string(),
+        }))
+    }
+
+    /// Compute the `Authorization` header answering the challenges in `headers`.
+    ///
+    /// Digest is preferred over Basic. Returns `None` if there is no challenge we support.
+    pub(crate) fn authorization(
+        &self,
+        headers: &HeaderMap,
+        method: &Method,
+        uri: &Uri,
+    ) -> Option<HeaderValue> {
+        let challenges: Vec<&str> = headers
+            .get_all(http::header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(split_challenges)
+            .collect();
+
+        #[cfg(feature = "digest-auth")]
+        {
+            let digest = challenges
+                .iter()
+                .filter_map(|c| strip_scheme(c, "digest"))
+                .find_map(|c| self.digest(c, method, uri));
+
+            if let Some(value) = digest {
+                return HeaderValue::from_str(&value).ok();
+            }
+        }
+        #[cfg(not(feature = "digest-auth"))]
+        let _ = (method, uri);
+
+        challenges
+            .iter()
+            .find(|c| strip_scheme(c, "basic").is_some())?;
+
+        HeaderValue::from_str(&self.basic()).ok()
+    }
+
+    pub(crate) fn basic(&self) -> String {
+        let user_pass = format!("{}:{}", self.0.username, self.0.password);
+        format!("Basic {}", BASE64_STANDARD.encode(user_pass))
+    }
+
+    #[cfg(feature = "digest-auth")]
+    fn digest(&self, challenge: &str, method: &Method, uri: &Uri) -> Option<String> {
+        let params = parse_params(challenge);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+
+        let realm = param("realm")?;
+        let nonce = param("nonce")?;
+        let opaque = param("opaque");
+
+        let algorithm = param("algorithm").unwrap_or("MD5");
+        let sess = if algorithm.eq_ignore_ascii_case("MD5") {
+            false
+        } else if algorithm.eq_ignore_ascii_case("MD5-sess") {
+            true
+        } else {
+            debug!("Unsupported digest algorithm: {}", algorithm);
+            return None;
+        };
+
+        // We only do qop=auth. A challenge with only auth-int is not supported.
+        let qop = match param("qop") {
+            Some(v) => {
+                if !v.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth")) {
+                    debug!("Unsupported digest qop: {}", v);
+                    return None;
+                }
+                Some("auth")
+            }
+            None => None,
+        };
+
+        let digest_uri = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        let cnonce = cnonce();
+
+        let response = digest_response(&DigestInput {
+            username: &self.0.username,
+            password: &self.0.password,
+            realm,
+            nonce,
+            cnonce: &cnonce,
+            nc: "00000001",
+            qop,
+            sess,
+            method: method.as_str(),
+            uri: digest_uri,
+        });
+
+        let mut value = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", \
+            response=\"{}\", algorithm={}",
+            quote(&self.0.username),
+            quote(realm),
+            quote(nonce),
+            quote(digest_uri),
+            response,
+            algorithm
+        );
+
+        if let Some(qop) = qop {
+            value.push_str(&format!(
+                ", qop={}, nc=00000001, cnonce=\"{}\"",
+                qop, cnonce
+            ));
+        }
+        if let Some(opaque) = opaque {
+            value.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+
+        Some(value)
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.0.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Split a `WWW-Authenticate` value into its challenges.
+///
+/// A server may send several challenges in one header, separated by commas like the
+/// parameters: `Basic realm="a", Digest realm="b", nonce="c"`. An element starts a new
+/// challenge when it begins with a scheme name rather than a `key=value` parameter.
+fn split_challenges(value: &str) -> Vec<&str> {
+    let mut challenges = vec![];
+    // Start of the current challenge, and end of its last element.
+    let mut current: Option<(usize, usize)> = None;
+
+    let mut element_start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices().chain([(value.len(), ',')]) {
+        if in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quotes = false,
+                _ => {}
+            }
+            if i < value.len() {
+                continue;
+            }
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => {
+                let element = &value[element_start..i];
+                if starts_challenge(element) {
+                    if let Some((start, end)) = current {
+                        challenges.push(value[start..end].trim());
+                    }
+                    current = Some((element_start, i));
+                } else if let Some((_, end)) = &mut current {
+                    if !element.trim().is_empty() {
+                        *end = i;
+                    }
+                }
+                element_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((start, end)) = current {
+        challenges.push(value[start..end].trim());
+    }
+
+    challenges
+}
+
+/// Whether a comma separated element of `WWW-Authenticate` begins with a scheme name.
+fn starts_challenge(element: &str) -> bool {
+    let element = element.trim_start();
+    let (name, rest) = element.split_once(' ').unwrap_or((element, ""));
+    !name.is_empty() && !name.contains('=') && !rest.trim_start().starts_with('=')
+}
+
+/// The challenge parameters, if the challenge is for `scheme`.
+fn strip_scheme<'a>(challenge: &'a str, scheme: &str) -> Option<&'a str> {
+    let challenge = challenge.trim_start();
+    let (name, rest) = challenge.split_once(' ').unwrap_or((challenge, ""));
+    name.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+#[cfg(feature = "digest-auth")]
+/// Parse `key=value, key="quoted, value"` pairs.
+fn parse_params(s: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        let key = key.trim();
+        if key.is_empty() {
+            break;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                value.push(c);
+            }
+        }
+
+        params.push((key.to_string(), value.trim().to_string()));
+    }
+
+    params
+}
+
+#[cfg(feature = "digest-auth")]
+/// Escape `"` and `\` for a quoted-string.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted
+}
+
+#[cfg(feature = "digest-auth")]
+fn cnonce() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let seed = format!("{}:{}:{}", nanos, count, std::process::id());
+
+    hex(&Md5::digest(seed.as_bytes()))[..16].to_string()
+}
+
+#[cfg(feature = "digest-auth")]
+struct DigestInput<'a> {
+    username: &'a str,
+    password: &'a str,
+    realm: &'a str,
+    nonce: &'a str,
+    cnonce: &'a str,
+    nc: &'a str,
+    qop: Option<&'a str>,
+    sess: bool,
+    method: &'a str,
+    uri: &'a str,
+}
+
+#[cfg(feature = "digest-auth")]
+/// RFC 2617 request-digest.
+fn digest_response(i: &DigestInput) -> String {
+    let h = |s: String| hex(&Md5::digest(s.as_bytes()));
+
+    let mut ha1 = h(format!("{}:{}:{}", i.username, i.realm, i.password));
+    if i.sess {
+        ha1 = h(format!("{}:{}:{}", ha1, i.nonce, i.cnonce));
+    }
+
+    let ha2 = h(format!("{}:{}", i.method, i.uri));
+
+    match i.qop {
+        Some(qop) => h(format!(
+            "{}:{}:{}:{}:{}:{}",
+            ha1, i.nonce, i.nc, i.cnonce, qop, ha2
+        )),
+        None => h(format!("{}:{}:{}", ha1, i.nonce, ha2)),
+    }
+}
+
+#[cfg(feature = "digest-auth")]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "digest-auth")]
+    fn rfc2617_example() {
+        let response = digest_response(&DigestInput {
+            username: "Mufasa",
+            password: "Circle Of Life",
+            realm: "testrealm@host.com",
+            nonce: "dcd98b7102dd2f0e8b11d0f600bfb0c093",
+            cnonce: "0a4f113b",
+            nc: "00000001",
+            qop: Some("auth"),
+            sess: false,
+            method: "GET",
+            uri: "/dir/index.html",
+        });
+        assert_eq!(response, "6629fae49393a05397450978507c4ef1");
+    }
+
+    #[test]
+    #[cfg(feature = "digest-auth")]
+    fn parse_challenge_params() {
+        let params = parse_params(
+            r#"realm="test, realm", qop="auth,auth-int", nonce=abc123, opaque="5ccc""#,
+        );
+        assert_eq!(
+            params,
+            vec![
+                ("realm".to_string(), "test, realm".to_string()),
+                ("qop".to_string(), "auth,auth-int".to_string()),
+                ("nonce".to_string(), "abc123".to_string()),
+                ("opaque".to_string(), "5ccc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "digest-auth")]
+    fn prefer_digest_over_basic() {
+        let mut headers = HeaderMap::new();
+        headers.append("www-authenticate", "Basic realm=\"x\"".parse().unwrap());
+        headers.append(
+            "www-authenticate",
+            "Digest realm=\"x\", nonce=\"n\", qop=\"auth\""
+                .parse()
+                .unwrap(),
+        );
+
+        let creds = Credentials::new("user", "pass");
+        let uri = "http://x.test/a?b".parse().unwrap();
+        let value = creds.authorization(&headers, &Method::GET, &uri).unwrap();
+        let value = value.to_str().unwrap();
+
+        assert!(value.starts_with("Digest username=\"user\""));
+        assert!(value.contains("uri=\"/a?b\""));
+        assert!(value.contains("qop=auth, nc=00000001"));
+    }
+
+    #[test]
+    #[cfg(feature = "digest-auth")]
+    fn quote_username_and_realm() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "www-authenticate",
+            r#"Digest realm="a\"b", nonce="n""#.parse().unwrap(),
+        );
+
+        let creds = Credentials::new(r#"us"e\r"#, "pass");
+        let uri = "http://x.test/".parse().unwrap();
+        let value = creds.authorization(&headers, &Method::GET, &uri).unwrap();
+        let value = value.to_str().unwrap();
+
+        assert!(value.starts_with(r#"Digest username="us\"e\\r""#));
+        assert!(value.contains(r#"realm="a\"b""#));
+    }
+
+    #[test]
+    fn split_several_challenges() {
+        assert_eq!(
+            split_challenges(r#"Basic realm="a", Digest realm="b, c", nonce="n", qop=auth"#),
+            vec![
+                r#"Basic realm="a""#,
+                r#"Digest realm="b, c", nonce="n", qop=auth"#
+            ]
+        );
+        assert_eq!(
+            split_challenges("Negotiate abc==, Basic"),
+            vec!["Negotiate abc==", "Basic"]
+        );
+        assert_eq!(
+            split_challenges(r#"Basic realm = "a""#),
+            vec![r#"Basic realm = "a""#]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "digest-auth")]
+    fn digest_after_basic_in_one_header() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "www-authenticate",
+            r#"Basic realm="a", Digest realm="b", nonce="n""#.parse().unwrap(),
+        );
+
+        let creds = Credentials::new("user", "pass");
+        let uri = "http://x.test/".parse().unwrap();
+        let value = creds.authorization(&headers, &Method::GET, &uri).unwrap();
+        let value = value.to_str().unwrap();
+
+        assert!(value.starts_with("Digest username=\"user\", realm=\"b\""));
+    }
+
+    #[test]
+    fn basic_challenge() {
+        let mut headers = HeaderMap::new();
+        headers.append("www-authenticate", "basic realm=\"x\"".parse().unwrap());
+
+        let creds = Credentials::new("user", "pass");
+        let uri = "http://x.test/".parse().unwrap();
+        let value = creds.authorization(&headers, &Method::GET, &uri).unwrap();
+        assert_eq!(value, "Basic dXNlcjpwYXNz");
+    }
+
+    #[test]
+    fn unsupported_challenge() {
+        let mut headers = HeaderMap::new();
+        headers.append("www-authenticate", "Bearer realm=\"x\"".parse().unwrap());
+
+        let creds = Credentials::new("user", "pass");
+        let uri = "http://x.test/".parse().unwrap();
+        assert!(creds.authorization(&headers, &Method::GET, &uri).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// Buffers kept by an agent to read bodies into, instead of allocating per response.
+///
+/// See [`ConfigBuilder::body_buffer_pool()`](crate::config::ConfigBuilder::body_buffer_pool).
+#[derive(Debug)]
+pub(crate) struct BufferPool {
+    max: usize,
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    /// Creates a pool, provided max is not 0.
+    pub fn new(max: usize) -> Option<Self> {
+        if max == 0 {
+            return None;
+        }
+
+        Some(BufferPool {
+            max,
+            buffers: Mutex::new(Vec::with_capacity(max)),
+        })
+    }
+
+    fn take(&self) -> Vec<u8> {
+        self.buffers.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    fn give_back(&self, mut buf: Vec<u8>) {
+        if buf.capacity() == 0 {
+            return;
+        }
+        buf.clear();
+
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max {
+            buffers.push(buf);
+        }
+    }
+}
+
+/// A body read to memory, in a buffer that goes back to the agent once dropped.
+///
+/// Created by [`Body::read_to_buffer()`](crate::Body::read_to_buffer). Dereferences
+/// to the body data.
+pub struct BodyBuffer {
+    buf: Vec<u8>,
+    pool: Option<Arc<BufferPool>>,
+}
+
+impl BodyBuffer {
+    pub(crate) fn new(pool: Option<Arc<BufferPool>>) -> Self {
+        let buf = pool.as_ref().map(|p| p.take()).unwrap_or_default();
+        BodyBuffer { buf, pool }
+    }
+
+    pub(crate) fn buf_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+
+    /// Take the data out of the buffer, which then doesn't go back to the agent.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        self.pool = None;
+        mem::take(&mut self.buf)
+    }
+}
+
+impl Deref for BodyBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl AsRef<[u8]> for BodyBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl Drop for BodyBuffer {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.give_back(mem::take(&mut self.buf));
+        }
+    }
+}
+
+impl fmt::Debug for BodyBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyBuffer")
+            .field("len", &self.buf.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zero_is_no_pool() {
+        assert!(BufferPool::new(0).is_none());
+    }
+
+    #[test]
+    fn buffer_is_reused() {
+        let pool = Arc::new(BufferPool::new(1).unwrap());
+
+        let mut a = BodyBuffer::new(Some(pool.clone()));
+        a.buf_mut().extend_from_slice(b"hello");
+        let ptr = a.as_ptr();
+        drop(a);
+
+        let b = BodyBuffer::new(Some(pool.clone()));
+        assert!(b.is_empty());
+        assert_eq!(b.buf.as_ptr(), ptr);
+
+        // Only max buffers are kept.
+        let mut c = BodyBuffer::new(Some(pool.clone()));
+        c.buf_mut().push(1);
+        drop((b, c));
+        assert_eq!(pool.buffers.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn into_vec_keeps_data() {
+        let pool = Arc::new(BufferPool::new(1).unwrap());
+
+        let mut a = BodyBuffer::new(Some(pool.clone()));
+        a.buf_mut().extend_from_slice(b"hello");
+        assert_eq!(a.into_vec(), b"hello");
+        assert!(pool.buffers.lock().unwrap().is_empty());
+    }
+}
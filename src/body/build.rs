@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use hoot::BodyMode;
 
-use super::{Body, BodyDataSource, ContentEncoding, ResponseInfo};
+use super::{Body, BodyDataSource, BufferGrowth, ResponseInfo};
 
 /// Builder for creating a response body.
 ///
@@ -42,10 +42,13 @@ impl BodyBuilder {
     pub(crate) fn new() -> Self {
         BodyBuilder {
             info: ResponseInfo {
-                content_encoding: ContentEncoding::None,
+                content_encodings: Vec::new(),
                 mime_type: None,
                 charset: None,
+                boundary: None,
                 body_mode: BodyMode::NoBody,
+                buffer_growth: BufferGrowth::default(),
+                buffer_pool: None,
             },
             limit: None,
         }
@@ -84,6 +87,23 @@ impl BodyBuilder {
         self
     }
 
+    /// Set the multipart boundary of the body
+    ///
+    /// **This does not set any HTTP headers. Affects [`Body::into_multipart()`].**
+    ///
+    /// ```
+    /// use ureq::Body;
+    ///
+    /// let body = Body::builder()
+    ///     .mime_type("multipart/mixed")
+    ///     .boundary("abc")
+    ///     .data("--abc\r\n\r\nHello world!\r\n--abc--\r\n");
+    /// ```
+    pub fn boundary(mut self, boundary: impl Into<String>) -> Self {
+        self.info.boundary = Some(boundary.into());
+        self
+    }
+
     /// Limit how much data is to be released from the body.
     ///
     /// **This does not set any HTTP headers. Affects Body decoding.**
@@ -0,0 +1,193 @@
+use std::io::{self, Cursor};
+use std::mem;
+
+use flate2::read::{DeflateDecoder as RawDecoder, ZlibDecoder};
+
+use crate::Error;
+
+type Prefixed<R> = io::Chain<Cursor<Vec<u8>>, R>;
+
+/// Decoder for `Content-Encoding: deflate`.
+///
+/// The spec says deflate means zlib-wrapped data, but plenty of servers send raw
+/// deflate. We sniff the zlib header to pick the format. Since a raw stream can
+/// start with bytes that happen to look like a zlib header, a zlib decode that fails
+/// before producing any output is restarted as raw deflate.
+pub(crate) struct DeflateDecoder<R>(Inner<R>);
+
+enum Inner<R> {
+    Sniff(R),
+    Zlib(ZlibDecoder<Recorder<Prefixed<R>>>, bool),
+    Raw(RawDecoder<Prefixed<Prefixed<R>>>),
+    Failed,
+}
+
+impl<R: io::Read> DeflateDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        DeflateDecoder(Inner::Sniff(reader))
+    }
+}
+
+impl<R: io::Read> io::Read for DeflateDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match mem::replace(&mut self.0, Inner::Failed) {
+                Inner::Sniff(mut reader) => {
+                    let mut header = [0; 2];
+                    let mut n = 0;
+                    while n < header.len() {
+                        let amount = reader.read(&mut header[n..])?;
+                        if amount == 0 {
+                            break;
+                        }
+                        n += amount;
+                    }
+
+                    if n == 0 {
+                        self.0 = Inner::Sniff(reader);
+                        return Ok(0);
+                    }
+
+                    let is_zlib = is_zlib_header(&header[..n]);
+                    let prefixed = Cursor::new(header[..n].to_vec()).chain(reader);
+
+                    self.0 = if is_zlib {
+                        Inner::Zlib(ZlibDecoder::new(Recorder::new(prefixed)), false)
+                    } else {
+                        debug!("Raw deflate without zlib header");
+                        Inner::Raw(RawDecoder::new(Cursor::new(vec![]).chain(prefixed)))
+                    };
+                }
+
+                Inner::Zlib(mut decoder, produced) => match decoder.read(buf) {
+                    Ok(n) => {
+                        if n > 0 && !produced {
+                            // The format is settled, no need to keep recording.
+                            decoder.get_mut().stop();
+                        }
+                        self.0 = Inner::Zlib(decoder, produced || n > 0);
+                        return Ok(n);
+                    }
+                    Err(e) if !produced => {
+                        debug!("Zlib decode failed, retry as raw deflate: {}", e);
+                        let (recorded, reader) = decoder.into_inner().into_parts();
+                        self.0 = Inner::Raw(RawDecoder::new(Cursor::new(recorded).chain(reader)));
+                    }
+                    Err(e) => return Err(Error::Decompress("deflate", e).into_io()),
+                },
+
+                Inner::Raw(mut decoder) => {
+                    let ret = decoder.read(buf);
+                    self.0 = Inner::Raw(decoder);
+                    return ret.map_err(|e| Error::Decompress("deflate", e).into_io());
+                }
+
+                Inner::Failed => {
+                    let e = io::Error::new(io::ErrorKind::Other, "previous read failed");
+                    return Err(Error::Decompress("deflate", e).into_io());
+                }
+            }
+        }
+    }
+}
+
+/// RFC 1950: compression method 8 (deflate), window size at most 32K, and the two
+/// header bytes as a big endian number is a multiple of 31.
+fn is_zlib_header(header: &[u8]) -> bool {
+    let [cmf, flg] = header else {
+        return false;
+    };
+    cmf & 0x0f == 8 && cmf >> 4 <= 7 && ((u16::from(*cmf) << 8) | u16::from(*flg)) % 31 == 0
+}
+
+/// Keeps a copy of the data read, to allow starting over.
+struct Recorder<R> {
+    reader: R,
+    recorded: Vec<u8>,
+    recording: bool,
+}
+
+impl<R> Recorder<R> {
+    fn new(reader: R) -> Self {
+        Recorder {
+            reader,
+            recorded: Vec::new(),
+            recording: true,
+        }
+    }
+
+    fn stop(&mut self) {
+        self.recording = false;
+        self.recorded = Vec::new();
+    }
+
+    fn into_parts(self) -> (Vec<u8>, R) {
+        (self.recorded, self.reader)
+    }
+}
+
+impl<R: io::Read> io::Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if self.recording {
+            self.recorded.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    use flate2::write::{DeflateEncoder, ZlibEncoder};
+    use flate2::Compression;
+
+    use super::*;
+
+    const TEXT: &str = "hello world, hello deflate, hello world";
+
+    fn decode(data: &[u8]) -> String {
+        let mut s = String::new();
+        DeflateDecoder::new(data).read_to_string(&mut s).unwrap();
+        s
+    }
+
+    #[test]
+    fn zlib_wrapped() {
+        let mut enc = ZlibEncoder::new(vec![], Compression::default());
+        enc.write_all(TEXT.as_bytes()).unwrap();
+        let data = enc.finish().unwrap();
+
+        assert!(is_zlib_header(&data[..2]));
+        assert_eq!(decode(&data), TEXT);
+    }
+
+    #[test]
+    fn raw_deflate() {
+        let mut enc = DeflateEncoder::new(vec![], Compression::default());
+        enc.write_all(TEXT.as_bytes()).unwrap();
+        let data = enc.finish().unwrap();
+
+        assert_eq!(decode(&data), TEXT);
+    }
+
+    #[test]
+    fn raw_deflate_looking_like_zlib() {
+        // A non-final stored block, where the padding bits after the block header
+        // make the first two bytes a valid zlib header (0x78 0x01). The stored
+        // length doesn't check out when read as zlib, which fails the zlib decode.
+        let data = [
+            0x78, 0x01, 0x00, 0xfe, 0xff, b'h', // stored block, LEN=1, "h"
+            0x01, 0x00, 0x00, 0xff, 0xff, // final stored block, LEN=0
+        ];
+
+        assert!(is_zlib_header(&data[..2]));
+        assert_eq!(decode(&data), "h");
+    }
+
+    #[test]
+    fn empty_body() {
+        assert_eq!(decode(b""), "");
+    }
+}
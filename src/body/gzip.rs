@@ -1,30 +1,104 @@
 use std::io;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
 
 use flate2::read::MultiGzDecoder;
 
 use crate::Error;
 
-pub(crate) struct GzipDecoder<R>(MultiGzDecoder<R>);
+pub(crate) struct GzipDecoder<R> {
+    decoder: MultiGzDecoder<R>,
+    end_gate: Option<EndGate>,
+}
 
 impl<R: io::Read> GzipDecoder<R> {
-    pub fn new(reader: R) -> Self {
-        GzipDecoder(MultiGzDecoder::new(reader))
+    pub fn new(reader: R, end_gate: Option<EndGate>) -> Self {
+        GzipDecoder {
+            decoder: MultiGzDecoder::new(reader),
+            end_gate,
+        }
     }
 }
 
 impl<R: io::Read> io::Read for GzipDecoder<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0
-            .read(buf)
-            .map_err(|e| Error::Decompress("gzip", e).into_io())
+        let n = match self.decoder.read(buf) {
+            Ok(n) => n,
+            Err(e) => {
+                if let Some(gate) = &self.end_gate {
+                    gate.fail();
+                }
+                return Err(Error::Decompress("gzip", e).into_io());
+            }
+        };
+
+        if n == 0 && !buf.is_empty() {
+            if let Some(gate) = self.end_gate.take() {
+                // The gzip trailer is verified, and the body may end. The body is
+                // already fully read, so this read ends it.
+                gate.open();
+                let extra = self.decoder.get_mut().read(&mut [0; 1])?;
+                debug_assert_eq!(extra, 0);
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+/// Holds off the end of a gzip encoded response body until the decoder has verified
+/// the gzip stream.
+///
+/// The connection is returned to the pool when the body ends. Without the gate, that
+/// happens as soon as the body is read to the end, also when the gzip stream in it turns
+/// out to be truncated or corrupt. With the gate, the body only ends once the decoder
+/// has checked the gzip trailer, and a connection with a broken stream is closed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EndGate(Arc<AtomicU8>);
+
+const PENDING: u8 = 0;
+const OPEN: u8 = 1;
+const FAILED: u8 = 2;
+
+impl EndGate {
+    fn open(&self) {
+        self.0.store(OPEN, Ordering::Release);
+    }
+
+    fn fail(&self) {
+        self.0.store(FAILED, Ordering::Release);
+    }
+
+    /// Whether the body may end.
+    pub fn is_open(&self) -> bool {
+        self.0.load(Ordering::Acquire) == OPEN
+    }
+
+    /// Whether the decoder failed, in which case the connection must not be reused.
+    pub fn is_failed(&self) -> bool {
+        self.0.load(Ordering::Acquire) == FAILED
+    }
+
+    /// Open the gate unless the decoder failed.
+    ///
+    /// Used when the rest of the body is drained without decoding.
+    pub fn open_unless_failed(&self) {
+        let _ = self
+            .0
+            .compare_exchange(PENDING, OPEN, Ordering::AcqRel, Ordering::Acquire);
     }
 }
 
 #[cfg(all(test, feature = "_test"))]
 mod test {
+    use std::io::{Read, Write};
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
     use crate::test::init_test_log;
     use crate::transport::set_handler;
-    use crate::Agent;
+    use crate::{Agent, Error};
 
     // Test that a stream gets returned to the pool if it is gzip encoded and the gzip
     // decoder reads the exact amount from a chunked stream, not past the 0. This
@@ -66,4 +140,175 @@ mod test {
 
         assert_eq!(agent.pool_count(), 1);
     }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut enc = GzEncoder::new(vec![], Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn gz_content_length_pooled() {
+        init_test_log();
+
+        let gz = gzip(b"hello world hello world");
+        let len = gz.len().to_string();
+
+        let agent = Agent::new_with_defaults();
+
+        set_handler(
+            "/gz_pooled",
+            200,
+            &[("content-encoding", "gzip"), ("content-length", &len)],
+            &gz,
+        );
+
+        let mut res = agent.get("https://example.test/gz_pooled").call().unwrap();
+        let text = res.body_mut().read_to_string().unwrap();
+        assert_eq!(text, "hello world hello world");
+
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn gz_truncated_not_pooled() {
+        init_test_log();
+
+        let gz = gzip(b"hello world hello world");
+        // Correct framing for the body, but the gzip trailer is cut off.
+        let truncated = &gz[..gz.len() - 4];
+        let len = truncated.len().to_string();
+
+        let agent = Agent::new_with_defaults();
+
+        set_handler(
+            "/gz_truncated",
+            200,
+            &[("content-encoding", "gzip"), ("content-length", &len)],
+            truncated,
+        );
+
+        let mut res = agent
+            .get("https://example.test/gz_truncated")
+            .call()
+            .unwrap();
+        let err = res.body_mut().read_to_string().unwrap_err();
+        assert!(matches!(err, Error::Decompress("gzip", _)), "{:?}", err);
+        drop(res);
+
+        assert_eq!(agent.pool_count(), 0);
+    }
+
+    #[test]
+    fn gz_bad_checksum_not_pooled() {
+        init_test_log();
+
+        let mut gz = gzip(b"hello world hello world");
+        // The CRC32 is the first 4 of the 8 trailer bytes.
+        let crc = gz.len() - 8;
+        gz[crc] ^= 0xff;
+        let len = gz.len().to_string();
+
+        let agent = Agent::new_with_defaults();
+
+        set_handler(
+            "/gz_checksum",
+            200,
+            &[("content-encoding", "gzip"), ("content-length", &len)],
+            &gz,
+        );
+
+        let mut res = agent
+            .get("https://example.test/gz_checksum")
+            .call()
+            .unwrap();
+        let err = res.body_mut().read_to_string().unwrap_err();
+        assert!(matches!(err, Error::Decompress("gzip", _)), "{:?}", err);
+        drop(res);
+
+        assert_eq!(agent.pool_count(), 0);
+    }
+
+    #[test]
+    fn gz_unread_body_drained_and_pooled() {
+        init_test_log();
+
+        let gz = gzip(b"hello world hello world");
+        let len = gz.len().to_string();
+
+        let agent = Agent::new_with_defaults();
+
+        set_handler(
+            "/gz_unread",
+            200,
+            &[("content-encoding", "gzip"), ("content-length", &len)],
+            &gz,
+        );
+
+        let mut res = agent.get("https://example.test/gz_unread").call().unwrap();
+        // Start to decode, then drop the rest.
+        let mut buf = [0; 5];
+        res.body_mut().as_reader().read_exact(&mut buf).unwrap();
+        drop(res);
+
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn gzip_decoded_reader_at_rest() {
+        init_test_log();
+
+        let mut enc = GzEncoder::new(vec![], Compression::default());
+        enc.write_all(b"a,b,c\n1,2,3\n").unwrap();
+        let gz = enc.finish().unwrap();
+        let len = gz.len().to_string();
+
+        set_handler(
+            "/data.csv.gz",
+            200,
+            &[
+                ("content-type", "application/gzip"),
+                ("content-length", &len),
+            ],
+            &gz,
+        );
+
+        let res = crate::get("https://example.test/data.csv.gz")
+            .call()
+            .unwrap();
+
+        let mut csv = String::new();
+        res.into_body()
+            .into_gzip_decoded_reader()
+            .read_to_string(&mut csv)
+            .unwrap();
+
+        assert_eq!(csv, "a,b,c\n1,2,3\n");
+    }
+
+    #[test]
+    fn gzip_content_length_removed() {
+        init_test_log();
+
+        let mut enc = GzEncoder::new(vec![], Compression::default());
+        enc.write_all(b"hello world hello world").unwrap();
+        let gz = enc.finish().unwrap();
+        let len = gz.len().to_string();
+
+        set_handler(
+            "/gz_len",
+            200,
+            &[("content-encoding", "gzip"), ("content-length", &len)],
+            &gz,
+        );
+
+        let mut res = crate::get("https://example.test/gz_len").call().unwrap();
+
+        assert!(res.headers().get("content-length").is_none());
+        assert_eq!(res.body().content_length(), None);
+        assert_eq!(
+            res.body_mut().read_to_string().unwrap(),
+            "hello world hello world"
+        );
+    }
 }
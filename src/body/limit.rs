@@ -16,6 +16,11 @@ impl<R> LimitReader<R> {
             left: limit,
         }
     }
+
+    #[cfg(feature = "gzip")]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
 }
 
 impl<R: io::Read> io::Read for LimitReader<R> {
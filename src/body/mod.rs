@@ -1,5 +1,9 @@
 use std::fmt;
-use std::io;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::iter;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 pub use build::BodyBuilder;
@@ -11,9 +15,19 @@ use crate::Error;
 use self::limit::LimitReader;
 use self::lossy::LossyUtf8Reader;
 
+mod buffers;
 mod build;
 mod limit;
 mod lossy;
+mod multipart;
+mod trailer;
+
+pub use self::buffers::BodyBuffer;
+pub use self::multipart::{Multipart, MultipartPart};
+
+pub(crate) use self::buffers::BufferPool;
+
+pub(crate) use self::trailer::TrailerParser;
 
 #[cfg(feature = "charset")]
 mod charset;
@@ -21,11 +35,23 @@ mod charset;
 #[cfg(feature = "gzip")]
 mod gzip;
 
+#[cfg(feature = "gzip")]
+pub(crate) use self::gzip::EndGate;
+
+#[cfg(feature = "gzip")]
+mod deflate;
+
 #[cfg(feature = "brotli")]
 mod brotli;
 
-/// Default max body size for read_to_string() and read_to_vec().
-const MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+/// Default max body size for read_to_string(), read_to_vec() and pipelined bodies.
+pub(crate) const MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Default buffer capacity for into_buf_reader().
+const BUF_READER_CAPACITY: usize = 8 * 1024;
+
+/// Max size of each item from chunks().
+const MAX_CHUNK_SIZE: usize = 16 * 1024;
 
 /// A response body returned as [`http::Response<Body>`].
 ///
@@ -60,10 +86,23 @@ enum BodyDataSource {
 
 #[derive(Clone)]
 pub(crate) struct ResponseInfo {
-    content_encoding: ContentEncoding,
+    /// In the order they were applied.
+    content_encodings: Vec<ContentEncoding>,
     mime_type: Option<String>,
     charset: Option<String>,
+    boundary: Option<String>,
     body_mode: BodyMode,
+    buffer_growth: BufferGrowth,
+    buffer_pool: Option<Arc<BufferPool>>,
+}
+
+/// How the buffer for reading a body to memory grows.
+///
+/// See [`ConfigBuilder::body_buffer_capacity()`](crate::config::ConfigBuilder::body_buffer_capacity).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BufferGrowth {
+    pub capacity: usize,
+    pub max_growth: usize,
 }
 
 impl Body {
@@ -82,6 +121,14 @@ impl Body {
         }
     }
 
+    /// A body that was already read into memory.
+    pub(crate) fn buffered(data: Vec<u8>, info: ResponseInfo) -> Self {
+        Body {
+            source: BodyDataSource::Reader(Box::new(io::Cursor::new(data))),
+            info: Arc::new(info),
+        }
+    }
+
     /// The mime-type of the `content-type` header.
     ///
     /// For the below header, we would get `Some("text/plain")`:
@@ -124,6 +171,85 @@ impl Body {
         self.info.charset.as_deref()
     }
 
+    /// The length of the body, as declared by the `content-length` header.
+    ///
+    /// This is `None` when the length isn't known up front, such as for a chunked body.
+    /// It is also `None` when the body is decompressed from a `content-encoding`, since the
+    /// header then tells the compressed length. In that case the `content-length` and
+    /// `content-encoding` headers are removed from the response.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let res = ureq::get("https://example.test/file.bin")
+    ///     .call()?;
+    ///
+    /// if let Some(len) = res.body().content_length() {
+    ///     println!("downloading {} bytes", len);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn content_length(&self) -> Option<u64> {
+        match self.info.body_mode {
+            BodyMode::LengthDelimited(v) if !self.info.is_decoded() => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Whether the body is known to be empty.
+    ///
+    /// This is `true` for responses that never have a body: to a `HEAD` request, and with
+    /// status `1xx`, `204 No Content` or `304 Not Modified`, regardless of any framing
+    /// headers the server sent. It's also `true` for `content-length: 0`. Reading such a
+    /// body returns no data right away, without touching the connection, which is reused.
+    ///
+    /// A chunked or close-delimited body is not known to be empty until it is read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let res = ureq::delete("https://example.test/resource/1")
+    ///     .call()?;
+    ///
+    /// if !res.body().is_empty() {
+    ///     println!("{}", res.into_body().read_to_string()?);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        match (&self.source, self.info.body_mode) {
+            (_, BodyMode::LengthDelimited(0)) => true,
+            // A body from a reader has no body mode unless it's limited.
+            (BodyDataSource::Handler(_), BodyMode::NoBody) => true,
+            _ => false,
+        }
+    }
+
+    /// Trailers sent by the server after a chunked body.
+    ///
+    /// Trailers are only available once the body has been read to the end. Before
+    /// that, and for bodies that are not chunked, this returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut res = ureq::get("https://example.test/chunked")
+    ///     .call()?;
+    ///
+    /// let body = res.body_mut().read_to_string()?;
+    ///
+    /// if let Some(trailers) = res.body().trailers() {
+    ///     println!("checksum: {:?}", trailers.get("x-checksum"));
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn trailers(&self) -> Option<&http::HeaderMap> {
+        match &self.source {
+            BodyDataSource::Handler(v) => v.trailers(),
+            BodyDataSource::Reader(_) => None,
+        }
+    }
+
     /// Handle this body as a shared `impl Read` of the body.
     ///
     /// This is the regular API which goes via [`http::Response::body_mut()`] to get a
@@ -156,6 +282,11 @@ impl Body {
     /// variant consumes the body and turns it into a reader with lifetime `'static`.
     /// The reader can for instance be sent to another thread.
     ///
+    /// The reader is `Send + Sync + 'static`, which means it can be stored in structs,
+    /// boxed as a `Box<dyn Read + Send>`, or handed to anything taking an owned
+    /// [`io::Read`], such as [`io::copy()`] or [`io::BufReader`]. It owns the connection,
+    /// which goes back to the pool once the body is read to the end.
+    ///
     /// * Reader is not limited. To set a limit use [`Body::into_with_config()`].
     ///
     /// ```
@@ -171,10 +302,185 @@ impl Body {
     ///     .read_to_end(&mut bytes)?;
     /// # Ok::<_, ureq::Error>(())
     /// ```
+    ///
+    /// Holding on to the reader beyond the response:
+    ///
+    /// ```
+    /// use std::io::{self, Read};
+    ///
+    /// struct Download {
+    ///     name: String,
+    ///     reader: Box<dyn Read + Send>,
+    /// }
+    ///
+    /// let reader = ureq::get("http://httpbin.org/bytes/100")
+    ///     .call()?
+    ///     .into_body()
+    ///     .into_reader();
+    ///
+    /// let mut download = Download {
+    ///     name: "bytes".to_string(),
+    ///     reader: Box::new(reader),
+    /// };
+    ///
+    /// let copied = io::copy(&mut download.reader, &mut io::sink())?;
+    /// assert_eq!(copied, 100);
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
     pub fn into_reader(self) -> BodyReader<'static> {
         self.into_with_config().reader()
     }
 
+    /// Turn this response into an owned, buffered `impl BufRead` of the body.
+    ///
+    /// This is convenient for line based protocols, where [`BufRead::read_line()`] or
+    /// [`BufRead::read_until()`] are used directly on the body. The buffer is 8KB. To
+    /// use another capacity, see [`BodyWithConfig::buf_reader()`].
+    ///
+    /// * Reader is not limited. To set a limit use [`Body::into_with_config()`].
+    ///
+    /// [`BufRead::read_line()`]: std::io::BufRead::read_line
+    /// [`BufRead::read_until()`]: std::io::BufRead::read_until
+    ///
+    /// ```
+    /// use std::io::BufRead;
+    ///
+    /// let res = ureq::get("http://httpbin.org/robots.txt")
+    ///     .call()?;
+    ///
+    /// let mut reader = res.into_body().into_buf_reader();
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "User-agent: *\n");
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn into_buf_reader(self) -> io::BufReader<BodyReader<'static>> {
+        self.into_with_config().buf_reader(BUF_READER_CAPACITY)
+    }
+
+    /// Turn this response into an iterator of body data, as it arrives.
+    ///
+    /// Each item is what one read from the connection gives, at most 16KB. The
+    /// boundaries have nothing to do with the chunks of `Transfer-Encoding: chunked`.
+    /// The data is decoded the same way as for [`Body::into_reader()`].
+    ///
+    /// An error while reading is returned as the last item.
+    ///
+    /// * Body is not limited. To set a limit use [`Body::into_with_config()`] and
+    ///   read from the [`reader`](BodyWithConfig::reader).
+    ///
+    /// ```
+    /// let res = ureq::get("http://httpbin.org/bytes/100")
+    ///     .call()?;
+    ///
+    /// let mut total = 0;
+    /// for chunk in res.into_body().chunks() {
+    ///     total += chunk?.len();
+    /// }
+    /// assert_eq!(total, 100);
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn chunks(self) -> impl Iterator<Item = Result<Vec<u8>, Error>> + 'static {
+        let mut reader = Some(self.into_reader());
+        let mut buf = vec![0; MAX_CHUNK_SIZE];
+
+        iter::from_fn(move || {
+            let r = reader.as_mut()?;
+            loop {
+                match r.read(&mut buf) {
+                    Ok(0) => {
+                        reader = None;
+                        return None;
+                    }
+                    Ok(n) => return Some(Ok(buf[..n].to_vec())),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        reader = None;
+                        return Some(Err(e.into()));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Turn a multipart response into an iterator of its parts.
+    ///
+    /// For a response like `Content-Type: multipart/mixed; boundary=xyz`, the body
+    /// is split on the boundary. Each [`MultipartPart`] has its own headers and
+    /// reads its own data. The parts are streamed from the connection, nothing is
+    /// buffered beyond what is needed to find the next boundary. Moving on to the
+    /// next part skips whatever is left unread of the previous one.
+    ///
+    /// Fails with [`Error::Multipart`] if the mime type isn't `multipart/*`, or there is
+    /// no `boundary` parameter.
+    ///
+    /// * Body is not limited. To set a limit use [`Body::into_with_config()`].
+    ///
+    /// ```no_run
+    /// use std::io::Read;
+    ///
+    /// let res = ureq::get("https://example.com/batch")
+    ///     .call()?;
+    ///
+    /// for part in res.into_body().into_multipart()? {
+    ///     let mut part = part?;
+    ///     let content_type = part.headers().get("content-type").cloned();
+    ///
+    ///     let mut data = String::new();
+    ///     part.read_to_string(&mut data)?;
+    ///     println!("{:?}: {}", content_type, data);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn into_multipart(self) -> Result<Multipart, Error> {
+        let is_multipart = self
+            .mime_type()
+            .map(|m| m.trim().to_ascii_lowercase().starts_with("multipart/"))
+            .unwrap_or(false);
+
+        if !is_multipart {
+            return Err(Error::Multipart(format!(
+                "not a multipart mime type: {}",
+                self.mime_type().unwrap_or("none")
+            )));
+        }
+
+        let Some(boundary) = self.info.boundary.clone() else {
+            return Err(Error::Multipart("no boundary in content-type".to_string()));
+        };
+
+        Ok(Multipart::new(self.into_reader(), &boundary))
+    }
+
+    /// Turn this response into an owned reader that gunzips the body data.
+    ///
+    /// This is for files that are gzipped at rest, such as a `.gz` download served
+    /// without a `Content-Encoding` header. It's unrelated to the transport level
+    /// `Content-Encoding: gzip`, which is decoded automatically. A body with both is
+    /// decoded twice, first for the `Content-Encoding`, then by this reader.
+    ///
+    /// There is no charset conversion of the decoded data.
+    ///
+    /// * Reader is not limited.
+    ///
+    /// ```no_run
+    /// use std::io::Read;
+    ///
+    /// let res = ureq::get("https://example.com/data.csv.gz")
+    ///     .call()?;
+    ///
+    /// let mut csv = String::new();
+    /// res.into_body()
+    ///     .into_gzip_decoded_reader()
+    ///     .read_to_string(&mut csv)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn into_gzip_decoded_reader(self) -> impl io::Read + 'static {
+        gzip::GzipDecoder::new(self.into_with_config().do_build_bytes(), None)
+    }
+
     /// Read the response as a string.
     ///
     /// * Response is limited to 10MB
@@ -197,6 +503,31 @@ impl Body {
             .read_to_string()
     }
 
+    /// Read the response as a string, transcoding it to UTF-8.
+    ///
+    /// The charset is taken from a byte order mark, or the `Content-Type` header.
+    /// Without either, the body is assumed to be UTF-8. Invalid chars are replaced
+    /// by the unicode replacement char. An unknown charset label results in
+    /// [`Error::UnknownCharset`].
+    ///
+    /// * Response is limited to `max_bytes` before transcoding.
+    ///
+    /// To use another fallback than UTF-8, see [`BodyWithConfig::read_to_utf8_string()`].
+    ///
+    /// ```
+    /// let res = ureq::get("http://httpbin.org/encoding/utf8")
+    ///     .call()?;
+    ///
+    /// let s = res.into_body().into_utf8_string(1024 * 1024)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "charset")]
+    pub fn into_utf8_string(self, max_bytes: u64) -> Result<String, Error> {
+        self.into_with_config()
+            .limit(max_bytes)
+            .read_to_utf8_string("utf-8")
+    }
+
     /// Read the response to a vec.
     ///
     /// * Response is limited to 10MB.
@@ -217,6 +548,65 @@ impl Body {
             .read_to_vec()
     }
 
+    /// Read the response into an existing vec, reusing its allocation.
+    ///
+    /// The data is appended to `buf`, and the number of bytes read is returned. A client
+    /// making many small requests can clear and reuse the same vec for every body, and
+    /// so avoid an allocation per response, which [`read_to_vec()`](Self::read_to_vec)
+    /// can't. The connection buffers are held by pooled connections and reused already.
+    ///
+    /// * Response is limited to 10MB.
+    ///
+    /// To change this default use [`Body::with_config()`].
+    /// ```
+    /// let agent = ureq::agent();
+    /// let mut buf = Vec::with_capacity(1024);
+    ///
+    /// for _ in 0..3 {
+    ///     buf.clear();
+    ///     agent.get("http://httpbin.org/bytes/100")
+    ///         .call()?
+    ///         .body_mut()
+    ///         .read_to_vec_into(&mut buf)?;
+    ///     assert_eq!(buf.len(), 100);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn read_to_vec_into(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        self.with_config()
+            .limit(MAX_BODY_SIZE)
+            .read_to_vec_into(buf)
+    }
+
+    /// Read the response into a buffer of the agent.
+    ///
+    /// With [`ConfigBuilder::body_buffer_pool()`](crate::config::ConfigBuilder::body_buffer_pool),
+    /// the buffer comes from the agent and goes back to it when the [`BodyBuffer`] is
+    /// dropped. A buffer that has grown to fit earlier bodies is then read into without
+    /// an allocation. Without a pool, this allocates like [`read_to_vec()`](Self::read_to_vec).
+    ///
+    /// * Response is limited to 10MB.
+    ///
+    /// To change this default use [`Body::with_config()`].
+    /// ```
+    /// let agent: ureq::Agent = ureq::Agent::config_builder()
+    ///     .body_buffer_pool(4)
+    ///     .build()
+    ///     .into();
+    ///
+    /// for _ in 0..3 {
+    ///     let buf = agent.get("http://httpbin.org/bytes/100")
+    ///         .call()?
+    ///         .body_mut()
+    ///         .read_to_buffer()?;
+    ///     assert_eq!(buf.len(), 100);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn read_to_buffer(&mut self) -> Result<BodyBuffer, Error> {
+        self.with_config().limit(MAX_BODY_SIZE).read_to_buffer()
+    }
+
     /// Read the response from JSON.
     ///
     /// * Response is limited to 10MB.
@@ -254,6 +644,117 @@ impl Body {
         Ok(value)
     }
 
+    /// Read the response as JSON, whatever the content-type says.
+    ///
+    /// * Response is limited to `max_bytes`.
+    ///
+    /// Some servers send JSON as `text/plain`, `application/javascript` or without
+    /// content-type at all, sometimes with a leading UTF-8 byte order mark. A strict
+    /// client checks [`Body::mime_type()`] for `application/json` (or a `+json` type)
+    /// before parsing. This doesn't, it skips any byte order mark and parses whatever
+    /// is there, which makes it an [`Error::Json`] when the body is not JSON after all.
+    ///
+    /// Like [`Body::read_json()`], which doesn't look at the content-type either, but
+    /// fails on a byte order mark.
+    ///
+    /// ```
+    /// let json: serde_json::Value = ureq::get("http://httpbin.org/get")
+    ///     .call()?
+    ///     .into_body()
+    ///     .read_json_lenient(1024 * 1024)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn read_json_lenient<T: serde::de::DeserializeOwned>(
+        self,
+        max_bytes: u64,
+    ) -> Result<T, Error> {
+        let data = self.into_with_config().limit(max_bytes).read_to_vec()?;
+        let json = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&data);
+        let value: T = serde_json::from_slice(json)?;
+        Ok(value)
+    }
+
+    /// Read and throw away the rest of the body.
+    ///
+    /// Use this when the headers show the body is not wanted. Reading the body to the
+    /// end means the connection can be returned to the pool and reused for the next
+    /// request, which is not possible if the body is just dropped half-read.
+    ///
+    /// The body data is not decompressed or charset converted.
+    ///
+    /// ```
+    /// let res = ureq::get("http://httpbin.org/bytes/100")
+    ///     .call()?;
+    ///
+    /// if res.body().mime_type() != Some("text/plain") {
+    ///     res.into_body().discard()?;
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn discard(self) -> Result<(), Error> {
+        let mut source: BodySourceRef = self.source.into();
+        io::copy(&mut source, &mut io::sink())?;
+        Ok(())
+    }
+
+    /// Write the body to a file.
+    ///
+    /// The file is created, or truncated if it exists. Returns the number of bytes written.
+    ///
+    /// The file gets the body data after decompression, but without any charset
+    /// conversion, regardless of mime-type.
+    ///
+    /// If reading the body or writing the file fails, the partially written file is removed.
+    ///
+    /// * Body is not limited. To set a limit use [`Body::into_with_config()`].
+    ///
+    /// ```no_run
+    /// let bytes = ureq::get("http://httpbin.org/bytes/100")
+    ///     .call()?
+    ///     .into_body()
+    ///     .save_to_path("download.bin")?;
+    ///
+    /// assert_eq!(bytes, 100);
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn save_to_path(self, path: impl AsRef<Path>) -> Result<u64, Error> {
+        self.into_with_config().save_to_path(path)
+    }
+
+    /// Write the body to an anonymous temporary file.
+    ///
+    /// Returns the open file, seeked to the start, for repeated or random access reads
+    /// of bodies too big to hold in memory.
+    ///
+    /// The file is created in [`std::env::temp_dir()`] and has no name that outlives it:
+    /// it is deleted when the returned [`File`] is dropped, and on failure. On unix the
+    /// file is unlinked straight after creation. On windows it is opened with
+    /// `FILE_FLAG_DELETE_ON_CLOSE`.
+    ///
+    /// The file gets the body data after decompression, but without any charset
+    /// conversion, regardless of mime-type.
+    ///
+    /// * Body is not limited. To set a limit use [`Body::into_with_config()`].
+    ///
+    /// ```no_run
+    /// use std::io::{Read, Seek, SeekFrom};
+    ///
+    /// let mut file = ureq::get("http://httpbin.org/bytes/100")
+    ///     .call()?
+    ///     .into_body()
+    ///     .into_temp_file()?;
+    ///
+    /// file.seek(SeekFrom::Start(50))?;
+    /// let mut rest = Vec::new();
+    /// file.read_to_end(&mut rest)?;
+    /// assert_eq!(rest.len(), 50);
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn into_temp_file(self) -> Result<File, Error> {
+        self.into_with_config().into_temp_file()
+    }
+
     /// Read the body data with configuration.
     ///
     /// This borrows the body which gives easier use with [`http::Response::body_mut()`].
@@ -357,6 +858,13 @@ impl<'a> BodyWithConfig<'a> {
         )
     }
 
+    /// Reader that only decompresses, without charset conversion.
+    fn do_build_bytes(self) -> ContentDecoder<LimitReader<BodySourceRef<'a>>> {
+        let mut body_mode = self.info.body_mode;
+        let reader = LimitReader::new(self.handler, self.limit);
+        content_decoder(reader, &self.info, &mut body_mode)
+    }
+
     /// Creates a reader.
     ///
     /// The reader is either shared or owned, depending on `with_config` or `into_with_config`.
@@ -390,48 +898,263 @@ impl<'a> BodyWithConfig<'a> {
         self.do_build()
     }
 
+    /// Creates a buffered reader with the given buffer capacity.
+    ///
+    /// ```
+    /// use std::io::BufRead;
+    ///
+    /// let reader = ureq::get("https://httpbin.org/get")
+    ///     .call()?
+    ///     .into_body()
+    ///     .into_with_config()
+    ///     .limit(1024)
+    ///     .buf_reader(256);
+    ///
+    /// for line in reader.lines() {
+    ///     println!("{}", line?);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn buf_reader(self, capacity: usize) -> io::BufReader<BodyReader<'a>> {
+        io::BufReader::with_capacity(capacity, self.do_build())
+    }
+
     /// Read into string.
     pub fn read_to_string(self) -> Result<String, Error> {
-        use std::io::Read;
+        let growth = self.info.buffer_growth;
         let mut reader = self.do_build();
-        let mut buf = String::new();
-        reader.read_to_string(&mut buf)?;
-        Ok(buf)
+        let mut buf = Vec::new();
+        read_to_end_growing(&mut reader, &mut buf, growth)?;
+        let s = String::from_utf8(buf).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        })?;
+        Ok(s)
+    }
+
+    /// Read into string, transcoding from the response charset to UTF-8.
+    ///
+    /// The charset is taken from a byte order mark, or the `Content-Type` header. The
+    /// `fallback_charset` label is used when neither is present. Unlike
+    /// [`BodyWithConfig::read_to_string()`], this transcodes regardless of the mime-type.
+    ///
+    /// ```
+    /// let s = ureq::get("http://httpbin.org/encoding/utf8")
+    ///     .call()?
+    ///     .into_body()
+    ///     .into_with_config()
+    ///     .limit(1024 * 1024)
+    ///     .read_to_utf8_string("iso-8859-1")?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "charset")]
+    pub fn read_to_utf8_string(self, fallback_charset: &str) -> Result<String, Error> {
+        use encoding_rs::Encoding;
+
+        let label = self.info.charset.as_deref().unwrap_or(fallback_charset);
+        let Some(encoding) = Encoding::for_label(label.as_bytes()) else {
+            return Err(Error::UnknownCharset(label.to_string()));
+        };
+
+        let growth = self.info.buffer_growth;
+        let mut reader = self.do_build_bytes();
+
+        let mut buf = Vec::new();
+        read_to_end_growing(&mut reader, &mut buf, growth)?;
+
+        // decode() prefers a BOM over the given encoding.
+        let (s, _, _) = encoding.decode(&buf);
+        Ok(s.into_owned())
     }
 
     /// Read into vector.
     pub fn read_to_vec(self) -> Result<Vec<u8>, Error> {
-        use std::io::Read;
+        let growth = self.info.buffer_growth;
         let mut reader = self.do_build();
         let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
+        read_to_end_growing(&mut reader, &mut buf, growth)?;
         Ok(buf)
     }
 
-    /// Read JSON body.
-    #[cfg(feature = "json")]
-    pub fn read_json<T: serde::de::DeserializeOwned>(self) -> Result<T, Error> {
-        let reader = self.do_build();
+    /// Read into an existing vector, appending to it.
+    ///
+    /// See [`Body::read_to_vec_into()`].
+    pub fn read_to_vec_into(self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let growth = self.info.buffer_growth;
+        let mut reader = self.do_build();
+        let n = read_to_end_growing(&mut reader, buf, growth)?;
+        Ok(n)
+    }
+
+    /// Read into a buffer of the agent.
+    ///
+    /// See [`Body::read_to_buffer()`].
+    pub fn read_to_buffer(self) -> Result<BodyBuffer, Error> {
+        let mut buf = BodyBuffer::new(self.info.buffer_pool.clone());
+        self.read_to_vec_into(buf.buf_mut())?;
+        Ok(buf)
+    }
+
+    /// Read JSON body.
+    #[cfg(feature = "json")]
+    pub fn read_json<T: serde::de::DeserializeOwned>(self) -> Result<T, Error> {
+        let reader = self.do_build();
         let value: T = serde_json::from_reader(reader)?;
         Ok(value)
     }
+
+    /// Write the body to a file, removing the file again on failure.
+    ///
+    /// See [`Body::save_to_path()`].
+    pub fn save_to_path(self, path: impl AsRef<Path>) -> Result<u64, Error> {
+        let path = path.as_ref();
+
+        let mut reader = self.do_build_bytes();
+
+        let mut file = File::create(path)?;
+
+        let result = io::copy(&mut reader, &mut file).and_then(|n| file.sync_all().map(|_| n));
+
+        match result {
+            Ok(n) => Ok(n),
+            Err(e) => {
+                drop(file);
+                if let Err(e) = fs::remove_file(path) {
+                    debug!("Failed to remove partial file {:?}: {}", path, e);
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Write the body to an anonymous temporary file, deleted when dropped.
+    ///
+    /// See [`Body::into_temp_file()`].
+    pub fn into_temp_file(self) -> Result<File, Error> {
+        let mut reader = self.do_build_bytes();
+
+        let mut file = create_temp_file()?;
+
+        io::copy(&mut reader, &mut file)?;
+        file.seek(SeekFrom::Start(0))?;
+
+        Ok(file)
+    }
+}
+
+/// Like [`Read::read_to_end()`], but growing `buf` as configured.
+fn read_to_end_growing(
+    reader: &mut impl Read,
+    buf: &mut Vec<u8>,
+    growth: BufferGrowth,
+) -> io::Result<usize> {
+    let start = buf.len();
+    let mut filled = start;
+
+    if buf.capacity() == start {
+        buf.reserve_exact(growth.capacity.max(1));
+    }
+
+    // Zero the spare capacity once, and read into it.
+    buf.resize(buf.capacity(), 0);
+
+    let result = loop {
+        if filled == buf.len() {
+            let increment = buf.len().min(growth.max_growth).max(1);
+            buf.reserve_exact(increment);
+            buf.resize(buf.capacity(), 0);
+        }
+
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break Ok(filled - start),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => break Err(e),
+        }
+    };
+
+    buf.truncate(filled);
+
+    result
+}
+
+/// Create a file in the temp dir that is removed from the file system once closed.
+fn create_temp_file() -> io::Result<File> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let dir = std::env::temp_dir();
+
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!(".ureq-{}-{}.tmp", std::process::id(), n));
+
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+
+        // Other users must not be able to open the file before it is removed.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x0400_0000;
+            options.custom_flags(FILE_FLAG_DELETE_ON_CLOSE);
+        }
+
+        let file = match options.open(&path) {
+            Ok(v) => v,
+            // Left over by an earlier process with the same id.
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+
+        #[cfg(not(windows))]
+        if let Err(e) = fs::remove_file(&path) {
+            // Don't leave the file behind. It's still empty, nothing leaks.
+            drop(file);
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+
+        return Ok(file);
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 enum ContentEncoding {
-    None,
     Gzip,
+    Deflate,
     Brotli,
     Unknown,
 }
 
+impl Default for BufferGrowth {
+    fn default() -> Self {
+        BufferGrowth {
+            capacity: 8 * 1024,
+            max_growth: 1024 * 1024,
+        }
+    }
+}
+
 impl ResponseInfo {
     pub fn new(headers: &http::HeaderMap, body_mode: BodyMode) -> Self {
-        let content_encoding = headers
-            .get("content-encoding")
-            .and_then(|v| v.to_str().ok())
+        // Several encodings can be listed, in one header or several.
+        let content_encodings = headers
+            .get_all("content-encoding")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
             .map(ContentEncoding::from)
-            .unwrap_or(ContentEncoding::None);
+            .collect();
 
         let (mime_type, charset) = headers
             .get("content-type")
@@ -439,14 +1162,49 @@ impl ResponseInfo {
             .map(split_content_type)
             .unwrap_or((None, None));
 
+        let boundary = headers
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .and_then(content_type_boundary);
+
         ResponseInfo {
-            content_encoding,
+            content_encodings,
             mime_type,
             charset,
+            boundary,
             body_mode,
+            buffer_growth: BufferGrowth::default(),
+            buffer_pool: None,
         }
     }
 
+    /// Use the configured growth of the buffer for reading the body to memory.
+    pub fn with_buffer_growth(mut self, capacity: usize, max_growth: usize) -> Self {
+        self.buffer_growth = BufferGrowth {
+            capacity,
+            max_growth,
+        };
+        self
+    }
+
+    /// Use the buffers of the agent for reading the body to a [`BodyBuffer`].
+    pub fn with_buffer_pool(mut self, pool: Option<Arc<BufferPool>>) -> Self {
+        self.buffer_pool = pool;
+        self
+    }
+
+    /// Whether the body data is decompressed when read.
+    ///
+    /// With several encodings, either all are undone, or none is.
+    pub(crate) fn is_decoded(&self) -> bool {
+        !self.content_encodings.is_empty()
+            && self.content_encodings.iter().all(|e| match e {
+                ContentEncoding::Unknown => false,
+                ContentEncoding::Gzip | ContentEncoding::Deflate => cfg!(feature = "gzip"),
+                ContentEncoding::Brotli => cfg!(feature = "brotli"),
+            })
+    }
+
     /// Whether the mime type indicats text.
     fn is_text(&self) -> bool {
         self.mime_type
@@ -476,12 +1234,31 @@ fn split_content_type(content_type: &str) -> (Option<String>, Option<String>) {
     (Some(mime_type.to_string()), charset)
 }
 
+fn content_type_boundary(content_type: &str) -> Option<String> {
+    // Content-Type: multipart/mixed; boundary="simple boundary"
+    let value = content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim())
+    })?;
+
+    let unquoted = value
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(value);
+
+    (!unquoted.is_empty()).then(|| unquoted.to_string())
+}
+
 /// A reader of the response data.
 ///
 /// 1. If `Transfer-Encoding: chunked`, the returned reader will unchunk it
 ///    and any `Content-Length` header is ignored.
-/// 2. If `Content-Encoding: gzip` (or `br`) and the corresponding feature
-///    flag is enabled (**gzip** and **brotli**), decompresses the body data.
+/// 2. If `Content-Encoding: gzip` (or `deflate`, `br`) and the corresponding feature
+///    flag is enabled (**gzip** and **brotli**), decompresses the body data. Stacked
+///    encodings, like `deflate, gzip`, are undone in reverse order. If any of them
+///    isn't supported, the body is left as is.
 /// 3. Given a header like `Content-Type: text/plain; charset=ISO-8859-1`
 ///    and the **charset** feature enabled, will translate the body to utf-8.
 ///    This mechanic need two components a mime-type starting `text/` and
@@ -534,25 +1311,7 @@ impl<'a> BodyReader<'a> {
         // in a proxy situation.
         let mut outgoing_body_mode = incoming_body_mode;
 
-        let reader = match info.content_encoding {
-            ContentEncoding::None | ContentEncoding::Unknown => ContentDecoder::PassThrough(reader),
-            #[cfg(feature = "gzip")]
-            ContentEncoding::Gzip => {
-                debug!("Decoding gzip");
-                outgoing_body_mode = BodyMode::Chunked;
-                ContentDecoder::Gzip(Box::new(gzip::GzipDecoder::new(reader)))
-            }
-            #[cfg(not(feature = "gzip"))]
-            ContentEncoding::Gzip => ContentDecoder::PassThrough(reader),
-            #[cfg(feature = "brotli")]
-            ContentEncoding::Brotli => {
-                debug!("Decoding brotli");
-                outgoing_body_mode = BodyMode::Chunked;
-                ContentDecoder::Brotli(Box::new(brotli::BrotliDecoder::new(reader)))
-            }
-            #[cfg(not(feature = "brotli"))]
-            ContentEncoding::Brotli => ContentDecoder::PassThrough(reader),
-        };
+        let reader = content_decoder(reader, info, &mut outgoing_body_mode);
 
         let reader = if info.is_text() {
             charset_decoder(
@@ -582,6 +1341,54 @@ impl<'a> BodyReader<'a> {
     }
 }
 
+fn content_decoder<'a>(
+    #[allow(unused_mut)] mut reader: LimitReader<BodySourceRef<'a>>,
+    info: &ResponseInfo,
+    body_mode: &mut BodyMode,
+) -> ContentDecoder<LimitReader<BodySourceRef<'a>>> {
+    if !info.is_decoded() {
+        return ContentDecoder::PassThrough(reader);
+    }
+
+    #[cfg(feature = "gzip")]
+    let mut end_gate = reader.get_mut().end_gate(info);
+
+    let mut decoder = ContentDecoder::PassThrough(reader);
+
+    *body_mode = BodyMode::Chunked;
+
+    // The last applied encoding is undone first.
+    for encoding in info.content_encodings.iter().rev() {
+        decoder = match encoding {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => {
+                debug!("Decoding gzip");
+                ContentDecoder::Gzip(Box::new(gzip::GzipDecoder::new(decoder, end_gate.take())))
+            }
+            #[cfg(not(feature = "gzip"))]
+            ContentEncoding::Gzip => decoder,
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Deflate => {
+                debug!("Decoding deflate");
+                ContentDecoder::Deflate(Box::new(deflate::DeflateDecoder::new(decoder)))
+            }
+            #[cfg(not(feature = "gzip"))]
+            ContentEncoding::Deflate => decoder,
+            #[cfg(feature = "brotli")]
+            ContentEncoding::Brotli => {
+                debug!("Decoding brotli");
+                ContentDecoder::Brotli(Box::new(brotli::BrotliDecoder::new(decoder)))
+            }
+            #[cfg(not(feature = "brotli"))]
+            ContentEncoding::Brotli => decoder,
+            // Ruled out by is_decoded().
+            ContentEncoding::Unknown => decoder,
+        };
+    }
+
+    decoder
+}
+
 #[allow(unused)]
 fn charset_decoder<R: io::Read>(
     reader: R,
@@ -649,11 +1456,14 @@ impl<R: io::Read> io::Read for CharsetDecoder<R> {
     }
 }
 
+/// Decoders wrap each other for stacked encodings, such as `deflate, gzip`.
 enum ContentDecoder<R: io::Read> {
     #[cfg(feature = "gzip")]
-    Gzip(Box<gzip::GzipDecoder<R>>),
+    Gzip(Box<gzip::GzipDecoder<ContentDecoder<R>>>),
+    #[cfg(feature = "gzip")]
+    Deflate(Box<deflate::DeflateDecoder<ContentDecoder<R>>>),
     #[cfg(feature = "brotli")]
-    Brotli(Box<brotli::BrotliDecoder<R>>),
+    Brotli(Box<brotli::BrotliDecoder<ContentDecoder<R>>>),
     PassThrough(R),
 }
 
@@ -662,6 +1472,8 @@ impl<R: io::Read> io::Read for ContentDecoder<R> {
         match self {
             #[cfg(feature = "gzip")]
             ContentDecoder::Gzip(v) => v.read(buf),
+            #[cfg(feature = "gzip")]
+            ContentDecoder::Deflate(v) => v.read(buf),
             #[cfg(feature = "brotli")]
             ContentDecoder::Brotli(v) => v.read(buf),
             ContentDecoder::PassThrough(v) => v.read(buf),
@@ -679,6 +1491,7 @@ impl From<&str> for ContentEncoding {
     fn from(s: &str) -> Self {
         match s {
             "gzip" => ContentEncoding::Gzip,
+            "deflate" => ContentEncoding::Deflate,
             "br" => ContentEncoding::Brotli,
             _ => {
                 info!("Unknown content-encoding: {}", s);
@@ -712,6 +1525,26 @@ pub(crate) enum BodySourceRef<'a> {
     ReaderOwned(Box<dyn io::Read + Send + Sync>),
 }
 
+impl<'a> BodySourceRef<'a> {
+    /// Gate for the end of a body that is only gzip encoded.
+    ///
+    /// See [`gzip::EndGate`]. Stacked encodings are not gated.
+    #[cfg(feature = "gzip")]
+    fn end_gate(&mut self, info: &ResponseInfo) -> Option<gzip::EndGate> {
+        let handler = match self {
+            BodySourceRef::HandlerShared(v) => &mut **v,
+            BodySourceRef::HandlerOwned(v) => v,
+            _ => return None,
+        };
+
+        if !matches!(info.content_encodings[..], [ContentEncoding::Gzip]) {
+            return None;
+        }
+
+        Some(handler.gate_end())
+    }
+}
+
 impl<'a> io::Read for BodySourceRef<'a> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match self {
@@ -729,7 +1562,226 @@ mod test {
 
     use crate::test::init_test_log;
     use crate::transport::set_handler;
-    use crate::Error;
+    use crate::{Agent, Error};
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn stacked_content_encodings() {
+        use std::io::Write;
+
+        use flate2::write::{GzEncoder, ZlibEncoder};
+        use flate2::Compression;
+
+        init_test_log();
+
+        // deflate first, then gzip on top.
+        let mut zlib = ZlibEncoder::new(vec![], Compression::default());
+        zlib.write_all(b"twice encoded").unwrap();
+        let mut gz = GzEncoder::new(vec![], Compression::default());
+        gz.write_all(&zlib.finish().unwrap()).unwrap();
+        let data = gz.finish().unwrap();
+        let len = data.len().to_string();
+
+        set_handler(
+            "/stacked",
+            200,
+            &[
+                ("content-encoding", "deflate, gzip"),
+                ("content-length", &len),
+            ],
+            &data,
+        );
+
+        let mut res = crate::get("https://my.test/stacked").call().unwrap();
+        assert!(res.headers().get("content-encoding").is_none());
+        assert!(res.headers().get("content-length").is_none());
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "twice encoded");
+
+        // An unknown encoding in the chain leaves the body and headers as is.
+        set_handler(
+            "/unknown_stacked",
+            200,
+            &[("content-encoding", "zstd, gzip"), ("content-length", &len)],
+            &data,
+        );
+
+        let mut res = crate::get("https://my.test/unknown_stacked")
+            .call()
+            .unwrap();
+        assert_eq!(res.headers()["content-encoding"], "zstd, gzip");
+        assert_eq!(res.body().content_length(), Some(data.len() as u64));
+        assert_eq!(res.body_mut().read_to_vec().unwrap(), data);
+    }
+
+    #[test]
+    fn truncated_body_is_error() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "1000")], &[b'x'; 400]);
+
+        let mut res = crate::get("https://my.test/get").call().unwrap();
+        let err = res.body_mut().read_to_vec().unwrap_err();
+
+        let Error::Io(e) = err else {
+            panic!("expected io error, got {:?}", err);
+        };
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            e.to_string(),
+            "Peer disconnected after 400 of 1000 body bytes"
+        );
+    }
+
+    #[test]
+    fn extra_response_data_is_error() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "2")], b"okGARBAGE");
+
+        let mut res = crate::get("https://my.test/get").call().unwrap();
+        let err = res.body_mut().read_to_string().unwrap_err();
+        assert!(matches!(err, Error::ExtraResponseData(7)));
+    }
+
+    #[test]
+    fn extra_response_data_allowed() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "2")], b"okGARBAGE");
+
+        let agent: Agent = Agent::config_builder()
+            .allow_extra_response_data(true)
+            .build()
+            .into();
+
+        let mut res = agent.get("https://my.test/get").call().unwrap();
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "ok");
+        assert_eq!(agent.pool_count(), 0);
+    }
+
+    #[test]
+    fn content_length_declared() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "2")], b"ok");
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        assert_eq!(res.body().content_length(), Some(2));
+        assert_eq!(res.headers()["content-length"], "2");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn read_json_lenient_text_plain() {
+        init_test_log();
+        let json = b"\xEF\xBB\xBF{\"hello\":\"world\"}";
+        set_handler("/get", 200, &[("content-type", "text/plain")], json);
+
+        let value: serde_json::Value = crate::get("https://my.test/get")
+            .call()
+            .unwrap()
+            .into_body()
+            .read_json_lenient(1024)
+            .unwrap();
+
+        assert_eq!(value["hello"], "world");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn read_json_lenient_limit() {
+        init_test_log();
+        set_handler("/get", 200, &[], b"[1, 2, 3]");
+
+        let err = crate::get("https://my.test/get")
+            .call()
+            .unwrap()
+            .into_body()
+            .read_json_lenient::<Vec<u32>>(4)
+            .unwrap_err();
+
+        assert!(matches!(err, Error::BodyExceedsLimit(4)));
+    }
+
+    #[test]
+    #[cfg(feature = "digest")]
+    fn verify_content_digest() {
+        init_test_log();
+        let digest = "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:";
+        // A connection has the handlers set when it was opened, /tampered needs a new one.
+        let headers = [
+            ("content-length", "11"),
+            ("content-digest", digest),
+            ("connection", "close"),
+        ];
+        set_handler("/digest", 200, &headers, b"hello world");
+
+        let agent: Agent = Agent::config_builder()
+            .verify_content_digest(true)
+            .build()
+            .into();
+
+        let body = agent
+            .get("https://my.test/digest")
+            .call()
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+        assert_eq!(body, "hello world");
+
+        set_handler("/tampered", 200, &headers, b"hello there");
+
+        let err = agent
+            .get("https://my.test/tampered")
+            .call()
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap_err();
+        assert!(matches!(err, Error::ContentDigest("sha-256")));
+    }
+
+    #[test]
+    fn max_response_size_by_content_length() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "11")], b"hello world");
+
+        let agent: Agent = Agent::config_builder()
+            .max_response_size(Some(10))
+            .build()
+            .into();
+
+        let err = agent.get("https://my.test/get").call().unwrap_err();
+        assert!(matches!(err, Error::BodyExceedsLimit(10)));
+
+        // Closed, not drained for reuse.
+        assert_eq!(agent.pool_count(), 0);
+    }
+
+    #[test]
+    fn max_response_size_chunked() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[("transfer-encoding", "chunked")],
+            b"6\r\nhello \r\n5\r\nworld\r\n0\r\n\r\n",
+        );
+
+        let agent: Agent = Agent::config_builder()
+            .max_response_size(Some(10))
+            .build()
+            .into();
+
+        let mut res = agent.get("https://my.test/get").call().unwrap();
+        let err = res.body_mut().read_to_string().unwrap_err();
+        assert!(matches!(err, Error::BodyExceedsLimit(10)));
+
+        let agent: Agent = Agent::config_builder()
+            .max_response_size(Some(11))
+            .build()
+            .into();
+
+        let mut res = agent.get("https://my.test/get").call().unwrap();
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "hello world");
+    }
 
     #[test]
     fn content_type_without_charset() {
@@ -779,6 +1831,280 @@ mod test {
         assert_eq!(b, "hello world!!!");
     }
 
+    #[test]
+    fn chunked_transfer_trailers() {
+        init_test_log();
+
+        let s = "3\r\n\
+            hel\r\n\
+            0\r\n\
+            x-checksum: abc\r\n\
+            \r\n";
+
+        set_handler(
+            "/get",
+            200,
+            &[("transfer-encoding", "chunked"), ("trailer", "x-checksum")],
+            s.as_bytes(),
+        );
+
+        let mut res = crate::get("https://my.test/get").call().unwrap();
+        assert!(res.body().trailers().is_none());
+
+        let b = res.body_mut().read_to_string().unwrap();
+        assert_eq!(b, "hel");
+
+        let trailers = res.body().trailers().unwrap();
+        assert_eq!(trailers.get("x-checksum").unwrap(), "abc");
+    }
+
+    #[test]
+    fn owned_reader_is_send_sync_static() {
+        fn is_send_sync_static<T: Send + Sync + 'static>(_: &T) {}
+
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "2")], b"ok");
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let reader = res.into_body().into_reader();
+        is_send_sync_static(&reader);
+
+        let mut buffered = std::io::BufReader::new(reader);
+        let mut s = String::new();
+        std::io::Read::read_to_string(&mut buffered, &mut s).unwrap();
+        assert_eq!(s, "ok");
+    }
+
+    #[test]
+    fn chunks_of_body() {
+        init_test_log();
+        let body: Vec<u8> = (0..40_000).map(|i| i as u8).collect();
+        set_handler("/get", 200, &[("content-length", "40000")], &body);
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let chunks: Vec<Vec<u8>> = res.into_body().chunks().map(|c| c.unwrap()).collect();
+
+        assert!(chunks.len() >= 3);
+        assert!(chunks.iter().all(|c| !c.is_empty() && c.len() <= 16 * 1024));
+        assert_eq!(chunks.concat(), body);
+    }
+
+    #[test]
+    fn chunks_end_after_error() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "100")], b"short");
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let mut chunks = res.into_body().chunks();
+
+        assert_eq!(chunks.next().unwrap().unwrap(), b"short");
+        assert!(chunks.next().unwrap().is_err());
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    fn buf_reader_read_line() {
+        use std::io::BufRead;
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "12")], b"first\nsecond");
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let mut reader = res.into_body().into_buf_reader();
+
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "first\n");
+
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "second");
+
+        line.clear();
+        assert_eq!(reader.read_line(&mut line).unwrap(), 0);
+    }
+
+    #[test]
+    fn save_to_path() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "5")], b"hello");
+
+        let path = std::env::temp_dir().join(format!("ureq-save-{}", std::process::id()));
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let n = res.into_body().save_to_path(&path).unwrap();
+        assert_eq!(n, 5);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_to_path_removes_partial_file() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "10")], b"hello");
+
+        let path = std::env::temp_dir().join(format!("ureq-partial-{}", std::process::id()));
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let err = res.into_body().save_to_path(&path).unwrap_err();
+        assert_eq!(err.into_io().kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn into_temp_file() {
+        use std::io::{Read, Seek, SeekFrom};
+
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "11")], b"hello world");
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let mut file = res.into_body().into_temp_file().unwrap();
+
+        let mut s = String::new();
+        file.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "hello world");
+
+        file.seek(SeekFrom::Start(6)).unwrap();
+        s.clear();
+        file.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "world");
+    }
+
+    #[test]
+    fn read_to_vec_into_reuses_buffer() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "5")], b"hello");
+
+        let mut buf = Vec::with_capacity(1024);
+        let ptr = buf.as_ptr();
+
+        for _ in 0..2 {
+            buf.clear();
+            let mut res = crate::get("https://my.test/get").call().unwrap();
+            let n = res.body_mut().read_to_vec_into(&mut buf).unwrap();
+            assert_eq!(n, 5);
+            assert_eq!(buf, b"hello");
+        }
+
+        assert_eq!(buf.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn read_to_buffer_reuses_agent_buffer() {
+        init_test_log();
+        set_handler("/get", 200, &[("content-length", "5")], b"hello");
+
+        let agent: Agent = Agent::config_builder().body_buffer_pool(1).build().into();
+
+        let mut ptrs = vec![];
+        for _ in 0..3 {
+            let mut res = agent.get("https://my.test/get").call().unwrap();
+            let buf = res.body_mut().read_to_buffer().unwrap();
+            assert_eq!(&*buf, b"hello");
+            ptrs.push(buf.as_ptr());
+        }
+
+        assert!(ptrs.iter().all(|p| *p == ptrs[0]));
+    }
+
+    #[test]
+    fn body_buffer_growth() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[("transfer-encoding", "chunked")],
+            b"5\r\nhello\r\n0\r\n\r\n",
+        );
+
+        let agent: Agent = Agent::config_builder()
+            .body_buffer_capacity(4)
+            .body_buffer_max_growth(1)
+            .build()
+            .into();
+
+        let mut res = agent.get("https://my.test/get").call().unwrap();
+        let data = res.body_mut().read_to_vec().unwrap();
+        assert_eq!(data, b"hello");
+        // 4, then growing by 1.
+        assert!(data.capacity() <= 6);
+    }
+
+    #[test]
+    fn buffer_growth_capped() {
+        use super::{read_to_end_growing, BufferGrowth};
+
+        let data = vec![7_u8; 100];
+        let growth = BufferGrowth {
+            capacity: 10,
+            max_growth: 16,
+        };
+
+        let mut buf = Vec::new();
+        let n = read_to_end_growing(&mut &data[..], &mut buf, growth).unwrap();
+        assert_eq!(n, 100);
+        assert_eq!(buf, data);
+        // 10, 20, 36, 52, 68, 84, 100, 116
+        assert!(buf.capacity() <= 116);
+
+        // Appends to what is there.
+        let n = read_to_end_growing(&mut &b"more"[..], &mut buf, growth).unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(&buf[100..], b"more");
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn into_utf8_string_latin1() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[
+                ("content-type", "text/plain; charset=iso-8859-1"),
+                ("content-length", "4"),
+            ],
+            b"caf\xe9",
+        );
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let s = res.into_body().into_utf8_string(1024).unwrap();
+        assert_eq!(s, "caf\u{e9}");
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn into_utf8_string_bom() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[("content-type", "text/plain"), ("content-length", "4")],
+            b"\xff\xfeh\x00",
+        );
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let s = res.into_body().into_utf8_string(1024).unwrap();
+        assert_eq!(s, "h");
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn into_utf8_string_unknown_charset() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[("content-type", "text/plain; charset=x-nope")],
+            b"",
+        );
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let err = res.into_body().into_utf8_string(1024).unwrap_err();
+        assert!(matches!(err, Error::UnknownCharset(_)));
+    }
+
     #[test]
     fn large_response_header() {
         init_test_log();
@@ -795,4 +2121,49 @@ mod test {
         let err = crate::get("https://my.test/get").call().unwrap_err();
         assert!(matches!(err, Error::LargeResponseHeader(_, _)));
     }
+
+    #[test]
+    fn discard_returns_connection_to_pool() {
+        init_test_log();
+        let agent = Agent::new_with_defaults();
+
+        let res = agent.get("https://my.test/bytes/100").call().unwrap();
+        assert_eq!(agent.pool_count(), 0);
+
+        res.into_body().discard().unwrap();
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn drop_drains_small_body() {
+        init_test_log();
+        let agent = Agent::new_with_defaults();
+
+        let res = agent.get("https://my.test/bytes/100").call().unwrap();
+        drop(res);
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn drop_closes_when_drain_disabled() {
+        init_test_log();
+        let agent: Agent = Agent::config_builder().max_drain_on_drop(0).build().into();
+
+        let res = agent.get("https://my.test/bytes/100").call().unwrap();
+        drop(res);
+        assert_eq!(agent.pool_count(), 0);
+    }
+
+    #[test]
+    fn drop_does_not_wait_for_stalled_body() {
+        init_test_log();
+        let agent = Agent::new_with_defaults();
+
+        let res = agent.get("https://my.test/stalled-body").call().unwrap();
+
+        let start = std::time::Instant::now();
+        drop(res);
+        assert!(start.elapsed() < std::time::Duration::from_secs(3));
+        assert_eq!(agent.pool_count(), 0);
+    }
 }
@@ -0,0 +1,518 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::Error;
+
+use super::BodyReader;
+
+/// Max size of the header section of a single part.
+const MAX_HEADERS_SIZE: usize = 64 * 1024;
+
+/// Size of each read from the body.
+const READ_SIZE: usize = 8 * 1024;
+
+/// Iterator of the parts of a `multipart/*` response body.
+///
+/// Obtained from [`Body::into_multipart()`](crate::Body::into_multipart). The parts
+/// are read from the connection as the iterator advances. A malformed body is
+/// returned as an [`Error::Multipart`], which also ends the iteration.
+pub struct Multipart {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// One part of a [`Multipart`] body.
+///
+/// Reads the data of the part, which ends at the next boundary. Once the iterator
+/// moves on to the next part, this one reads no more data.
+pub struct MultipartPart {
+    headers: HeaderMap,
+    index: usize,
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    reader: BodyReader<'static>,
+    /// CRLF, "--" and the boundary.
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    pos: usize,
+    state: State,
+    /// The part being read, starting from 1. The preamble is 0.
+    index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Reading the preamble, or the data of a part, up to the next delimiter.
+    Data,
+    /// Right after a delimiter, before the rest of the boundary line.
+    Delimiter,
+    /// After the close delimiter, or an error.
+    Done,
+}
+
+impl Multipart {
+    pub(crate) fn new(reader: BodyReader<'static>, boundary: &str) -> Self {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+
+        let inner = Inner {
+            reader,
+            delimiter,
+            // The first delimiter can start the body, without a CRLF in front.
+            buf: b"\r\n".to_vec(),
+            pos: 0,
+            state: State::Data,
+            index: 0,
+        };
+
+        Multipart {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+}
+
+impl Iterator for Multipart {
+    type Item = Result<MultipartPart, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.next_part() {
+            Ok(Some(headers)) => Some(Ok(MultipartPart {
+                headers,
+                index: inner.index,
+                inner: self.inner.clone(),
+            })),
+            Ok(None) => None,
+            Err(e) => {
+                inner.state = State::Done;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+impl MultipartPart {
+    /// The headers of this part.
+    ///
+    /// These are only the headers at the start of the part, such as `Content-Type`.
+    /// The headers of the response are on the [`http::Response`].
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+impl Read for MultipartPart {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.index != self.index || inner.state != State::Data {
+            return Ok(0);
+        }
+
+        let result = inner.read_data(buf);
+
+        if result.is_err() {
+            inner.state = State::Done;
+        }
+
+        result
+    }
+}
+
+impl Inner {
+    fn next_part(&mut self) -> io::Result<Option<HeaderMap>> {
+        // Skip what is left of the preamble or the previous part.
+        let mut scratch = [0; 1024];
+        while self.state == State::Data {
+            self.read_data(&mut scratch)?;
+        }
+
+        if self.state == State::Done {
+            return Ok(None);
+        }
+
+        if self.starts_with(b"--")? {
+            // The close delimiter, which can end the body. Whatever follows is the
+            // epilogue, which is ignored.
+            self.state = State::Done;
+            return Ok(None);
+        }
+
+        let line = self.read_line(MAX_HEADERS_SIZE)?;
+
+        // Transport padding is allowed between the boundary and the CRLF.
+        if !line.iter().all(|b| *b == b' ' || *b == b'\t') {
+            return Err(error("invalid boundary line"));
+        }
+
+        let mut headers = HeaderMap::new();
+        let mut size = 0;
+
+        loop {
+            let line = self.read_line(MAX_HEADERS_SIZE.saturating_sub(size))?;
+            size += line.len() + 2;
+
+            if line.is_empty() {
+                break;
+            }
+
+            let (name, value) = parse_header(&line)?;
+            headers.append(name, value);
+        }
+
+        self.state = State::Data;
+        self.index += 1;
+
+        Ok(Some(headers))
+    }
+
+    /// Read data up to the next delimiter.
+    ///
+    /// Gives 0 once the delimiter is reached, and moves past it.
+    fn read_data(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let data = &self.buf[self.pos..];
+
+            let available = match find(data, &self.delimiter) {
+                Some(0) => {
+                    self.pos += self.delimiter.len();
+                    self.state = State::Delimiter;
+                    return Ok(0);
+                }
+                Some(i) => i,
+                // The end of the buffer could be the start of a delimiter.
+                None => data.len() - partial_suffix(data, &self.delimiter),
+            };
+
+            if available > 0 {
+                let n = available.min(out.len());
+                out[..n].copy_from_slice(&data[..n]);
+                self.pos += n;
+                return Ok(n);
+            }
+
+            if !self.fill()? {
+                return Err(error("body ended before the closing boundary"));
+            }
+        }
+    }
+
+    /// Whether the unread data starts with `prefix`, reading more of the body if needed.
+    fn starts_with(&mut self, prefix: &[u8]) -> io::Result<bool> {
+        while self.buf.len() - self.pos < prefix.len() {
+            if !self.fill()? {
+                break;
+            }
+        }
+
+        Ok(self.buf[self.pos..].starts_with(prefix))
+    }
+
+    /// Read a line, without the CRLF.
+    fn read_line(&mut self, limit: usize) -> io::Result<Vec<u8>> {
+        loop {
+            let data = &self.buf[self.pos..];
+
+            if let Some(i) = find(data, b"\r\n") {
+                let line = data[..i].to_vec();
+                self.pos += i + 2;
+                return Ok(line);
+            }
+
+            if data.len() > limit {
+                return Err(error("part headers too large"));
+            }
+
+            if !self.fill()? {
+                return Err(error("body ended in the part headers"));
+            }
+        }
+    }
+
+    /// Read more of the body into the buffer. False at the end of the body.
+    fn fill(&mut self) -> io::Result<bool> {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+
+        let n = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        };
+
+        self.buf.truncate(len + n);
+
+        Ok(n > 0)
+    }
+}
+
+fn parse_header(line: &[u8]) -> io::Result<(HeaderName, HeaderValue)> {
+    let colon = line
+        .iter()
+        .position(|b| *b == b':')
+        .ok_or_else(|| error("part header without colon"))?;
+
+    let name =
+        HeaderName::from_bytes(&line[..colon]).map_err(|_| error("invalid part header name"))?;
+    let value = HeaderValue::from_bytes(trim(&line[colon + 1..]))
+        .map_err(|_| error("invalid part header value"))?;
+
+    Ok((name, value))
+}
+
+fn trim(mut v: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = v {
+        v = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = v {
+        v = rest;
+    }
+    v
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Length of the longest end of `data` that is the start of `delimiter`.
+fn partial_suffix(data: &[u8], delimiter: &[u8]) -> usize {
+    let max = data.len().min(delimiter.len() - 1);
+    (1..=max)
+        .rev()
+        .find(|n| data.ends_with(&delimiter[..*n]))
+        .unwrap_or(0)
+}
+
+fn error(msg: &str) -> io::Error {
+    Error::Multipart(msg.to_string()).into_io()
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for MultipartPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultipartPart")
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use crate::{Body, Error};
+
+    fn multipart(data: &'static str) -> Vec<(String, String)> {
+        let body = Body::builder()
+            .mime_type("multipart/mixed")
+            .boundary("simple boundary")
+            .data(data);
+
+        body.into_multipart()
+            .unwrap()
+            .map(|part| {
+                let mut part = part.unwrap();
+                let content_type = part
+                    .headers()
+                    .get("content-type")
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                let mut data = String::new();
+                part.read_to_string(&mut data).unwrap();
+                (content_type, data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parts_with_headers() {
+        let parts = multipart(
+            "This is the preamble.\r\n\
+            --simple boundary\r\n\
+            \r\n\
+            implicitly typed plain text\r\n\
+            --simple boundary  \r\n\
+            Content-type: text/plain; charset=us-ascii\r\n\
+            \r\n\
+            explicitly typed plain text.\r\n\
+            \r\n\
+            --simple boundary--\r\n\
+            This is the epilogue.\r\n",
+        );
+
+        assert_eq!(
+            parts,
+            vec![
+                (String::new(), "implicitly typed plain text".to_string()),
+                (
+                    "text/plain; charset=us-ascii".to_string(),
+                    "explicitly typed plain text.\r\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn boundary_starts_body() {
+        let parts = multipart(
+            "--simple boundary\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            --simple boundar\r\n\
+            --simple boundary--",
+        );
+
+        assert_eq!(
+            parts,
+            vec![("text/plain".to_string(), "--simple boundar".to_string())]
+        );
+    }
+
+    #[test]
+    fn streamed_one_byte_at_a_time() {
+        struct OneByte(&'static [u8]);
+
+        impl Read for OneByte {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let Some((first, rest)) = self.0.split_first() else {
+                    return Ok(0);
+                };
+                buf[0] = *first;
+                self.0 = rest;
+                Ok(1)
+            }
+        }
+
+        let body = Body::builder()
+            .mime_type("multipart/mixed")
+            .boundary("xyz")
+            .reader(OneByte(
+                b"--xyz\r\n\r\nfirst\r\n--x\r\n--xyz\r\n\r\nsecond\r\n--xyz--",
+            ));
+
+        let mut parts = body.into_multipart().unwrap();
+
+        let mut first = parts.next().unwrap().unwrap();
+        let mut data = String::new();
+        first.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "first\r\n--x");
+
+        let mut second = parts.next().unwrap().unwrap();
+        let mut data = String::new();
+        second.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "second");
+
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn unread_part_is_skipped() {
+        let body = Body::builder()
+            .mime_type("multipart/mixed")
+            .boundary("xyz")
+            .data("--xyz\r\n\r\nfirst part\r\n--xyz\r\n\r\nsecond\r\n--xyz--\r\n");
+
+        let mut parts = body.into_multipart().unwrap();
+
+        let mut first = parts.next().unwrap().unwrap();
+        let mut second = parts.next().unwrap().unwrap();
+
+        // The first part ended once the iterator moved past it.
+        let mut data = String::new();
+        first.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "");
+
+        second.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "second");
+
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn missing_close_delimiter() {
+        let body = Body::builder()
+            .mime_type("multipart/mixed")
+            .boundary("xyz")
+            .data("--xyz\r\n\r\ntruncated");
+
+        let mut parts = body.into_multipart().unwrap();
+
+        let mut part = parts.next().unwrap().unwrap();
+        let err = part.read_to_end(&mut vec![]).unwrap_err();
+        assert!(matches!(Error::from(err), Error::Multipart(_)));
+
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn not_multipart() {
+        let body = Body::builder()
+            .mime_type("text/plain")
+            .boundary("xyz")
+            .data("--xyz--");
+        let err = body.into_multipart().unwrap_err();
+        assert!(matches!(err, Error::Multipart(_)));
+
+        let body = Body::builder().mime_type("multipart/mixed").data("--xyz--");
+        let err = body.into_multipart().unwrap_err();
+        assert!(matches!(err, Error::Multipart(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "_test")]
+    fn multipart_response() {
+        use crate::test::init_test_log;
+        use crate::transport::set_handler;
+        use crate::Agent;
+
+        init_test_log();
+        set_handler(
+            "/multipart",
+            200,
+            &[
+                ("content-type", "multipart/mixed; boundary=\"a b\""),
+                ("content-length", "54"),
+            ],
+            b"--a b\r\nContent-Type: application/json\r\n\r\n{}\r\n--a b--\r\n",
+        );
+
+        let agent = Agent::new_with_defaults();
+
+        let res = agent.get("https://my.test/multipart").call().unwrap();
+        let mut parts = res.into_body().into_multipart().unwrap();
+
+        let mut part = parts.next().unwrap().unwrap();
+        assert_eq!(part.headers()["content-type"], "application/json");
+        let mut data = String::new();
+        part.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "{}");
+
+        assert!(parts.next().is_none());
+        drop(part);
+        drop(parts);
+
+        assert_eq!(agent.pool_count(), 1);
+    }
+}
@@ -0,0 +1,179 @@
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+/// Max length of a single trailer line. Longer lines are ignored.
+const MAX_LINE: usize = 8 * 1024;
+
+/// Follows the chunked framing of the raw response input to pick up the trailers.
+///
+/// hoot decodes the chunked body, but doesn't hand us the trailer section that can
+/// follow the last chunk. This parser is fed the exact input hoot consumed, which
+/// means it doesn't need to validate the framing, only keep track of it.
+pub(crate) struct TrailerParser {
+    state: State,
+    size: u64,
+    line: Vec<u8>,
+    trailers: HeaderMap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Reading the hex chunk size.
+    Size,
+    /// Skipping chunk extensions until end of the size line.
+    Extension,
+    /// Skipping chunk data.
+    Data,
+    /// Skipping the CRLF after chunk data.
+    DataEnd,
+    /// Reading trailer lines after the last chunk.
+    Trailer,
+    /// Trailer section ended.
+    Done,
+}
+
+impl TrailerParser {
+    pub fn new() -> Self {
+        TrailerParser {
+            state: State::Size,
+            size: 0,
+            line: Vec::new(),
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    pub fn feed(&mut self, mut input: &[u8]) {
+        while let Some((&b, rest)) = input.split_first() {
+            match self.state {
+                State::Size => match b {
+                    b'\n' => self.size_line_end(),
+                    _ => match (b as char).to_digit(16) {
+                        Some(d) => self.size = self.size.saturating_mul(16) + d as u64,
+                        None => self.state = State::Extension,
+                    },
+                },
+                State::Extension => {
+                    if b == b'\n' {
+                        self.size_line_end();
+                    }
+                }
+                State::Data => {
+                    let n = self.size.min(input.len() as u64) as usize;
+                    self.size -= n as u64;
+                    if self.size == 0 {
+                        self.state = State::DataEnd;
+                    }
+                    input = &input[n..];
+                    continue;
+                }
+                State::DataEnd => {
+                    if b == b'\n' {
+                        self.state = State::Size;
+                    }
+                }
+                State::Trailer => {
+                    if b == b'\n' {
+                        self.trailer_line_end();
+                    } else if self.line.len() <= MAX_LINE {
+                        self.line.push(b);
+                    }
+                }
+                State::Done => return,
+            }
+            input = rest;
+        }
+    }
+
+    fn size_line_end(&mut self) {
+        self.state = if self.size == 0 {
+            State::Trailer
+        } else {
+            State::Data
+        };
+    }
+
+    fn trailer_line_end(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+
+        if line.is_empty() {
+            self.state = State::Done;
+            return;
+        }
+
+        if line.len() > MAX_LINE {
+            return;
+        }
+
+        let Some(colon) = line.iter().position(|c| *c == b':') else {
+            return;
+        };
+
+        let name = HeaderName::from_bytes(trim(&line[..colon]));
+        let value = HeaderValue::from_bytes(trim(&line[colon + 1..]));
+
+        if let (Ok(name), Ok(value)) = (name, value) {
+            self.trailers.append(name, value);
+        }
+    }
+
+    /// The trailers, provided the end of the trailer section was reached.
+    pub fn into_trailers(self) -> Option<HeaderMap> {
+        (self.state == State::Done).then_some(self.trailers)
+    }
+}
+
+fn trim(mut v: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = v {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        v = rest;
+    }
+    while let [rest @ .., last] = v {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        v = rest;
+    }
+    v
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Option<HeaderMap> {
+        let mut p = TrailerParser::new();
+        for c in chunks {
+            p.feed(c);
+        }
+        p.into_trailers()
+    }
+
+    #[test]
+    fn no_trailers() {
+        let t = parse(&[b"5\r\nhello\r\n0\r\n\r\n"]).unwrap();
+        assert!(t.is_empty());
+    }
+
+    #[test]
+    fn trailers_after_last_chunk() {
+        let t = parse(&[b"5;ext=1\r\nhello\r\n0\r\nX-Sum: abc\r\nx-status:  ok \r\n\r\n"]);
+        let t = t.unwrap();
+        assert_eq!(t.get("x-sum").unwrap(), "abc");
+        assert_eq!(t.get("x-status").unwrap(), "ok");
+    }
+
+    #[test]
+    fn trailers_split_input() {
+        let input = b"a\r\n0123456789\r\n0\r\nx-sum: abc\r\n\r\n";
+        let chunks: Vec<&[u8]> = input.chunks(1).collect();
+        let t = parse(&chunks).unwrap();
+        assert_eq!(t.get("x-sum").unwrap(), "abc");
+    }
+
+    #[test]
+    fn incomplete() {
+        assert!(parse(&[b"5\r\nhello\r\n0\r\nx-sum: abc\r\n"]).is_none());
+    }
+}
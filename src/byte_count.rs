@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counts the bytes sent and received by all connections of an agent.
+///
+/// Relaxed atomics are enough, since the counts are only ever added to and read, and
+/// nothing else is synchronized through them.
+#[derive(Debug, Default)]
+pub(crate) struct ByteCounter {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl ByteCounter {
+    pub fn add_sent(&self, amount: usize) {
+        self.sent.fetch_add(amount as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_received(&self, amount: usize) {
+        self.received.fetch_add(amount as u64, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> ByteCounts {
+        ByteCounts {
+            sent: self.sent.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset to zero, returning the counts before.
+    pub fn take(&self) -> ByteCounts {
+        ByteCounts {
+            sent: self.sent.swap(0, Ordering::Relaxed),
+            received: self.received.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Bytes sent and received by an agent.
+///
+/// See [`Agent::byte_counts()`](crate::Agent::byte_counts).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteCounts {
+    sent: u64,
+    received: u64,
+}
+
+impl ByteCounts {
+    /// Bytes sent, request heads and bodies.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Bytes received, response heads and bodies.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn add_and_take() {
+        let c = ByteCounter::default();
+        c.add_sent(10);
+        c.add_sent(5);
+        c.add_received(100);
+
+        let counts = c.counts();
+        assert_eq!(counts.sent(), 15);
+        assert_eq!(counts.received(), 100);
+
+        assert_eq!(c.take(), counts);
+        assert_eq!(c.counts(), ByteCounts::default());
+    }
+}
@@ -1,14 +1,17 @@
 //! Agent configuration
 
 use std::fmt;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use hoot::client::flow::RedirectAuthHeaders;
-use http::Uri;
+use http::{HeaderMap, HeaderName, Request, Response, Uri};
 
+use crate::auth::Credentials;
 use crate::middleware::{Middleware, MiddlewareChain};
 use crate::resolver::IpFamily;
-use crate::{Agent, AsSendBody, Proxy, RequestBuilder};
+use crate::{Agent, AsSendBody, Error, Proxy, RequestBuilder};
 
 #[cfg(feature = "_tls")]
 use crate::tls::TlsConfig;
@@ -33,6 +36,11 @@ use crate::tls::TlsConfig;
 /// let agent = Agent::new_with_config(config);
 /// ```
 ///
+/// ## Settings from elsewhere
+///
+/// For settings read from a config file, or ported from another HTTP client, see
+/// [`AgentConfig`].
+///
 ///
 /// # Request level config
 ///
@@ -91,25 +99,64 @@ pub struct Config {
     pub(crate) http_status_as_error: bool,
     pub(crate) https_only: bool,
     pub(crate) ip_family: IpFamily,
+    pub(crate) header_casing: Arc<Vec<String>>,
+    pub(crate) capture_request_head: Option<RequestHeadCapture>,
+    pub(crate) redact_captured_headers: bool,
+    pub(crate) request_signer: Option<RequestSigner>,
+    pub(crate) request_id: Option<RequestIdHeader>,
+    pub(crate) connect_port: Option<u16>,
+    pub(crate) resolve_overrides: Arc<Vec<(String, u16, SocketAddr)>>,
     #[cfg(feature = "_tls")]
     pub(crate) tls_config: TlsConfig,
     pub(crate) proxy: Option<Proxy>,
     pub(crate) no_delay: bool,
     pub(crate) max_redirects: u32,
     pub(crate) redirect_auth_headers: RedirectAuthHeaders,
+    pub(crate) redirect_post_as_get: bool,
+    pub(crate) max_cross_host_redirects: Option<u32>,
+    pub(crate) redirect_check: Option<RedirectCheck>,
+    pub(crate) redirect_statuses: Arc<Vec<u16>>,
+    pub(crate) auth_credentials: Option<Credentials>,
     pub(crate) user_agent: Option<String>,
     pub(crate) timeouts: Timeouts,
     pub(crate) max_response_header_size: usize,
+    pub(crate) max_response_size: Option<u64>,
     pub(crate) input_buffer_size: usize,
     pub(crate) output_buffer_size: usize,
+    pub(crate) body_buffer_capacity: usize,
+    pub(crate) body_buffer_max_growth: usize,
+    pub(crate) body_buffer_pool: usize,
+    pub(crate) max_coalesce_body_size: usize,
+    pub(crate) max_requests_per_second: Option<f64>,
+    pub(crate) max_concurrent_requests_per_host: Option<usize>,
+    pub(crate) concurrency_slow_start: bool,
     pub(crate) max_idle_connections: usize,
     pub(crate) max_idle_connections_per_host: usize,
     pub(crate) max_idle_age: Duration,
+    pub(crate) retry_stale_connection: bool,
+    pub(crate) probe_pooled_connections: bool,
+    pub(crate) retry_predicate: Option<RetryPredicate>,
+    pub(crate) max_drain_on_drop: usize,
+    pub(crate) max_drain_time_on_drop: Duration,
+    pub(crate) allow_extra_response_data: bool,
+    pub(crate) allow_ambiguous_framing: bool,
+    pub(crate) count_bytes: bool,
+    #[cfg(feature = "digest")]
+    pub(crate) send_content_digest: bool,
+    #[cfg(feature = "digest")]
+    pub(crate) verify_content_digest: bool,
     pub(crate) middleware: MiddlewareChain,
 
     // Techically not config, but here to pass as argument from
     // RequestBuilder::force_send_body() to run()
     pub(crate) force_send_body: bool,
+
+    // Also not config, passed from RequestBuilder::fresh_connection() to run()
+    pub(crate) fresh_connection: bool,
+
+    // Also not config, passed from RequestBuilder::sniff_content_type() to run()
+    #[cfg(feature = "content-sniff")]
+    pub(crate) sniff_content_type: bool,
 }
 
 impl Config {
@@ -213,6 +260,220 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
+    /// Header names to send with exactly this casing.
+    ///
+    /// Header names are case insensitive, and ureq sends them all in lowercase. Some
+    /// non-compliant servers only recognize a header with a specific casing. For the
+    /// names given here, the header is instead sent as written, such as `X-ApiKey`.
+    /// This applies both to headers set on the request and headers ureq adds.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .header_casing(&["X-ApiKey"])
+    ///     .build()
+    ///     .into();
+    ///
+    /// let res = agent.get("http://httpbin.org/get")
+    ///     // sent as X-ApiKey
+    ///     .header("x-apikey", "secret")
+    ///     .call()?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    ///
+    /// Defaults to no names, i.e. all lowercase.
+    pub fn header_casing(mut self, v: &[&str]) -> Self {
+        self.config().header_casing = Arc::new(v.iter().map(|n| n.to_string()).collect());
+        self
+    }
+
+    /// Observe each request head exactly as written to the connection.
+    ///
+    /// The function is called with the raw bytes of the request line and headers, once
+    /// for every request sent, including redirects and retries. The request body is not
+    /// included. This is meant for debugging why a server rejects a request.
+    ///
+    /// The values of sensitive headers are replaced with `***`, see
+    /// [`redact_captured_headers()`](Self::redact_captured_headers).
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .capture_request_head(|head| {
+    ///         eprintln!("{}", String::from_utf8_lossy(head));
+    ///     })
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to not capturing.
+    pub fn capture_request_head(mut self, v: impl Fn(&[u8]) + Send + Sync + 'static) -> Self {
+        self.config().capture_request_head = Some(RequestHeadCapture(Arc::new(v)));
+        self
+    }
+
+    /// Whether to redact sensitive headers in captured request heads.
+    ///
+    /// Redacts the values of `authorization`, `proxy-authorization` and `cookie` in heads
+    /// given to [`capture_request_head()`](Self::capture_request_head).
+    ///
+    /// Defaults to `true`.
+    pub fn redact_captured_headers(mut self, v: bool) -> Self {
+        self.config().redact_captured_headers = v;
+        self
+    }
+
+    /// Compute headers, such as a signature, over the request as it is sent.
+    ///
+    /// The function is called right before each request is sent, including redirects and
+    /// retries, once ureq has added its own headers such as `content-length`, `cookie` and
+    /// `user-agent`. It gets the final method, uri and headers, and the body when it is
+    /// held in memory (such as `&[u8]` or `String`), or is empty. For a streamed body it
+    /// gets `None`, and must sign without the payload. The `host` header is not among the
+    /// headers, it is written from the uri.
+    ///
+    /// The returned headers are added to the request. Returning an error aborts the
+    /// request with that error.
+    ///
+    /// This makes schemes like AWS SigV4 possible, which sign over the canonical request.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    /// use ureq::http::{HeaderMap, HeaderValue};
+    /// # fn sign(_: &str) -> &'static str { "c2lnbmF0dXJl" }
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .sign_request(|request, body| {
+    ///         let canonical = format!(
+    ///             "{} {} {}",
+    ///             request.method(),
+    ///             request.uri().path(),
+    ///             body.map(|b| b.len()).unwrap_or(0)
+    ///         );
+    ///         let mut headers = HeaderMap::new();
+    ///         headers.insert("x-signature", HeaderValue::from_static(sign(&canonical)));
+    ///         Ok(headers)
+    ///     })
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to no signing.
+    pub fn sign_request(
+        mut self,
+        v: impl Fn(&Request<()>, Option<&[u8]>) -> Result<HeaderMap, Error> + Send + Sync + 'static,
+    ) -> Self {
+        self.config().request_signer = Some(RequestSigner(Arc::new(v)));
+        self
+    }
+
+    /// Send a generated request id header with each request.
+    ///
+    /// Each request gets a new id of 32 random hex digits in the `header`, such as
+    /// `x-request-id`, which makes the request possible to find in the logs of the
+    /// servers along the way. A request that already has the header keeps it, which is
+    /// how an id is propagated from an incoming request. Redirects and retries of a
+    /// request send the same id.
+    ///
+    /// The id is inserted as a [`RequestId`](crate::RequestId) extension on the response.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    /// use ureq::http::HeaderName;
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .request_id(HeaderName::from_static("x-request-id"))
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to no request id.
+    pub fn request_id(mut self, header: HeaderName) -> Self {
+        self.config().request_id = Some(RequestIdHeader {
+            header,
+            generate: None,
+        });
+        self
+    }
+
+    /// Send a request id header with each request, with ids from a function.
+    ///
+    /// Like [`request_id()`](Self::request_id), but the function gives the header
+    /// value, for instance to send a W3C `traceparent` of the current trace. The function
+    /// is called once per request. A value that isn't a valid header value fails the
+    /// request with [`Error::Http`].
+    ///
+    /// ```
+    /// use ureq::Agent;
+    /// use ureq::http::HeaderName;
+    /// # fn current_trace_id() -> &'static str { "4bf92f3577b34da6a3ce929d0e0e4736" }
+    /// # fn new_span_id() -> &'static str { "00f067aa0ba902b7" }
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .request_id_with(HeaderName::from_static("traceparent"), || {
+    ///         format!("00-{}-{}-01", current_trace_id(), new_span_id())
+    ///     })
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to no request id.
+    pub fn request_id_with(
+        mut self,
+        header: HeaderName,
+        generate: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.config().request_id = Some(RequestIdHeader {
+            header,
+            generate: Some(Arc::new(generate)),
+        });
+        self
+    }
+
+    /// Port to connect to, overriding the port of the request uri.
+    ///
+    /// The uri is still used as is for the `Host` header and TLS. This is useful when
+    /// tunneling, or testing against a server on an alternate port. Without an override,
+    /// the port is taken from the uri, or is the default for the scheme: 80 for `http`
+    /// and 443 for `https`.
+    ///
+    /// The override has no effect when connecting via an HTTP proxy, since the
+    /// proxy is what opens the connection to the target.
+    ///
+    /// Defaults to `None`.
+    pub fn connect_port(mut self, v: Option<u16>) -> Self {
+        self.config().connect_port = v;
+        self
+    }
+
+    /// Resolve a host and port to a fixed address, bypassing DNS.
+    ///
+    /// This works like curl's `--resolve`. Calling it several times adds more mappings,
+    /// and several addresses can be given for the same host and port. The host is
+    /// matched ignoring ASCII case, and the port is the one of the uri, or the default
+    /// for the scheme. Hosts without a mapping are looked up as usual.
+    ///
+    /// ```
+    /// use std::net::SocketAddr;
+    ///
+    /// let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    ///
+    /// let config = ureq::Agent::config_builder()
+    ///     .resolve("example.test", 80, addr)
+    ///     .build();
+    /// ```
+    ///
+    /// The mappings are used by the [`DefaultResolver`](crate::resolver::DefaultResolver).
+    ///
+    /// Defaults to no mappings.
+    pub fn resolve(mut self, host: &str, port: u16, addr: SocketAddr) -> Self {
+        let overrides = Arc::make_mut(&mut self.config().resolve_overrides);
+        overrides.push((host.to_string(), port, addr));
+        self
+    }
+
     /// Config for TLS.
     ///
     /// This config is generic for all TLS connectors.
@@ -243,6 +504,18 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
 
     /// The max number of redirects to follow before giving up
     ///
+    /// With 0, redirects are not followed at all and the 3xx response is returned as
+    /// is. [`ResponseExt::location()`](crate::ResponseExt::location) tells where it leads.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .max_redirects(0)
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
     /// Defaults to 10
     pub fn max_redirects(mut self, v: u32) -> Self {
         self.config().max_redirects = v;
@@ -260,6 +533,131 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
+    /// Whether a POST redirected with 301 or 302 becomes a GET
+    ///
+    /// The method used for the redirected request depends on the status:
+    ///
+    /// | Status   | Method of redirected request                                 |
+    /// |----------|--------------------------------------------------------------|
+    /// | 301, 302 | GET for a POST when this setting is `true`, otherwise unchanged |
+    /// | 303      | GET, except HEAD which stays HEAD                            |
+    /// | 307, 308 | Unchanged                                                    |
+    ///
+    /// A changed method drops the request body. When the method is unchanged, the body is
+    /// sent again if it is held in memory (such as `&[u8]` or `String`). A body from a
+    /// reader can't be sent again, and ureq fails with [`Error::RedirectFailed`].
+    ///
+    /// `true` is how browsers behave.
+    ///
+    /// Defaults to `true`.
+    ///
+    /// [`Error::RedirectFailed`]: crate::Error::RedirectFailed
+    pub fn redirect_post_as_get(mut self, v: bool) -> Self {
+        self.config().redirect_post_as_get = v;
+        self
+    }
+
+    /// The max number of followed redirects that go to another host
+    ///
+    /// This is counted separately from [`max_redirects()`](Self::max_redirects), for
+    /// redirects where the host or port differs from the previous request. Exceeding
+    /// the limit fails with [`Error::RedirectDenied`], since following redirects to
+    /// unexpected hosts is how open redirects are abused. With `Some(0)`, only
+    /// redirects on the same host are followed.
+    ///
+    /// `None` means no limit other than `max_redirects()`.
+    ///
+    /// Defaults to `None`
+    ///
+    /// [`Error::RedirectDenied`]: crate::Error::RedirectDenied
+    pub fn max_cross_host_redirects(mut self, v: Option<u32>) -> Self {
+        self.config().max_cross_host_redirects = v;
+        self
+    }
+
+    /// Check each redirect before it is followed
+    ///
+    /// The function is given the uri of the current request and the redirect target.
+    /// Returning an error stops following the redirect, and the error is the result
+    /// of the call.
+    ///
+    /// ```
+    /// use ureq::{Agent, Error};
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .redirect_check(|_from, to| {
+    ///         if to.scheme_str() != Some("https") {
+    ///             return Err(Error::RedirectDenied(format!("not https: {}", to)));
+    ///         }
+    ///         Ok(())
+    ///     })
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to no check.
+    pub fn redirect_check(
+        mut self,
+        v: impl Fn(&Uri, &Uri) -> Result<(), Error> + Send + Sync + 'static,
+    ) -> Self {
+        self.config().redirect_check = Some(RedirectCheck(Arc::new(v)));
+        self
+    }
+
+    /// The 3xx status codes that are followed as redirects
+    ///
+    /// A response with any other status is returned to the caller as is, with the
+    /// `Location` header left to inspect. This is for APIs that use, say, `302` to point
+    /// at a resource rather than to redirect.
+    ///
+    /// Only `301`, `302`, `303`, `307` and `308` can be followed, other codes in the
+    /// list are ignored.
+    ///
+    /// ```
+    /// use ureq::Agent;
+    ///
+    /// // Never follow 302.
+    /// let agent: Agent = Agent::config_builder()
+    ///     .redirect_statuses(&[301, 303, 307, 308])
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to `[301, 302, 303, 307, 308]`.
+    pub fn redirect_statuses(mut self, v: &[u16]) -> Self {
+        self.config().redirect_statuses = Arc::new(v.to_vec());
+        self
+    }
+
+    /// Credentials to answer `401 Unauthorized` challenges
+    ///
+    /// When a response is `401` with a `WWW-Authenticate` header asking for `Digest` or
+    /// `Basic` auth, the request is retried once with an `Authorization` header computed
+    /// from these credentials. Digest is preferred when the server offers both. Digest
+    /// requires the **digest-auth** feature.
+    ///
+    /// No retry happens when the request already has an `Authorization` header, or
+    /// when the request body can't be sent again (see
+    /// [`redirect_post_as_get()`](Self::redirect_post_as_get) for which bodies can).
+    ///
+    /// Credentials set on the agent are offered to every host the agent talks to. Prefer
+    /// setting them on the request level config.
+    ///
+    /// ```
+    /// use ureq::Credentials;
+    ///
+    /// let mut request = ureq::get("http://httpbin.org/digest-auth/auth/user/pass")
+    ///     .config()
+    ///     .auth_credentials(Some(Credentials::new("user", "pass")))
+    ///     .build();
+    /// ```
+    ///
+    /// Defaults to `None`.
+    pub fn auth_credentials(mut self, v: Option<Credentials>) -> Self {
+        self.config().auth_credentials = v;
+        self
+    }
+
     /// Value to use for the `User-Agent` field.
     ///
     /// This can be overridden by setting a `user-agent` header on the request
@@ -282,6 +680,22 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
+    /// Max size of a response body, as sent by the server.
+    ///
+    /// A response with a larger `Content-Length` fails straight away with
+    /// [`Error::BodyExceedsLimit`](crate::Error::BodyExceedsLimit), without reading
+    /// the body. Bodies without a declared length (chunked, or ended by closing the
+    /// connection) fail with the same error once more than this has been read.
+    ///
+    /// The size is before any decompression. To limit the decompressed size, use
+    /// [`BodyWithConfig::limit()`](crate::BodyWithConfig::limit).
+    ///
+    /// Defaults to `None`.
+    pub fn max_response_size(mut self, v: Option<u64>) -> Self {
+        self.config().max_response_size = v;
+        self
+    }
+
     /// Default size of the input buffer
     ///
     /// The default connectors use this setting.
@@ -302,6 +716,131 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
+    /// Initial capacity of the buffer a body of unknown length is read into.
+    ///
+    /// Used when reading a body to memory, such as with
+    /// [`Body::read_to_vec()`](crate::Body::read_to_vec) or
+    /// [`Body::read_to_string()`](crate::Body::read_to_string). Once full, the buffer
+    /// grows by doubling, but by no more than
+    /// [`body_buffer_max_growth()`](Self::body_buffer_max_growth) at a time.
+    ///
+    /// A small capacity saves memory when reading many small bodies concurrently, a
+    /// large one saves reallocations for big bodies.
+    ///
+    /// Defaults to 8kb.
+    pub fn body_buffer_capacity(mut self, v: usize) -> Self {
+        self.config().body_buffer_capacity = v;
+        self
+    }
+
+    /// Max amount to grow the buffer a body is read into by at a time.
+    ///
+    /// Doubling the buffer for a large body means a spike of up to twice the body size
+    /// in memory. Capping the growth trades that spike for more reallocations. See
+    /// [`body_buffer_capacity()`](Self::body_buffer_capacity).
+    ///
+    /// Defaults to 1MB.
+    pub fn body_buffer_max_growth(mut self, v: usize) -> Self {
+        self.config().body_buffer_max_growth = v;
+        self
+    }
+
+    /// Number of body buffers the agent keeps for reuse.
+    ///
+    /// [`Body::read_to_buffer()`](crate::Body::read_to_buffer) reads into a buffer of
+    /// the agent, which goes back to it when the [`BodyBuffer`](crate::BodyBuffer) is
+    /// dropped. For many small responses, this saves allocating a buffer for each body.
+    /// The pool holds up to this many buffers, each as large as the largest body it was
+    /// used for. Size it to the number of bodies held at the same time.
+    ///
+    /// The `body_buffers` example counts the allocations per request with and without
+    /// the pool.
+    ///
+    /// This setting has no effect when used per-request.
+    ///
+    /// Defaults to `0`, meaning no pool.
+    pub fn body_buffer_pool(mut self, v: usize) -> Self {
+        self.config().body_buffer_pool = v;
+        self
+    }
+
+    /// Max size of a request body to send in the same write as the request head.
+    ///
+    /// For bodies with a known length up to this size, the request line, headers and
+    /// body are buffered and handed to the transport together. This saves a write
+    /// (typically a syscall) per request. In-memory bodies larger than this are also
+    /// sent together with the head, without copying them to the output buffer, using
+    /// [`Transport::transmit_output_vectored()`](crate::transport::Transport::transmit_output_vectored).
+    ///
+    /// Requests using `Expect: 100-continue` always send the head on its own. Set to 0
+    /// to never combine the head and body.
+    ///
+    /// Defaults to 4kb.
+    pub fn max_coalesce_body_size(mut self, v: usize) -> Self {
+        self.config().max_coalesce_body_size = v;
+        self
+    }
+
+    /// Max number of requests per second for the agent.
+    ///
+    /// Before each request, including each followed redirect, the calling thread blocks
+    /// until the request is allowed. Requests are spread out evenly, i.e. a rate of `2.0`
+    /// lets a request start every 500ms, with no allowance for bursts. The rate is shared
+    /// between all clones of the agent, and threads get their turn in the order they ask.
+    ///
+    /// If waiting would pass the global or per call timeout, the request fails
+    /// immediately with [`Error::Timeout`](crate::Error::Timeout).
+    ///
+    /// A rate that isn't a positive number means no limit. A rate so low that the wait
+    /// between requests doesn't fit in a [`Duration`] fails each request with
+    /// [`Error::InvalidRequestRate`](crate::Error::InvalidRequestRate).
+    ///
+    /// This setting has no effect when used per-request.
+    ///
+    /// Defaults to `None`
+    pub fn max_requests_per_second(mut self, v: Option<f64>) -> Self {
+        self.config().max_requests_per_second = v;
+        self
+    }
+
+    /// Max number of requests in flight to the same host for the agent.
+    ///
+    /// A request is in flight from connecting until the response body is read to the end
+    /// (or dropped). Further requests to the host block until one of them finishes. This
+    /// differs from the pool settings, which bound idle connections, not active requests.
+    ///
+    /// Holding on to unread response bodies while making more requests to the same host
+    /// on the same thread blocks forever, unless there is a global or per call timeout.
+    /// When the timeout passes while waiting, the request fails with
+    /// [`Error::Timeout`](crate::Error::Timeout).
+    ///
+    /// `Some(0)` means no limit.
+    ///
+    /// This setting has no effect when used per-request.
+    ///
+    /// Defaults to `None`
+    pub fn max_concurrent_requests_per_host(mut self, v: Option<usize>) -> Self {
+        self.config().max_concurrent_requests_per_host = v;
+        self
+    }
+
+    /// Ramp up the number of requests in flight to a host gradually.
+    ///
+    /// Each host starts out allowing a single request in flight. Every request that gets
+    /// a response allows one more, up to
+    /// [`max_concurrent_requests_per_host`](Self::max_concurrent_requests_per_host),
+    /// which means the number doubles per round of requests. A request that fails
+    /// without a response starts the host over at one.
+    ///
+    /// This has no effect unless `max_concurrent_requests_per_host` is set, and like it,
+    /// has no effect when used per-request.
+    ///
+    /// Defaults to `false`.
+    pub fn concurrency_slow_start(mut self, v: bool) -> Self {
+        self.config().concurrency_slow_start = v;
+        self
+    }
+
     /// Max number of idle pooled connections overall.
     ///
     /// This setting has no effect when used per-request.
@@ -324,6 +863,11 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
 
     /// Max duration to keep an idle connection in the pool
     ///
+    /// Servers close connections that have been idle for some time, and reusing such a
+    /// connection fails. Set this shorter than the server's idle timeout. Connections
+    /// idle for longer are closed whenever the agent uses the pool, that is when making
+    /// a request or returning a connection after a response.
+    ///
     /// This can also be configured per-request to be shorter than the pool.
     /// For example: if the pool is configured to 15 seconds and we have a
     /// connection with an age of 10 seconds, a request setting this config
@@ -336,6 +880,192 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
+    /// Check that a pooled connection is still open before reusing it.
+    ///
+    /// Servers close keep-alive connections that have been idle for a while. Before a
+    /// pooled connection is used for a request, a quick non-blocking read tells whether
+    /// the server has closed it. A closed connection is dropped, and the request uses
+    /// another pooled connection or opens a new one. For TLS connections, the probe is
+    /// of the underlying socket.
+    ///
+    /// Connections are checked the same way when they are returned to the pool, which
+    /// is not affected by this setting.
+    ///
+    /// Defaults to `true`
+    pub fn probe_pooled_connections(mut self, v: bool) -> Self {
+        self.config().probe_pooled_connections = v;
+        self
+    }
+
+    /// Retry once on a new connection when a pooled connection turns out to be dead.
+    ///
+    /// Pooled connections are checked before they are reused, but a server can still
+    /// close an idle connection just as the request is sent. With this enabled, a request
+    /// that fails on a reused connection before any response arrives is sent again on a
+    /// freshly opened connection.
+    ///
+    /// Only idempotent methods (`GET`, `HEAD`, `PUT`, `DELETE`, `OPTIONS` and `TRACE`) are
+    /// retried, and only when the request body can be sent again. Bodies from readers
+    /// can not.
+    ///
+    /// Defaults to `false`
+    pub fn retry_stale_connection(mut self, v: bool) -> Self {
+        self.config().retry_stale_connection = v;
+        self
+    }
+
+    /// Decide after each attempt whether to send the request again
+    ///
+    /// The function is given the number of attempts made so far, starting at 1, the
+    /// request and the outcome of the last attempt. The outcome is the response head,
+    /// before [`http_status_as_error()`](Self::http_status_as_error) turns error
+    /// statuses into [`Error::StatusCode`], or the error of the attempt.
+    ///
+    /// [`RetryDecision::Retry`] waits the given time and then sends the request again.
+    /// No retry happens when the request body can't be sent again, such as bodies from
+    /// readers. If the wait would end after the global timeout or deadline, the call
+    /// fails with [`Error::Timeout`] instead of waiting.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ureq::Agent;
+    /// use ureq::config::RetryDecision;
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .retry_predicate(|attempts, _request, outcome| match outcome {
+    ///         Ok(res) if res.status() == 503 && attempts < 3 => {
+    ///             RetryDecision::Retry(Duration::from_secs(1))
+    ///         }
+    ///         _ => RetryDecision::Stop,
+    ///     })
+    ///     .build()
+    ///     .into();
+    /// ```
+    ///
+    /// Defaults to no retries.
+    pub fn retry_predicate(
+        mut self,
+        v: impl Fn(u32, &Request<()>, Result<&Response<()>, &Error>) -> RetryDecision
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.config().retry_predicate = Some(RetryPredicate(Arc::new(v)));
+        self
+    }
+
+    /// Max number of body bytes to read and throw away when a response body is dropped
+    /// before being read to the end.
+    ///
+    /// Draining a small remainder lets the connection go back to the pool for reuse.
+    /// If the body is larger than this, the connection is closed instead, to not waste
+    /// bandwidth on data nobody wants. Set to 0 to always close.
+    ///
+    /// The drain waits at most [`max_drain_time_on_drop()`](Self::max_drain_time_on_drop)
+    /// for the data to arrive. A server that is slower than that gets its connection
+    /// closed, the drop never blocks for long.
+    ///
+    /// Defaults to 16kb.
+    pub fn max_drain_on_drop(mut self, v: usize) -> Self {
+        self.config().max_drain_on_drop = v;
+        self
+    }
+
+    /// Max time to wait for the rest of a response body when it is drained on drop.
+    ///
+    /// See [`max_drain_on_drop()`](Self::max_drain_on_drop). The drain also stops at the
+    /// `recv_body` timeout, if that comes first.
+    ///
+    /// Defaults to 1 second.
+    pub fn max_drain_time_on_drop(mut self, v: Duration) -> Self {
+        self.config().max_drain_time_on_drop = v;
+        self
+    }
+
+    /// Tolerate unexpected data after the end of a response.
+    ///
+    /// A misbehaving server can send more bytes than the response framing says, such
+    /// as after the `content-length` of the body. By default, that is an
+    /// [`Error::ExtraResponseData`] when reading the end of the body. With this
+    /// enabled, the data is ignored with a warning in the log, and the connection is
+    /// closed instead of reused.
+    ///
+    /// Only data arriving together with the response is detected. Data arriving later
+    /// makes the pool discard the connection, without an error.
+    ///
+    /// Defaults to `false`
+    pub fn allow_extra_response_data(mut self, v: bool) -> Self {
+        self.config().allow_extra_response_data = v;
+        self
+    }
+
+    /// Accept responses where the body length is ambiguous.
+    ///
+    /// Per RFC 7230 section 3.3.3, a response with several differing `content-length`
+    /// values, or with both `content-length` and `transfer-encoding: chunked`, can be
+    /// read differently by different parties, which is how request smuggling works. By
+    /// default such a response is an [`Error::AmbiguousFraming`]. Repeating the same
+    /// `content-length` value is not ambiguous and always accepted.
+    ///
+    /// With this enabled, ureq doesn't check, and such a response is read as well as
+    /// the headers allow.
+    ///
+    /// Defaults to `false`
+    pub fn allow_ambiguous_framing(mut self, v: bool) -> Self {
+        self.config().allow_ambiguous_framing = v;
+        self
+    }
+
+    /// Count the bytes sent and received by the agent.
+    ///
+    /// The counts are read with [`Agent::byte_counts()`]. This is a pair of atomic
+    /// counters shared by all clones of the agent, updated as data goes over the
+    /// connections.
+    ///
+    /// This setting has no effect when used per-request.
+    ///
+    /// Defaults to `false`
+    pub fn count_bytes(mut self, v: bool) -> Self {
+        self.config().count_bytes = v;
+        self
+    }
+
+    /// Send a `Content-Digest` header with in-memory request bodies.
+    ///
+    /// Requires the **digest** feature.
+    ///
+    /// The header is a SHA-256 of the body as per RFC 9530, such as
+    /// `content-digest: sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:`.
+    /// Only bodies held in memory, such as `&[u8]` or `String`, get the header, since
+    /// the digest must be sent before the body. A `content-digest` header set on the
+    /// request is left as is.
+    ///
+    /// Defaults to `false`
+    #[cfg(feature = "digest")]
+    pub fn send_content_digest(mut self, v: bool) -> Self {
+        self.config().send_content_digest = v;
+        self
+    }
+
+    /// Verify response bodies against the `Content-Digest` header.
+    ///
+    /// Requires the **digest** feature.
+    ///
+    /// The digest is calculated while the body is read, and checked at the end of the
+    /// body, which means the data is already handed out when a mismatch is found. A
+    /// mismatch makes the read of the end fail with [`Error::ContentDigest`]. The check
+    /// is of the body as sent, before any decompression.
+    ///
+    /// `sha-256` and `sha-512` digests are supported. Responses without a supported
+    /// digest, and bodies not read to the end, are not checked.
+    ///
+    /// Defaults to `false`
+    #[cfg(feature = "digest")]
+    pub fn verify_content_digest(mut self, v: bool) -> Self {
+        self.config().verify_content_digest = v;
+        self
+    }
+
     /// Add middleware to use for each request in this agent.
     ///
     /// Defaults to no middleware.
@@ -347,7 +1077,8 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
     /// Timeout for the entire call
     ///
     /// This is end-to-end, from DNS lookup to finishing reading the response body.
-    /// Thus it covers all other timeouts.
+    /// Thus it covers all other timeouts. When the call starts, the timeout becomes a
+    /// point in time, the same as a [`deadline()`](Self::deadline).
     ///
     /// Defaults to `None`.
     pub fn timeout_global(mut self, v: Option<Duration>) -> Self {
@@ -355,6 +1086,38 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
+    /// Deadline for the entire call
+    ///
+    /// Like [`timeout_global()`](Self::timeout_global), but as an absolute point in time.
+    /// This is useful when one deadline should span several requests, such as retries done
+    /// by the caller. Passing the deadline fails the call with
+    /// [`Error::Timeout(Timeout::Global)`](crate::Error::Timeout). If both are set, the
+    /// earliest one applies.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let deadline = Instant::now() + Duration::from_secs(30);
+    ///
+    /// for _ in 0..3 {
+    ///     let result = ureq::get("http://httpbin.org/get")
+    ///         .config()
+    ///         .deadline(Some(deadline))
+    ///         .build()
+    ///         .call();
+    ///
+    ///     if result.is_ok() {
+    ///         break;
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Defaults to `None`.
+    pub fn deadline(mut self, v: Option<Instant>) -> Self {
+        self.config().timeouts.deadline = v;
+        self
+    }
+
     /// Timeout for call-by-call when following redirects
     ///
     /// This covers a single call and the timeout is reset when
@@ -369,8 +1132,9 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
     /// Max duration for doing the DNS lookup when establishing the connection
     ///
     /// Because most platforms do not have an async syscall for looking up
-    /// a host name, setting this might force str0m to spawn a thread to handle
-    /// the timeout.
+    /// a host name, setting this might force ureq to spawn a thread to handle
+    /// the timeout. A lookup that takes too long fails with
+    /// [`Error::Timeout(Timeout::Resolve)`](crate::Error::Timeout).
     ///
     /// Defaults to `None`.
     pub fn timeout_resolve(mut self, v: Option<Duration>) -> Self {
@@ -401,6 +1165,21 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
     /// Only used if there is a request body and we sent the `Expect: 100-continue`
     /// header to indicate we want the server to respond with 100.
     ///
+    /// When the time is up without a response, the body is sent anyway, as per
+    /// RFC 9110. This is not an error. `None` waits for the server to respond, bounded
+    /// only by the global timeout.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let res = ureq::put("http://httpbin.org/put")
+    ///     .header("expect", "100-continue")
+    ///     .config()
+    ///     .timeout_await_100(Some(Duration::from_millis(200)))
+    ///     .build()
+    ///     .send("large upload");
+    /// ```
+    ///
     /// This defaults to 1 second.
     pub fn timeout_await_100(mut self, v: Option<Duration>) -> Self {
         self.config().timeouts.await_100 = v;
@@ -423,7 +1202,15 @@ impl<Scope: private::ConfigScope> ConfigBuilder<Scope> {
         self
     }
 
-    /// Max duration for receving the response body.
+    /// Max duration for receiving the response body
+    ///
+    /// This is the total time from receiving the response headers until the end of
+    /// the body, however steadily the data arrives. It's a cap on the download time, not
+    /// an idle timeout. Reading the body after this fails with
+    /// [`Error::Timeout(Timeout::RecvBody)`](crate::Error::Timeout).
+    ///
+    /// Time spent by the caller between reads counts too, since the clock is the wall
+    /// clock.
     ///
     /// Defaults to `None`.
     pub fn timeout_recv_body(mut self, v: Option<Duration>) -> Self {
@@ -453,6 +1240,140 @@ impl<S: AsSendBody> ConfigBuilder<HttpCrateScope<S>> {
     }
 }
 
+/// The common agent settings as a plain struct.
+///
+/// An alternative to [`ConfigBuilder`] for settings that come from elsewhere, such as a
+/// config file or the options of another HTTP client. With the **serde** feature, the
+/// struct is `Serialize` and `Deserialize`, and fields missing when deserializing keep
+/// their defaults.
+///
+/// [`AgentConfig::build()`] applies the settings to a [`ConfigBuilder`]. Anything not
+/// covered here is set on the builder as usual, see [`AgentConfig::builder()`].
+///
+/// ```
+/// use std::time::Duration;
+/// use ureq::config::AgentConfig;
+/// use ureq::Agent;
+///
+/// let settings = AgentConfig {
+///     timeout_global: Some(Duration::from_secs(10)),
+///     max_redirects: 5,
+///     user_agent: Some("my-app/1.0".to_string()),
+///     ..Default::default()
+/// };
+///
+/// let agent = Agent::new_with_config(settings.build()?);
+/// # Ok::<_, ureq::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct AgentConfig {
+    /// See [`ConfigBuilder::timeout_global()`].
+    pub timeout_global: Option<Duration>,
+
+    /// See [`ConfigBuilder::timeout_per_call()`].
+    pub timeout_per_call: Option<Duration>,
+
+    /// See [`ConfigBuilder::timeout_connect()`].
+    pub timeout_connect: Option<Duration>,
+
+    /// See [`ConfigBuilder::timeout_recv_response()`].
+    pub timeout_recv_response: Option<Duration>,
+
+    /// See [`ConfigBuilder::timeout_recv_body()`].
+    pub timeout_recv_body: Option<Duration>,
+
+    /// Proxy uri, such as `socks5://localhost:1080`, see [`Proxy::new()`].
+    ///
+    /// `None` keeps the proxy from the environment, see [`Proxy::try_from_env()`].
+    pub proxy: Option<String>,
+
+    /// See [`ConfigBuilder::max_redirects()`].
+    pub max_redirects: u32,
+
+    /// See [`ConfigBuilder::user_agent()`].
+    pub user_agent: Option<String>,
+
+    /// See [`ConfigBuilder::https_only()`].
+    pub https_only: bool,
+
+    /// See [`ConfigBuilder::http_status_as_error()`].
+    pub http_status_as_error: bool,
+
+    /// See [`TlsConfigBuilder::use_sni()`](crate::tls::TlsConfigBuilder::use_sni).
+    #[cfg(feature = "_tls")]
+    pub tls_use_sni: bool,
+
+    /// See [`TlsConfigBuilder::disable_verification()`](crate::tls::TlsConfigBuilder::disable_verification).
+    #[cfg(feature = "_tls")]
+    pub tls_disable_verification: bool,
+}
+
+impl AgentConfig {
+    /// A [`ConfigBuilder`] with these settings applied.
+    ///
+    /// Fails if the proxy uri is not valid.
+    pub fn builder(self) -> Result<ConfigBuilder<AgentScope>, Error> {
+        let mut builder = Config::builder()
+            .timeout_global(self.timeout_global)
+            .timeout_per_call(self.timeout_per_call)
+            .timeout_connect(self.timeout_connect)
+            .timeout_recv_response(self.timeout_recv_response)
+            .timeout_recv_body(self.timeout_recv_body)
+            .max_redirects(self.max_redirects)
+            .user_agent(self.user_agent)
+            .https_only(self.https_only)
+            .http_status_as_error(self.http_status_as_error);
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Some(Proxy::new(proxy)?));
+        }
+
+        #[cfg(feature = "_tls")]
+        {
+            let tls_config = TlsConfig {
+                use_sni: self.tls_use_sni,
+                disable_verification: self.tls_disable_verification,
+                ..TlsConfig::default()
+            };
+            builder = builder.tls_config(tls_config);
+        }
+
+        Ok(builder)
+    }
+
+    /// Build the [`Config`] with these settings.
+    ///
+    /// Fails if the proxy uri is not valid.
+    pub fn build(self) -> Result<Config, Error> {
+        Ok(self.builder()?.build())
+    }
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        let config = Config::default();
+
+        Self {
+            timeout_global: config.timeouts.global,
+            timeout_per_call: config.timeouts.per_call,
+            timeout_connect: config.timeouts.connect,
+            timeout_recv_response: config.timeouts.recv_response,
+            timeout_recv_body: config.timeouts.recv_body,
+            proxy: None,
+            max_redirects: config.max_redirects,
+            user_agent: config.user_agent,
+            https_only: config.https_only,
+            http_status_as_error: config.http_status_as_error,
+            #[cfg(feature = "_tls")]
+            tls_use_sni: config.tls_config.use_sni,
+            #[cfg(feature = "_tls")]
+            tls_disable_verification: config.tls_config.disable_verification,
+        }
+    }
+}
+
 /// Request timeout configuration.
 ///
 /// This can be configured both on Agent level as well as per request.
@@ -484,6 +1405,9 @@ pub struct Timeouts {
 
     /// Max duration for receving the response body.
     pub recv_body: Option<Duration>,
+
+    /// Absolute point in time when the entire call must be finished
+    pub deadline: Option<Instant>,
 }
 
 #[derive(Debug, Clone)]
@@ -497,22 +1421,57 @@ impl Default for Config {
             http_status_as_error: true,
             https_only: false,
             ip_family: IpFamily::Any,
+            header_casing: Arc::new(vec![]),
+            capture_request_head: None,
+            redact_captured_headers: true,
+            request_signer: None,
+            request_id: None,
+            connect_port: None,
+            resolve_overrides: Arc::new(vec![]),
             #[cfg(feature = "_tls")]
             tls_config: TlsConfig::default(),
             proxy: Proxy::try_from_env(),
             no_delay: true,
             max_redirects: 10,
             redirect_auth_headers: RedirectAuthHeaders::Never,
+            redirect_post_as_get: true,
+            max_cross_host_redirects: None,
+            redirect_check: None,
+            redirect_statuses: Arc::new(vec![301, 302, 303, 307, 308]),
+            auth_credentials: None,
             user_agent: None,
             timeouts: Timeouts::default(),
             max_response_header_size: 64 * 1024,
+            max_response_size: None,
             input_buffer_size: 128 * 1024,
             output_buffer_size: 128 * 1024,
+            body_buffer_capacity: 8 * 1024,
+            body_buffer_max_growth: 1024 * 1024,
+            body_buffer_pool: 0,
+            max_coalesce_body_size: 4 * 1024,
+            max_requests_per_second: None,
+            max_concurrent_requests_per_host: None,
+            concurrency_slow_start: false,
             max_idle_connections: 10,
             max_idle_connections_per_host: 3,
             max_idle_age: Duration::from_secs(15),
+            retry_stale_connection: false,
+            probe_pooled_connections: true,
+            retry_predicate: None,
+            max_drain_on_drop: 16 * 1024,
+            max_drain_time_on_drop: Duration::from_secs(1),
+            allow_extra_response_data: false,
+            allow_ambiguous_framing: false,
+            count_bytes: false,
+            #[cfg(feature = "digest")]
+            send_content_digest: false,
+            #[cfg(feature = "digest")]
+            verify_content_digest: false,
             middleware: MiddlewareChain::default(),
             force_send_body: false,
+            fresh_connection: false,
+            #[cfg(feature = "content-sniff")]
+            sniff_content_type: false,
         }
     }
 }
@@ -529,10 +1488,93 @@ impl Default for Timeouts {
             send_body: None,
             recv_response: None,
             recv_body: None,
+            deadline: None,
+        }
+    }
+}
+
+/// Function observing request heads, see [`ConfigBuilder::capture_request_head()`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub(crate) struct RequestHeadCapture(pub Arc<dyn Fn(&[u8]) + Send + Sync>);
+
+impl fmt::Debug for RequestHeadCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestHeadCapture").finish()
+    }
+}
+
+/// Function computing signature headers, see [`ConfigBuilder::sign_request()`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub(crate) struct RequestSigner(
+    pub Arc<dyn Fn(&Request<()>, Option<&[u8]>) -> Result<HeaderMap, Error> + Send + Sync>,
+);
+
+impl fmt::Debug for RequestSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestSigner").finish()
+    }
+}
+
+/// Header for request ids, see [`ConfigBuilder::request_id()`].
+#[derive(Clone)]
+pub(crate) struct RequestIdHeader {
+    pub header: HeaderName,
+    /// Generates the ids, or `None` for random ids.
+    pub generate: Option<Arc<dyn Fn() -> String + Send + Sync>>,
+}
+
+impl RequestIdHeader {
+    pub fn generate(&self) -> String {
+        match &self.generate {
+            Some(f) => f(),
+            None => crate::request_id::generate_id(),
         }
     }
 }
 
+impl fmt::Debug for RequestIdHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdHeader")
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+/// Whether to send a request again, see [`ConfigBuilder::retry_predicate()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Send the request again after waiting this long.
+    Retry(Duration),
+    /// Don't retry, the last attempt is the result of the call.
+    Stop,
+}
+
+/// Function deciding on retries, see [`ConfigBuilder::retry_predicate()`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub(crate) struct RetryPredicate(
+    pub Arc<dyn Fn(u32, &Request<()>, Result<&Response<()>, &Error>) -> RetryDecision + Send + Sync>,
+);
+
+impl fmt::Debug for RetryPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPredicate").finish()
+    }
+}
+
+/// Function to check redirects, see [`ConfigBuilder::redirect_check()`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub(crate) struct RedirectCheck(pub Arc<dyn Fn(&Uri, &Uri) -> Result<(), Error> + Send + Sync>);
+
+impl fmt::Debug for RedirectCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedirectCheck").finish()
+    }
+}
+
 impl fmt::Debug for Config {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut dbg = f.debug_struct("Config");
@@ -540,21 +1582,52 @@ impl fmt::Debug for Config {
         dbg.field("http_status_as_error", &self.http_status_as_error)
             .field("https_only", &self.https_only)
             .field("ip_family", &self.ip_family)
+            .field("header_casing", &self.header_casing)
+            .field("capture_request_head", &self.capture_request_head)
+            .field("redact_captured_headers", &self.redact_captured_headers)
+            .field("request_signer", &self.request_signer)
+            .field("request_id", &self.request_id)
+            .field("connect_port", &self.connect_port)
+            .field("resolve_overrides", &self.resolve_overrides)
             .field("proxy", &self.proxy)
             .field("no_delay", &self.no_delay)
             .field("max_redirects", &self.max_redirects)
             .field("redirect_auth_headers", &self.redirect_auth_headers)
+            .field("redirect_post_as_get", &self.redirect_post_as_get)
+            .field("max_cross_host_redirects", &self.max_cross_host_redirects)
+            .field("redirect_check", &self.redirect_check)
+            .field("redirect_statuses", &self.redirect_statuses)
+            .field("auth_credentials", &self.auth_credentials)
             .field("user_agent", &self.user_agent)
             .field("timeouts", &self.timeouts)
             .field("max_response_header_size", &self.max_response_header_size)
+            .field("max_response_size", &self.max_response_size)
             .field("input_buffer_size", &self.input_buffer_size)
             .field("output_buffer_size", &self.output_buffer_size)
+            .field("body_buffer_capacity", &self.body_buffer_capacity)
+            .field("body_buffer_max_growth", &self.body_buffer_max_growth)
+            .field("body_buffer_pool", &self.body_buffer_pool)
+            .field("max_coalesce_body_size", &self.max_coalesce_body_size)
+            .field("max_requests_per_second", &self.max_requests_per_second)
+            .field(
+                "max_concurrent_requests_per_host",
+                &self.max_concurrent_requests_per_host,
+            )
+            .field("concurrency_slow_start", &self.concurrency_slow_start)
             .field("max_idle_connections", &self.max_idle_connections)
             .field(
                 "max_idle_connections_per_host",
                 &self.max_idle_connections_per_host,
             )
             .field("max_idle_age", &self.max_idle_age)
+            .field("retry_stale_connection", &self.retry_stale_connection)
+            .field("probe_pooled_connections", &self.probe_pooled_connections)
+            .field("retry_predicate", &self.retry_predicate)
+            .field("max_drain_on_drop", &self.max_drain_on_drop)
+            .field("max_drain_time_on_drop", &self.max_drain_time_on_drop)
+            .field("allow_extra_response_data", &self.allow_extra_response_data)
+            .field("allow_ambiguous_framing", &self.allow_ambiguous_framing)
+            .field("count_bytes", &self.count_bytes)
             .field("middleware", &self.middleware);
 
         #[cfg(feature = "_tls")]
@@ -562,6 +1635,12 @@ impl fmt::Debug for Config {
             dbg.field("tls_config", &self.tls_config);
         }
 
+        #[cfg(feature = "digest")]
+        {
+            dbg.field("send_content_digest", &self.send_content_digest)
+                .field("verify_content_digest", &self.verify_content_digest);
+        }
+
         dbg.finish()
     }
 }
@@ -578,6 +1657,7 @@ impl fmt::Debug for Timeouts {
             .field("send_body", &self.send_body)
             .field("recv_response", &self.recv_response)
             .field("recv_body", &self.recv_body)
+            .field("deadline", &self.deadline)
             .finish()
     }
 }
@@ -592,4 +1672,53 @@ mod test {
         let c = Config::default();
         assert_no_alloc(|| c.clone());
     }
+
+    #[test]
+    fn agent_config_default_is_config_default() {
+        let config = AgentConfig::default().build().unwrap();
+        let default = Config::default();
+
+        assert_eq!(config.max_redirects, default.max_redirects);
+        assert_eq!(config.http_status_as_error, default.http_status_as_error);
+        assert_eq!(config.user_agent, default.user_agent);
+        assert_eq!(config.timeouts.global, default.timeouts.global);
+    }
+
+    #[test]
+    fn agent_config_applied() {
+        let config = AgentConfig {
+            timeout_connect: Some(Duration::from_secs(3)),
+            proxy: Some("socks5://localhost:1080".to_string()),
+            max_redirects: 2,
+            https_only: true,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(config.timeouts.connect, Some(Duration::from_secs(3)));
+        assert!(config.proxy.is_some());
+        assert_eq!(config.max_redirects, 2);
+        assert!(config.https_only);
+    }
+
+    #[test]
+    fn agent_config_bad_proxy() {
+        let settings = AgentConfig {
+            proxy: Some("ftp://localhost".to_string()),
+            ..Default::default()
+        };
+        assert!(settings.build().is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "json"))]
+    fn agent_config_deserialize_partial() {
+        let settings: AgentConfig =
+            serde_json::from_str(r#"{"max_redirects": 3, "https_only": true}"#).unwrap();
+
+        assert_eq!(settings.max_redirects, 3);
+        assert!(settings.https_only);
+        assert_eq!(settings.user_agent, AgentConfig::default().user_agent);
+    }
 }
@@ -1,9 +1,9 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::io;
+use std::mem;
 use std::sync::{Mutex, MutexGuard};
 
-use cookie_store::CookieStore;
 use http::Uri;
 
 use crate::util::UriExt;
@@ -11,14 +11,22 @@ use crate::Error;
 
 #[derive(Debug)]
 pub(crate) struct SharedCookieJar {
-    inner: Mutex<CookieStore>,
+    inner: Mutex<cookie_store::CookieStore>,
 }
 
 /// Collection of cookies.
 ///
 /// The jar is accessed using [`Agent::cookie_jar_lock`][crate::Agent::cookie_jar_lock].
 /// It can be saved and loaded.
-pub struct CookieJar<'a>(MutexGuard<'a, CookieStore>);
+pub struct CookieJar<'a>(MutexGuard<'a, cookie_store::CookieStore>);
+
+/// Collection of cookies that is not in use by an agent.
+///
+/// Swapped in and out of an agent using
+/// [`Agent::set_cookie_store`][crate::Agent::set_cookie_store], to keep the cookies of
+/// separate sessions apart.
+#[derive(Debug)]
+pub struct CookieStore(cookie_store::CookieStore);
 
 /// Representation of an HTTP cookie.
 ///
@@ -144,7 +152,7 @@ impl<'a> CookieJar<'a> {
     ///
     /// Replaces all the contents of the current cookie jar.
     pub fn load_json<R: io::BufRead>(&mut self, reader: R) -> Result<(), Error> {
-        let store = CookieStore::load_json(reader)?;
+        let store = cookie_store::CookieStore::load_json(reader)?;
         *self.0 = store;
         Ok(())
     }
@@ -163,10 +171,34 @@ impl<'a> CookieJar<'a> {
     pub fn release(self) {}
 }
 
+impl CookieStore {
+    /// An empty store.
+    pub fn new() -> Self {
+        CookieStore(cookie_store::CookieStore::new())
+    }
+
+    /// Load JSON-formatted cookies from `reader`, skipping any __expired__ cookies.
+    pub fn load_json<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        Ok(CookieStore(cookie_store::CookieStore::load_json(reader)?))
+    }
+
+    /// Serialize any __unexpired__ and __persistent__ cookies in the store to JSON format and
+    /// write them to `writer`
+    pub fn save_json<W: io::Write>(&self, writer: &mut W) -> Result<(), Error> {
+        Ok(self.0.save_json(writer)?)
+    }
+}
+
+impl Default for CookieStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SharedCookieJar {
     pub(crate) fn new() -> Self {
         SharedCookieJar {
-            inner: Mutex::new(CookieStore::new()),
+            inner: Mutex::new(cookie_store::CookieStore::new()),
         }
     }
 
@@ -175,6 +207,11 @@ impl SharedCookieJar {
         CookieJar(lock)
     }
 
+    pub(crate) fn replace(&self, store: CookieStore) -> CookieStore {
+        let mut lock = self.inner.lock().unwrap();
+        CookieStore(mem::replace(&mut *lock, store.0))
+    }
+
     pub(crate) fn get_request_cookies(&self, uri: &Uri) -> String {
         let mut cookies = String::new();
 
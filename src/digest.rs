@@ -0,0 +1,139 @@
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use http::{HeaderMap, HeaderValue};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::Error;
+
+/// `Content-Digest` header value for a body, as per RFC 9530.
+pub(crate) fn content_digest(data: &[u8]) -> HeaderValue {
+    let hash = BASE64_STANDARD.encode(Sha256::digest(data));
+    // unwrap is ok, base64 is always a valid header value.
+    HeaderValue::from_str(&format!("sha-256=:{}:", hash)).unwrap()
+}
+
+/// Checks a response body against the `Content-Digest` header.
+///
+/// The digest is over the content as sent, before any decompression.
+pub(crate) struct DigestCheck {
+    hasher: Hasher,
+    expected: Vec<u8>,
+}
+
+enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl DigestCheck {
+    /// A check for the strongest supported algorithm in the header, if there is one.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut sha256 = None;
+        let mut sha512 = None;
+
+        let entries = headers
+            .get_all("content-digest")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+
+        for entry in entries {
+            // Parameters after ; are not used by any algorithm.
+            let entry = entry.split(';').next().unwrap_or_default();
+            let Some((algorithm, value)) = entry.split_once('=') else {
+                continue;
+            };
+
+            let value = value.trim();
+            let Some(value) = value.strip_prefix(':').and_then(|v| v.strip_suffix(':')) else {
+                continue;
+            };
+            let Ok(expected) = BASE64_STANDARD.decode(value) else {
+                continue;
+            };
+
+            match algorithm.trim() {
+                "sha-256" => sha256 = Some(expected),
+                "sha-512" => sha512 = Some(expected),
+                a => debug!("Ignore content-digest algorithm: {}", a),
+            }
+        }
+
+        if let Some(expected) = sha512 {
+            Some(DigestCheck {
+                hasher: Hasher::Sha512(Sha512::new()),
+                expected,
+            })
+        } else {
+            sha256.map(|expected| DigestCheck {
+                hasher: Hasher::Sha256(Sha256::new()),
+                expected,
+            })
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.hasher {
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+        }
+    }
+
+    pub fn verify(self) -> Result<(), Error> {
+        let (algorithm, actual) = match self.hasher {
+            Hasher::Sha256(h) => ("sha-256", h.finalize().to_vec()),
+            Hasher::Sha512(h) => ("sha-512", h.finalize().to_vec()),
+        };
+
+        if actual != self.expected {
+            return Err(Error::ContentDigest(algorithm));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(header: &str) -> Option<DigestCheck> {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-digest", header.parse().unwrap());
+        DigestCheck::from_headers(&headers)
+    }
+
+    #[test]
+    fn sha256_roundtrip() {
+        let header = content_digest(b"hello world");
+        assert_eq!(
+            header,
+            "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:"
+        );
+
+        let mut c = check(header.to_str().unwrap()).unwrap();
+        c.update(b"hello ");
+        c.update(b"world");
+        c.verify().unwrap();
+    }
+
+    #[test]
+    fn mismatch() {
+        let mut c = check("sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:").unwrap();
+        c.update(b"hello there");
+        assert!(matches!(c.verify(), Err(Error::ContentDigest("sha-256"))));
+    }
+
+    #[test]
+    fn prefers_sha512() {
+        let c = check("sha-256=:AAAA:, sha-512=:AAAA:").unwrap();
+        assert!(matches!(c.hasher, Hasher::Sha512(_)));
+    }
+
+    #[test]
+    fn unsupported_or_broken() {
+        assert!(check("md5=:AAAA:").is_none());
+        assert!(check("sha-256=AAAA").is_none());
+        assert!(check("sha-256=:not base64!:").is_none());
+    }
+}
@@ -40,10 +40,20 @@ pub enum Error {
     /// A redirect failed.
     ///
     /// This happens when ureq encounters a redirect when sending a request body
-    /// such as a POST request, and receives a 307/308 response. ureq refuses to
-    /// redirect the POST body and instead raises this error.
+    /// such as a POST request, and receives a 307/308 response. Bodies from a
+    /// reader can't be sent again, so ureq refuses to redirect the POST body and
+    /// instead raises this error. In-memory bodies such as `&[u8]` or `String` are
+    /// sent again. The same goes for 301/302 when
+    /// [`redirect_post_as_get`](crate::config::ConfigBuilder::redirect_post_as_get)
+    /// is `false`.
     RedirectFailed,
 
+    /// A redirect was not allowed to be followed.
+    ///
+    /// See [`max_cross_host_redirects`](crate::config::ConfigBuilder::max_cross_host_redirects)
+    /// and [`redirect_check`](crate::config::ConfigBuilder::redirect_check).
+    RedirectDenied(String),
+
     /// Error when creating proxy settings.
     InvalidProxyUrl,
 
@@ -51,6 +61,9 @@ pub enum Error {
     ConnectionFailed,
 
     /// A send body (Such as `&str`) is larger than the `content-length` header.
+    ///
+    /// Also used for a response body larger than a limit, such as
+    /// [`ConfigBuilder::max_response_size()`](crate::config::ConfigBuilder::max_response_size).
     BodyExceedsLimit(u64),
 
     /// Some error with TLS.
@@ -128,9 +141,49 @@ pub enum Error {
     #[cfg(feature = "json")]
     Json(serde_json::Error),
 
+    /// The response body doesn't match the `Content-Digest` header.
+    ///
+    /// The value is the digest algorithm, such as `"sha-256"`. See
+    /// [`ConfigBuilder::verify_content_digest`](crate::config::ConfigBuilder::verify_content_digest).
+    #[cfg(feature = "digest")]
+    ContentDigest(&'static str),
+
+    /// Serde urlencoded error, such as for a struct with nested fields
+    /// in [`RequestBuilder::query_struct()`](crate::RequestBuilder::query_struct).
+    #[cfg(feature = "urlencoded")]
+    UrlEncoded(serde_urlencoded::ser::Error),
+
     /// Attempt to connect to a CONNECT proxy failed.
     ConnectProxyFailed(String),
 
+    /// The server sent this many bytes after the end of the response.
+    ///
+    /// See [`ConfigBuilder::allow_extra_response_data`](crate::config::ConfigBuilder::allow_extra_response_data).
+    ExtraResponseData(usize),
+
+    /// Request trailers could not be sent.
+    ///
+    /// Trailers require a chunked request body, and every trailer must be
+    /// announced up front in the `Trailer` request header.
+    InvalidTrailer(String),
+
+    /// The response headers don't clearly say where the body ends.
+    ///
+    /// This is a response with differing `Content-Length` values, or with both
+    /// `Content-Length` and `Transfer-Encoding: chunked`. Either is a sign of request
+    /// smuggling. See
+    /// [`ConfigBuilder::allow_ambiguous_framing`](crate::config::ConfigBuilder::allow_ambiguous_framing).
+    AmbiguousFraming(String),
+
+    /// A multipart response body is not well formed.
+    ///
+    /// See [`Body::into_multipart()`](crate::Body::into_multipart).
+    Multipart(String),
+
+    /// The rate of [`max_requests_per_second()`](crate::config::ConfigBuilder::max_requests_per_second)
+    /// is too low to give a wait between requests.
+    InvalidRequestRate(f64),
+
     /// hoot made no progress and there is no more input to read.
     ///
     /// We should never see this value.
@@ -153,6 +206,30 @@ impl Error {
         }
     }
 
+    /// The wrapped [`std::io::Error`], if this is [`Error::Io`].
+    ///
+    /// Like [`into_io()`](Error::into_io), but by reference and without wrapping
+    /// the other variants.
+    pub fn as_io(&self) -> Option<&io::Error> {
+        if let Self::Io(e) = self {
+            Some(e)
+        } else {
+            None
+        }
+    }
+
+    /// The kind of the wrapped [`std::io::Error`], if this is [`Error::Io`].
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// let err: ureq::Error = io::Error::from(io::ErrorKind::ConnectionReset).into();
+    /// assert_eq!(err.io_kind(), Some(io::ErrorKind::ConnectionReset));
+    /// ```
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        self.as_io().map(|e| e.kind())
+    }
+
     pub(crate) fn disconnected() -> Error {
         io::Error::new(io::ErrorKind::UnexpectedEof, "Peer disconnected").into()
     }
@@ -184,6 +261,7 @@ impl fmt::Display for Error {
             Error::Timeout(v) => write!(f, "timeout: {}", v),
             Error::HostNotFound => write!(f, "host not found"),
             Error::RedirectFailed => write!(f, "redirect failed"),
+            Error::RedirectDenied(v) => write!(f, "redirect denied: {}", v),
             Error::InvalidProxyUrl => write!(f, "invalid proxy url"),
             Error::ConnectionFailed => write!(f, "connection failed"),
             Error::BodyExceedsLimit(v) => {
@@ -215,7 +293,18 @@ impl fmt::Display for Error {
             Error::Decompress(x, y) => write!(f, "{} decompression failed: {}", x, y),
             #[cfg(feature = "json")]
             Error::Json(v) => write!(f, "json: {}", v),
+            #[cfg(feature = "digest")]
+            Error::ContentDigest(v) => write!(f, "body does not match {} content-digest", v),
+            #[cfg(feature = "urlencoded")]
+            Error::UrlEncoded(v) => write!(f, "urlencoded: {}", v),
             Error::ConnectProxyFailed(v) => write!(f, "CONNECT proxy failed: {}", v),
+            Error::ExtraResponseData(v) => {
+                write!(f, "{} bytes of unexpected data after the response", v)
+            }
+            Error::InvalidTrailer(v) => write!(f, "invalid trailer: {}", v),
+            Error::AmbiguousFraming(v) => write!(f, "ambiguous response framing: {}", v),
+            Error::Multipart(v) => write!(f, "multipart: {}", v),
+            Error::InvalidRequestRate(v) => write!(f, "request rate too low: {}", v),
             Error::BodyStalled => write!(f, "body data reading stalled"),
         }
     }
@@ -275,6 +364,13 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "urlencoded")]
+impl From<serde_urlencoded::ser::Error> for Error {
+    fn from(value: serde_urlencoded::ser::Error) -> Self {
+        Self::UrlEncoded(value)
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -305,6 +401,16 @@ mod test {
         assert!(matches!(err, Error::StatusCode(500)));
     }
 
+    #[test]
+    fn as_io_only_for_io() {
+        let err = Error::disconnected();
+        assert_eq!(err.as_io().unwrap().to_string(), "Peer disconnected");
+        assert_eq!(err.io_kind(), Some(io::ErrorKind::UnexpectedEof));
+
+        assert!(Error::StatusCode(500).as_io().is_none());
+        assert_eq!(Error::StatusCode(500).io_kind(), None);
+    }
+
     #[test]
     fn ensure_error_size() {
         // This is platform dependent, so we can't be too strict or precise.
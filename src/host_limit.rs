@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+use crate::timings::NextTimeout;
+use crate::transport::time::Duration;
+use crate::Error;
+
+/// How many hosts without requests in flight keep their slow start window.
+const MAX_IDLE_HOSTS: usize = 100;
+
+/// Caps the number of concurrent requests per host for an agent.
+pub(crate) struct HostLimiter {
+    max: usize,
+    slow_start: bool,
+    hosts: Mutex<HashMap<String, HostState>>,
+    freed: Condvar,
+}
+
+struct HostState {
+    in_flight: usize,
+    /// The current cap, which is below max while slow starting.
+    window: usize,
+    /// When a request to the host last ended, to evict the longest idle.
+    last_used: Instant,
+}
+
+/// A slot for one in-flight request. The slot is freed on drop.
+pub(crate) struct HostPermit {
+    limiter: Arc<HostLimiter>,
+    host: String,
+    succeeded: bool,
+}
+
+impl HostLimiter {
+    /// Creates a limiter, provided max is not 0.
+    ///
+    /// With slow start, a host begins at one request in flight, and the window grows by
+    /// one for each request that succeeds, i.e. it doubles every round trip.
+    pub fn new(max: usize, slow_start: bool) -> Option<Self> {
+        if max == 0 {
+            return None;
+        }
+
+        Some(HostLimiter {
+            max,
+            slow_start,
+            hosts: Mutex::new(HashMap::new()),
+            freed: Condvar::new(),
+        })
+    }
+
+    fn initial_window(&self) -> usize {
+        if self.slow_start {
+            1
+        } else {
+            self.max
+        }
+    }
+
+    /// Block until there is a free slot for the host.
+    pub fn acquire(
+        self: &Arc<Self>,
+        host: &str,
+        timeout: NextTimeout,
+    ) -> Result<HostPermit, Error> {
+        let host = host.to_ascii_lowercase();
+
+        let deadline = match timeout.after {
+            Duration::Exact(v) => Instant::now().checked_add(v),
+            Duration::NotHappening => None,
+        };
+
+        let mut hosts = self.hosts.lock().unwrap();
+
+        loop {
+            let state = hosts.entry(host.clone()).or_insert_with(|| HostState {
+                in_flight: 0,
+                window: self.initial_window(),
+                last_used: Instant::now(),
+            });
+
+            if state.in_flight < state.window {
+                state.in_flight += 1;
+                return Ok(HostPermit {
+                    limiter: self.clone(),
+                    host,
+                    succeeded: false,
+                });
+            }
+
+            debug!("Wait for free slot to: {}", host);
+
+            hosts = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return Err(Error::Timeout(timeout.reason));
+                    }
+                    self.freed.wait_timeout(hosts, left).unwrap().0
+                }
+                None => self.freed.wait(hosts).unwrap(),
+            };
+        }
+    }
+}
+
+impl HostPermit {
+    /// Mark the request as successful, which widens the slow start window.
+    pub fn succeeded(&mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for HostPermit {
+    fn drop(&mut self) {
+        let limiter = &self.limiter;
+        let mut hosts = limiter.hosts.lock().unwrap();
+
+        if let Some(state) = hosts.get_mut(&self.host) {
+            state.in_flight -= 1;
+            state.last_used = Instant::now();
+
+            state.window = if self.succeeded {
+                (state.window + 1).min(limiter.max)
+            } else {
+                // Start over after a failure.
+                limiter.initial_window()
+            };
+
+            // The window must be kept, or slow start would never get past 1.
+            if state.in_flight == 0 && state.window == limiter.initial_window() {
+                hosts.remove(&self.host);
+            }
+        }
+
+        evict_idle(&mut hosts);
+
+        self.limiter.freed.notify_all();
+    }
+}
+
+/// Forget the longest idle host once there are too many, which then slow starts again.
+fn evict_idle(hosts: &mut HashMap<String, HostState>) {
+    let idle = hosts.values().filter(|s| s.in_flight == 0).count();
+    if idle <= MAX_IDLE_HOSTS {
+        return;
+    }
+
+    let oldest = hosts
+        .iter()
+        .filter(|(_, s)| s.in_flight == 0)
+        .min_by_key(|(_, s)| s.last_used)
+        .map(|(h, _)| h.clone());
+
+    if let Some(host) = oldest {
+        hosts.remove(&host);
+    }
+}
+
+impl fmt::Debug for HostLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostLimiter")
+            .field("max", &self.max)
+            .field("slow_start", &self.slow_start)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for HostPermit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HostPermit").field(&self.host).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+    use std::time::Duration as StdDuration;
+
+    use super::*;
+    use crate::Timeout;
+
+    fn timeout(after: Duration) -> NextTimeout {
+        NextTimeout {
+            after,
+            reason: Timeout::Global,
+        }
+    }
+
+    #[test]
+    fn zero_is_no_limit() {
+        assert!(HostLimiter::new(0, false).is_none());
+    }
+
+    #[test]
+    fn hosts_are_separate() {
+        let limiter = Arc::new(HostLimiter::new(1, false).unwrap());
+        let _a = limiter
+            .acquire("a.test", timeout(Duration::NotHappening))
+            .unwrap();
+        let _b = limiter
+            .acquire("b.test", timeout(Duration::NotHappening))
+            .unwrap();
+    }
+
+    #[test]
+    fn full_host_times_out() {
+        let limiter = Arc::new(HostLimiter::new(1, false).unwrap());
+        let _a = limiter
+            .acquire("a.test", timeout(Duration::NotHappening))
+            .unwrap();
+
+        let short = Duration::Exact(StdDuration::from_millis(10));
+        let err = limiter.acquire("A.TEST", timeout(short)).unwrap_err();
+        assert!(matches!(err, Error::Timeout(Timeout::Global)));
+    }
+
+    #[test]
+    fn dropped_permit_frees_slot() {
+        let limiter = Arc::new(HostLimiter::new(1, false).unwrap());
+        let permit = limiter
+            .acquire("a.test", timeout(Duration::NotHappening))
+            .unwrap();
+
+        let limiter2 = limiter.clone();
+        let waiter = thread::spawn(move || {
+            limiter2
+                .acquire("a.test", timeout(Duration::NotHappening))
+                .is_ok()
+        });
+
+        thread::sleep(StdDuration::from_millis(20));
+        drop(permit);
+
+        assert!(waiter.join().unwrap());
+        assert!(limiter.hosts.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn slow_start_widens_on_success() {
+        let limiter = Arc::new(HostLimiter::new(4, true).unwrap());
+        let short = Duration::Exact(StdDuration::from_millis(10));
+
+        let mut a = limiter.acquire("a.test", timeout(short)).unwrap();
+        assert!(limiter.acquire("a.test", timeout(short)).is_err());
+        a.succeeded();
+        drop(a);
+
+        // 1 -> 2
+        let mut a = limiter.acquire("a.test", timeout(short)).unwrap();
+        let mut b = limiter.acquire("a.test", timeout(short)).unwrap();
+        assert!(limiter.acquire("a.test", timeout(short)).is_err());
+        a.succeeded();
+        b.succeeded();
+        drop((a, b));
+
+        // 2 -> 4, which is the max
+        let permits: Vec<_> = (0..4)
+            .map(|_| limiter.acquire("a.test", timeout(short)).unwrap())
+            .collect();
+        assert!(limiter.acquire("a.test", timeout(short)).is_err());
+        drop(permits);
+
+        // Failures start over.
+        let _a = limiter.acquire("a.test", timeout(short)).unwrap();
+        assert!(limiter.acquire("a.test", timeout(short)).is_err());
+    }
+
+    #[test]
+    fn idle_hosts_are_evicted() {
+        let limiter = Arc::new(HostLimiter::new(4, true).unwrap());
+
+        for i in 0..MAX_IDLE_HOSTS + 10 {
+            let host = format!("{}.test", i);
+            let mut a = limiter
+                .acquire(&host, timeout(Duration::NotHappening))
+                .unwrap();
+            a.succeeded();
+        }
+
+        assert_eq!(limiter.hosts.lock().unwrap().len(), MAX_IDLE_HOSTS);
+    }
+}
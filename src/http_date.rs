@@ -0,0 +1,157 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parse an HTTP-date in any of the three formats allowed by RFC 9110.
+///
+/// ```text
+/// Sun, 06 Nov 1994 08:49:37 GMT    ; IMF-fixdate
+/// Sunday, 06-Nov-94 08:49:37 GMT   ; obsolete RFC 850 format
+/// Sun Nov  6 08:49:37 1994         ; ANSI C's asctime() format
+/// ```
+///
+/// The day name is not checked against the date.
+pub(crate) fn parse_http_date(s: &str) -> Option<SystemTime> {
+    let s = s.trim();
+
+    let (day, month, year, time) = if let Some((_, rest)) = s.split_once(", ") {
+        if rest.contains('-') {
+            // 06-Nov-94 08:49:37 GMT
+            let (date, time) = rest.strip_suffix(" GMT")?.split_once(' ')?;
+            let mut parts = date.split('-');
+            let day = parts.next()?;
+            let month = parts.next()?;
+            let year = parts.next()?;
+            if parts.next().is_some() || year.len() != 2 {
+                return None;
+            }
+            // RFC 9110 says to read years more than 50 years in the future as the
+            // past, which for two-digit years is close enough to this.
+            let year: u64 = year.parse().ok()?;
+            let year = if year < 70 { 2000 + year } else { 1900 + year };
+            (day, month, year, time)
+        } else {
+            // 06 Nov 1994 08:49:37 GMT
+            let mut parts = rest.strip_suffix(" GMT")?.split(' ');
+            let day = parts.next()?;
+            let month = parts.next()?;
+            let year = parts.next()?;
+            let time = parts.next()?;
+            if parts.next().is_some() || year.len() != 4 {
+                return None;
+            }
+            (day, month, year.parse().ok()?, time)
+        }
+    } else {
+        // Sun Nov  6 08:49:37 1994
+        let mut parts = s.split_whitespace();
+        let _day_name = parts.next()?;
+        let month = parts.next()?;
+        let day = parts.next()?;
+        let time = parts.next()?;
+        let year = parts.next()?;
+        if parts.next().is_some() || year.len() != 4 {
+            return None;
+        }
+        (day, month, year.parse().ok()?, time)
+    };
+
+    if day.is_empty() || day.len() > 2 {
+        return None;
+    }
+    let day: u64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+
+    let mut hms = time.split(':');
+    let hour: u64 = two_digits(hms.next()?)?;
+    let min: u64 = two_digits(hms.next()?)?;
+    // 60 is allowed for leap seconds.
+    let sec: u64 = two_digits(hms.next()?)?;
+    if hms.next().is_some() || hour > 23 || min > 59 || sec > 60 {
+        return None;
+    }
+
+    if year < 1970 || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let days = days_since_epoch(year, month, day);
+    let secs = days * 86_400 + hour * 3600 + min * 60 + sec;
+
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+fn two_digits(s: &str) -> Option<u64> {
+    if s.len() != 2 {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_leap_year(year: u64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a civil date, for years from 1970 and months 1 to 12.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html>.
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    const EXPECTED: u64 = 784_111_777;
+
+    fn secs(s: &str) -> Option<u64> {
+        parse_http_date(s).map(|t| t.duration_since(UNIX_EPOCH).unwrap().as_secs())
+    }
+
+    #[test]
+    fn imf_fixdate() {
+        assert_eq!(secs("Sun, 06 Nov 1994 08:49:37 GMT"), Some(EXPECTED));
+        assert_eq!(secs("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(secs("Tue, 29 Feb 2000 12:00:00 GMT"), Some(951_825_600));
+    }
+
+    #[test]
+    fn rfc850() {
+        assert_eq!(secs("Sunday, 06-Nov-94 08:49:37 GMT"), Some(EXPECTED));
+    }
+
+    #[test]
+    fn asctime() {
+        assert_eq!(secs("Sun Nov  6 08:49:37 1994"), Some(EXPECTED));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(secs(""), None);
+        assert_eq!(secs("Sun, 06 Nov 1994 08:49:37 CET"), None);
+        assert_eq!(secs("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+        assert_eq!(secs("Sun, 31 Nov 1994 08:49:37 GMT"), None);
+        assert_eq!(secs("Sun, 06 Nov 1994 24:00:00 GMT"), None);
+        assert_eq!(secs("Sun, 06 Nov 1994 8:49:37 GMT"), None);
+        assert_eq!(secs("Fri, 29 Feb 2001 00:00:00 GMT"), None);
+    }
+}
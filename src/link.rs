@@ -0,0 +1,293 @@
+use std::fmt;
+
+use http::header::{HeaderMap, LINK};
+use http::Uri;
+
+/// A link from the `link` response header.
+///
+/// See [`ResponseExt::links()`](crate::ResponseExt::links).
+///
+/// ```text
+///     Link: <https://api.example.test/items?page=2>; rel="next"
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Link {
+    uri: Uri,
+    rel: Option<String>,
+    params: Vec<(String, String)>,
+}
+
+impl Link {
+    /// The link target.
+    ///
+    /// Relative targets are resolved against the uri of the request, i.e. the last
+    /// uri when following redirects.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// The `rel` parameter, such as `"next"`.
+    ///
+    /// This can hold several space separated relation types, use [`Link::has_rel()`]
+    /// to check for one of them.
+    pub fn rel(&self) -> Option<&str> {
+        self.rel.as_deref()
+    }
+
+    /// Whether the `rel` parameter contains the relation type.
+    ///
+    /// Relation types are compared ignoring ASCII case.
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rel
+            .as_deref()
+            .map(|r| {
+                r.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case(rel))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Any other parameter of the link, such as `title`.
+    ///
+    /// Parameter names are compared ignoring ASCII case. Quoted values are unquoted.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Link")
+            .field("uri", &self.uri)
+            .field("rel", &self.rel)
+            .finish()
+    }
+}
+
+/// Parse all `link` headers. Malformed links are skipped.
+pub(crate) fn parse_links(headers: &HeaderMap, base: &Uri) -> Vec<Link> {
+    let mut links = Vec::new();
+
+    for value in headers.get_all(LINK) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        parse_link_value(value, base, &mut links);
+    }
+
+    links
+}
+
+fn parse_link_value(mut s: &str, base: &Uri, links: &mut Vec<Link>) {
+    loop {
+        s = s.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+
+        let Some(rest) = s.strip_prefix('<') else {
+            return;
+        };
+        let Some((target, rest)) = rest.split_once('>') else {
+            return;
+        };
+        s = rest;
+
+        let mut rel = None;
+        let mut params = Vec::new();
+
+        // ; rel="next"; title=foo
+        loop {
+            s = s.trim_start();
+            let Some(rest) = s.strip_prefix(';') else {
+                break;
+            };
+
+            let rest = rest.trim_start();
+            let name_end = rest.find(['=', ';', ',']).unwrap_or(rest.len());
+            let name = rest[..name_end].trim();
+            let rest = &rest[name_end..];
+
+            let (value, rest) = match rest.strip_prefix('=') {
+                Some(v) => parse_param_value(v.trim_start()),
+                None => (String::new(), rest),
+            };
+            s = rest;
+
+            if name.eq_ignore_ascii_case("rel") {
+                // Only the first rel counts.
+                if rel.is_none() {
+                    rel = Some(value);
+                }
+            } else if !name.is_empty() {
+                params.push((name.to_string(), value));
+            }
+        }
+
+        if let Some(uri) = resolve(base, target.trim()) {
+            links.push(Link { uri, rel, params });
+        }
+    }
+}
+
+/// Returns the value and the rest of the input.
+fn parse_param_value(s: &str) -> (String, &str) {
+    let Some(quoted) = s.strip_prefix('"') else {
+        let end = s.find([';', ',']).unwrap_or(s.len());
+        return (s[..end].trim().to_string(), &s[end..]);
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, &quoted[i + 1..]),
+            '\\' => {
+                if let Some((_, c)) = chars.next() {
+                    value.push(c);
+                }
+            }
+            _ => value.push(c),
+        }
+    }
+
+    // Unterminated quote, take the rest.
+    (value, "")
+}
+
+/// Resolve a uri reference against a base uri as per RFC 3986 section 5.2.
+pub(crate) fn resolve(base: &Uri, reference: &str) -> Option<Uri> {
+    if let Ok(uri) = reference.parse::<Uri>() {
+        if uri.scheme().is_some() {
+            return Some(uri);
+        }
+    }
+
+    let scheme = base.scheme_str()?;
+    let authority = base.authority()?.as_str();
+
+    // Fragments are not part of the uri sent in requests.
+    let reference = reference.split('#').next().unwrap_or_default();
+
+    let resolved = if let Some(rest) = reference.strip_prefix("//") {
+        format!("{}://{}", scheme, rest)
+    } else if reference.starts_with('/') {
+        let (path, query) = split_query(reference);
+        format!(
+            "{}://{}{}{}",
+            scheme,
+            authority,
+            remove_dot_segments(path),
+            query
+        )
+    } else if reference.is_empty() {
+        base.to_string()
+    } else if reference.starts_with('?') {
+        format!("{}://{}{}{}", scheme, authority, base.path(), reference)
+    } else {
+        let (path, query) = split_query(reference);
+        let base_path = base.path();
+        let dir = &base_path[..base_path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+        let merged = format!("{}{}", if dir.is_empty() { "/" } else { dir }, path);
+        format!(
+            "{}://{}{}{}",
+            scheme,
+            authority,
+            remove_dot_segments(&merged),
+            query
+        )
+    };
+
+    resolved.parse().ok()
+}
+
+fn split_query(s: &str) -> (&str, &str) {
+    match s.find('?') {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    }
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+
+    let mut segments = path.split('/').skip(1).peekable();
+
+    while let Some(segment) = segments.next() {
+        let last = segments.peek().is_none();
+        match segment {
+            "." => {
+                if last {
+                    out.push("");
+                }
+            }
+            ".." => {
+                out.pop();
+                if last {
+                    out.push("");
+                }
+            }
+            s => out.push(s),
+        }
+    }
+
+    format!("/{}", out.join("/"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn links(value: &str) -> Vec<Link> {
+        let mut headers = HeaderMap::new();
+        headers.insert(LINK, value.parse().unwrap());
+        let base: Uri = "https://api.test/v1/items?page=1".parse().unwrap();
+        parse_links(&headers, &base)
+    }
+
+    #[test]
+    fn github_style() {
+        let l = links(
+            "<https://api.test/v1/items?page=2>; rel=\"next\", \
+             <https://api.test/v1/items?page=5>; rel=\"last\"",
+        );
+        assert_eq!(l.len(), 2);
+        assert_eq!(l[0].uri(), "https://api.test/v1/items?page=2");
+        assert!(l[0].has_rel("next"));
+        assert_eq!(l[1].rel(), Some("last"));
+    }
+
+    #[test]
+    fn relative_targets() {
+        let l = links(
+            "</v2/items>; rel=next, <other?page=3>; rel=prev, <../up>; rel=up, <?page=9>; rel=last",
+        );
+        assert_eq!(l[0].uri(), "https://api.test/v2/items");
+        assert_eq!(l[1].uri(), "https://api.test/v1/other?page=3");
+        assert_eq!(l[2].uri(), "https://api.test/up");
+        assert_eq!(l[3].uri(), "https://api.test/v1/items?page=9");
+    }
+
+    #[test]
+    fn quoted_params() {
+        let l = links(r#"<https://x.test/>; rel="next last"; title="a, \"b\"; c""#);
+        assert_eq!(l.len(), 1);
+        assert!(l[0].has_rel("LAST"));
+        assert!(!l[0].has_rel("prev"));
+        assert_eq!(l[0].param("Title"), Some(r#"a, "b"; c"#));
+    }
+
+    #[test]
+    fn malformed_is_skipped() {
+        assert!(links("no brackets; rel=next").is_empty());
+        assert!(links("<https://x.test/").is_empty());
+    }
+
+    #[test]
+    fn dot_segments() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("/a/.."), "/");
+        assert_eq!(remove_dot_segments("/../a"), "/a");
+    }
+}
@@ -177,6 +177,10 @@ impl MiddlewareChain {
 
         chain.push(Box::new(mw));
     }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
 }
 
 /// Continuation of a [`Middleware`] chain.
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use http::{header, Request, Response, Uri};
+
+use crate::body::Body;
+use crate::{Agent, Error, ResponseExt, SendBody};
+
+/// Iterator over the pages of a paginated API.
+///
+/// Created by [`RequestBuilder::pages()`](crate::RequestBuilder::pages). Each page is
+/// requested when the iterator is advanced, and the next page is found from the
+/// `rel="next"` link of the response (see [`ResponseExt::next_link()`]). The iteration ends
+/// when a response has no next link, or after the first error.
+///
+/// The headers and configuration of the initial request are used for every page.
+/// The exception is the `authorization` header, which is dropped when a next link
+/// leads to another host, and then stays dropped for the remaining pages.
+pub struct Pages {
+    agent: Agent,
+    next: Option<Result<Request<()>, Error>>,
+}
+
+impl Pages {
+    pub(crate) fn new(agent: Agent, request: Result<Request<()>, Error>) -> Self {
+        Pages {
+            agent,
+            next: Some(request),
+        }
+    }
+}
+
+impl Iterator for Pages {
+    type Item = Result<Response<Body>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let request = match self.next.take()? {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let template = request.clone();
+
+        let response = match self.agent.run_via_middleware(request, SendBody::none()) {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if let Some(uri) = response.next_link() {
+            // A page linking to itself would never end.
+            if &uri != template.uri() {
+                self.next = Some(Ok(next_request(template, uri)));
+            }
+        }
+
+        Some(Ok(response))
+    }
+}
+
+fn next_request(mut request: Request<()>, uri: Uri) -> Request<()> {
+    let same_host =
+        request.uri().scheme() == uri.scheme() && request.uri().authority() == uri.authority();
+
+    if !same_host {
+        request.headers_mut().remove(header::AUTHORIZATION);
+    }
+
+    *request.uri_mut() = uri;
+
+    request
+}
+
+impl fmt::Debug for Pages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let next = self.next.as_ref().and_then(|r| r.as_ref().ok());
+        f.debug_struct("Pages")
+            .field("next", &next.map(|r| r.uri()))
+            .finish()
+    }
+}
@@ -1,10 +1,11 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt;
 use std::sync::{Arc, Mutex, Weak};
 
 use http::uri::{Authority, Scheme};
 use http::Uri;
 
+use crate::byte_count::ByteCounter;
 use crate::config::Config;
 use crate::proxy::Proxy;
 use crate::transport::time::{Duration, Instant};
@@ -15,13 +16,19 @@ use crate::Error;
 pub(crate) struct ConnectionPool {
     connector: Box<dyn Connector>,
     pool: Arc<Mutex<Pool>>,
+    byte_counter: Option<Arc<ByteCounter>>,
 }
 
 impl ConnectionPool {
-    pub fn new(connector: impl Connector, config: &Config) -> Self {
+    pub fn new(
+        connector: impl Connector,
+        config: &Config,
+        byte_counter: Option<Arc<ByteCounter>>,
+    ) -> Self {
         ConnectionPool {
             connector: Box::new(connector),
             pool: Arc::new(Mutex::new(Pool::new(config))),
+            byte_counter,
         }
     }
 
@@ -29,16 +36,26 @@ impl ConnectionPool {
         &self,
         details: &ConnectionDetails,
         max_idle_age: Duration,
+        fresh: bool,
     ) -> Result<Connection, Error> {
-        let key = PoolKey::new(details.uri, &details.config.proxy);
+        let key = PoolKey::new(
+            details.uri,
+            &details.config.proxy,
+            details.config.connect_port,
+        );
 
         {
             let mut pool = self.pool.lock().unwrap();
             pool.purge(details.now);
 
-            if let Some(conn) = pool.get(&key, max_idle_age, details.now) {
-                debug!("Use pooled: {:?}", key);
-                return Ok(conn);
+            if !fresh {
+                let probe = details.config.probe_pooled_connections;
+                if let Some(mut conn) = pool.get(&key, max_idle_age, details.now, probe) {
+                    debug!("Use pooled: {:?}", key);
+                    conn.reused = true;
+                    conn.in_use = Some(pool.check_out(&self.pool, &key));
+                    return Ok(conn);
+                }
             }
         }
 
@@ -47,17 +64,51 @@ impl ConnectionPool {
             .connect(details, None)?
             .ok_or(Error::ConnectionFailed)?;
 
+        let in_use = self.pool.lock().unwrap().check_out(&self.pool, &key);
+
         let conn = Connection {
             transport,
             key,
             last_use: details.now,
             pool: Arc::downgrade(&self.pool),
+            reused: false,
+            in_use: Some(in_use),
+            byte_counter: self.byte_counter.clone(),
             position_per_host: None,
         };
 
         Ok(conn)
     }
 
+    pub fn stats(&self) -> PoolStats {
+        let pool = self.pool.lock().unwrap();
+
+        // (idle, in_use) per host
+        let mut per_host: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+
+        for c in &pool.lru {
+            per_host.entry(c.key.host()).or_default().0 += 1;
+        }
+        for (host, count) in &pool.in_use {
+            per_host.entry(host).or_default().1 += count;
+        }
+
+        let hosts = per_host
+            .into_iter()
+            .map(|(host, (idle, in_use))| HostPoolStats {
+                host: host.to_string(),
+                idle,
+                in_use,
+            })
+            .collect();
+
+        PoolStats {
+            idle: pool.lru.len(),
+            in_use: pool.in_use.values().sum(),
+            hosts,
+        }
+    }
+
     #[cfg(test)]
     pub fn pool_count(&self) -> usize {
         let lock = self.pool.lock().unwrap();
@@ -71,6 +122,15 @@ pub(crate) struct Connection {
     last_use: Instant,
     pool: Weak<Mutex<Pool>>,
 
+    /// Whether this connection was taken from the pool, as opposed to newly opened.
+    reused: bool,
+
+    /// Counts the connection as in use until it goes back to the pool, or is dropped.
+    /// Connections in the pool have `None`.
+    in_use: Option<InUse>,
+
+    byte_counter: Option<Arc<ByteCounter>>,
+
     /// Used to prune max_idle_connections_by_host.
     ///
     /// # Example
@@ -98,7 +158,25 @@ impl Connection {
     }
 
     pub fn transmit_output(&mut self, amount: usize, timeout: NextTimeout) -> Result<(), Error> {
-        self.transport.transmit_output(amount, timeout)
+        self.transport.transmit_output(amount, timeout)?;
+        if let Some(c) = &self.byte_counter {
+            c.add_sent(amount);
+        }
+        Ok(())
+    }
+
+    pub fn transmit_output_vectored(
+        &mut self,
+        amount: usize,
+        data: &[u8],
+        timeout: NextTimeout,
+    ) -> Result<(), Error> {
+        self.transport
+            .transmit_output_vectored(amount, data, timeout)?;
+        if let Some(c) = &self.byte_counter {
+            c.add_sent(amount + data.len());
+        }
+        Ok(())
     }
 
     pub fn await_input(&mut self, timeout: NextTimeout) -> Result<bool, Error> {
@@ -106,12 +184,43 @@ impl Connection {
     }
 
     pub fn consume_input(&mut self, amount: usize) {
-        self.transport.buffers().input_consume(amount)
+        self.transport.buffers().input_consume(amount);
+        if let Some(c) = &self.byte_counter {
+            c.add_received(amount);
+        }
+    }
+
+    #[cfg(feature = "_tls")]
+    pub fn alpn_protocol(&self) -> Option<Vec<u8>> {
+        self.transport.alpn_protocol()
+    }
+
+    #[cfg(feature = "_tls")]
+    pub fn peer_certificates(&self) -> Option<Vec<Vec<u8>>> {
+        self.transport.peer_certificates()
+    }
+
+    #[cfg(feature = "_tls")]
+    pub fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+        crate::tls::TlsInfo::new(
+            self.transport.tls_protocol_version(),
+            self.transport.tls_cipher_suite(),
+            self.transport.alpn_protocol(),
+        )
+    }
+
+    pub fn is_reused(&self) -> bool {
+        self.reused
     }
 
-    pub fn close(self) {
+    /// Take the transport out of the connection, which then never goes back to the pool.
+    pub fn into_transport(self) -> Box<dyn Transport> {
+        self.transport
+    }
+
+    pub fn close(mut self) {
         debug!("Close: {:?}", self.key);
-        // Just consume self.
+        self.transport.shutdown();
     }
 
     pub fn reuse(mut self, now: Instant) {
@@ -125,6 +234,9 @@ impl Connection {
         }
         self.last_use = now;
 
+        // Released before taking the lock below, since releasing locks the pool.
+        self.in_use = None;
+
         let Some(arc) = self.pool.upgrade() else {
             debug!("Pool gone: {:?}", self.key);
             return;
@@ -138,8 +250,9 @@ impl Connection {
         pool.purge(now);
     }
 
+    /// Time since the connection was last returned to the pool.
     fn age(&self, now: Instant) -> Duration {
-        now.duration_since(now)
+        now.duration_since(self.last_use)
     }
 
     fn is_open(&mut self) -> bool {
@@ -158,29 +271,37 @@ impl Connection {
 /// ```
 ///
 /// It's correct to include username/password since connections with differing such and
-/// the same host/port must not be mixed up.
+/// the same host/port must not be mixed up. An overridden connect port is also part of
+/// the key, since it means connecting elsewhere.
 ///
 #[derive(Clone, PartialEq, Eq)]
 struct PoolKey(Arc<PoolKeyInner>);
 
 impl PoolKey {
-    fn new(uri: &Uri, proxy: &Option<Proxy>) -> Self {
+    fn new(uri: &Uri, proxy: &Option<Proxy>, connect_port: Option<u16>) -> Self {
         let inner = PoolKeyInner(
             uri.scheme().expect("uri with scheme").clone(),
             uri.authority().expect("uri with authority").clone(),
             proxy.clone(),
+            connect_port,
         );
 
         PoolKey(Arc::new(inner))
     }
+
+    fn host(&self) -> &str {
+        self.0 .1.host()
+    }
 }
 
 #[derive(PartialEq, Eq)]
-struct PoolKeyInner(Scheme, Authority, Option<Proxy>);
+struct PoolKeyInner(Scheme, Authority, Option<Proxy>, Option<u16>);
 
 #[derive(Debug)]
 struct Pool {
     lru: VecDeque<Connection>,
+    /// Connections handed out and not yet returned, per host.
+    in_use: HashMap<String, usize>,
     max_idle_connections: usize,
     max_idle_connections_per_host: usize,
     max_idle_age: Duration,
@@ -190,6 +311,7 @@ impl Pool {
     fn new(config: &Config) -> Self {
         Pool {
             lru: VecDeque::new(),
+            in_use: HashMap::new(),
             max_idle_connections: config.max_idle_connections,
             max_idle_connections_per_host: config.max_idle_connections_per_host,
             max_idle_age: config.max_idle_age.into(),
@@ -244,16 +366,40 @@ impl Pool {
         }
     }
 
+    fn check_out(&mut self, arc: &Arc<Mutex<Pool>>, key: &PoolKey) -> InUse {
+        let host = key.host().to_string();
+        *self.in_use.entry(host.clone()).or_default() += 1;
+        InUse {
+            pool: Arc::downgrade(arc),
+            host,
+        }
+    }
+
+    fn check_in(&mut self, host: &str) {
+        if let Some(count) = self.in_use.get_mut(host) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_use.remove(host);
+            }
+        }
+    }
+
     fn add(&mut self, conn: Connection) {
         self.lru.push_back(conn)
     }
 
-    fn get(&mut self, key: &PoolKey, max_idle_age: Duration, now: Instant) -> Option<Connection> {
+    fn get(
+        &mut self,
+        key: &PoolKey,
+        max_idle_age: Duration,
+        now: Instant,
+        probe: bool,
+    ) -> Option<Connection> {
         while let Some(i) = self.lru.iter().position(|c| c.key == *key) {
             let mut conn = self.lru.remove(i).unwrap(); // unwrap ok since we just got the position
 
             // Before we release the connection, we probe that it appears to still work.
-            if !conn.is_open() {
+            if probe && !conn.is_open() {
                 // This connection is broken. Try find another one.
                 continue;
             }
@@ -271,6 +417,76 @@ impl Pool {
     }
 }
 
+/// Marks a connection as in use. Checks it back in on drop.
+///
+/// Must not be dropped while holding the pool lock.
+struct InUse {
+    pool: Weak<Mutex<Pool>>,
+    host: String,
+}
+
+impl Drop for InUse {
+    fn drop(&mut self) {
+        if let Some(arc) = self.pool.upgrade() {
+            arc.lock().unwrap().check_in(&self.host);
+        }
+    }
+}
+
+/// The connections of an agent's pool.
+///
+/// See [`Agent::pool_stats()`](crate::Agent::pool_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    idle: usize,
+    in_use: usize,
+    hosts: Vec<HostPoolStats>,
+}
+
+impl PoolStats {
+    /// Connections idle in the pool, waiting to be reused.
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+
+    /// Connections currently used by a request or a response body.
+    pub fn in_use(&self) -> usize {
+        self.in_use
+    }
+
+    /// The counts per host, sorted by host.
+    pub fn hosts(&self) -> &[HostPoolStats] {
+        &self.hosts
+    }
+}
+
+/// The connections of an agent's pool for one host.
+///
+/// See [`PoolStats::hosts()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostPoolStats {
+    host: String,
+    idle: usize,
+    in_use: usize,
+}
+
+impl HostPoolStats {
+    /// The host, as in the request uri.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Connections to the host idle in the pool.
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+
+    /// Connections to the host currently in use.
+    pub fn in_use(&self) -> usize {
+        self.in_use
+    }
+}
+
 impl fmt::Debug for ConnectionPool {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ConnectionPool")
@@ -294,6 +510,7 @@ impl fmt::Debug for PoolKey {
             .field("scheme", &self.0 .0)
             .field("authority", &DebugAuthority(&self.0 .1))
             .field("proxy", &self.0 .2)
+            .field("connect_port", &self.0 .3)
             .finish()
     }
 }
@@ -305,6 +522,6 @@ mod test {
     #[test]
     fn poolkey_new() {
         // Test that PoolKey::new() does not panic on unrecognized schemes.
-        PoolKey::new(&Uri::from_static("zzz://example.com"), &None);
+        PoolKey::new(&Uri::from_static("zzz://example.com"), &None, None);
     }
 }
@@ -6,9 +6,10 @@ use std::fmt;
 use std::io::Write;
 use std::sync::Arc;
 
-use http::{StatusCode, Uri};
+use http::header::{HeaderName, HeaderValue, PROXY_AUTHORIZATION, USER_AGENT};
+use http::{HeaderMap, Response, StatusCode, Uri};
 
-use crate::transport::{ConnectionDetails, Connector, Transport, TransportAdapter};
+use crate::transport::{ConnectionDetails, Connector, NextTimeout, Transport, TransportAdapter};
 use crate::util::{AuthorityExt, DebugUri, SchemeExt, UriExt};
 use crate::Error;
 
@@ -213,39 +214,18 @@ impl Connector for ConnectProxyConnector {
                 .port_u16()
                 .unwrap_or(uri.scheme().unwrap().default_port().unwrap());
 
-            write!(w, "CONNECT {}:{} HTTP/1.1\r\n", host, port)?;
-            write!(w, "Host: {}:{}\r\n", host, port)?;
-            write!(w, "User-Agent: {}\r\n", details.config.get_user_agent())?;
-            write!(w, "Proxy-Connection: Keep-Alive\r\n")?;
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                HeaderName::from_static("proxy-connection"),
+                HeaderValue::from_static("Keep-Alive"),
+            );
 
-            let use_creds = proxy.username().is_some() || proxy.password().is_some();
-
-            if use_creds {
-                let user = proxy.username().unwrap_or_default();
-                let pass = proxy.password().unwrap_or_default();
-                let creds = BASE64_STANDARD.encode(format!("{}:{}", user, pass));
-                write!(w, "Proxy-Authorization: basic {}\r\n", creds)?;
-            }
-
-            write!(w, "\r\n")?;
-            w.flush()?;
+            let target = format!("{}:{}", host, port);
+            let user_agent = details.config.get_user_agent();
+            write_connect(&mut w, proxy, &target, user_agent, &headers)?;
 
             let mut transport = w.into_inner();
-
-            let response = loop {
-                let made_progress = transport.await_input(details.timeout)?;
-                let buffers = transport.buffers();
-                let input = buffers.input();
-                let Some((used_input, response)) = try_parse_response::<20>(input)? else {
-                    if !made_progress {
-                        let reason = "proxy server did not respond".to_string();
-                        return Err(Error::ConnectProxyFailed(reason));
-                    }
-                    continue;
-                };
-                buffers.input_consume(used_input);
-                break response;
-            };
+            let response = read_connect_response(&mut *transport, details.timeout)?;
 
             match response.status() {
                 StatusCode::OK => {
@@ -264,6 +244,67 @@ impl Connector for ConnectProxyConnector {
     }
 }
 
+/// Write a `CONNECT` request for `target` to the proxy.
+///
+/// `User-Agent` and `Proxy-Authorization` are only added when not in `headers`.
+pub(crate) fn write_connect(
+    w: &mut TransportAdapter,
+    proxy: &Proxy,
+    target: &str,
+    user_agent: &str,
+    headers: &HeaderMap,
+) -> Result<(), Error> {
+    write!(w, "CONNECT {} HTTP/1.1\r\n", target)?;
+    write!(w, "Host: {}\r\n", target)?;
+
+    if !headers.contains_key(USER_AGENT) {
+        write!(w, "User-Agent: {}\r\n", user_agent)?;
+    }
+
+    let use_creds = proxy.username().is_some() || proxy.password().is_some();
+
+    if use_creds && !headers.contains_key(PROXY_AUTHORIZATION) {
+        let user = proxy.username().unwrap_or_default();
+        let pass = proxy.password().unwrap_or_default();
+        let creds = BASE64_STANDARD.encode(format!("{}:{}", user, pass));
+        write!(w, "Proxy-Authorization: basic {}\r\n", creds)?;
+    }
+
+    for (name, value) in headers {
+        write!(w, "{}: ", name)?;
+        w.write_all(value.as_bytes())?;
+        write!(w, "\r\n")?;
+    }
+
+    write!(w, "\r\n")?;
+    w.flush()?;
+
+    Ok(())
+}
+
+/// Read the proxy response head to a `CONNECT`.
+///
+/// Whatever comes after the head is left in the input buffers of the transport.
+pub(crate) fn read_connect_response(
+    transport: &mut dyn Transport,
+    timeout: NextTimeout,
+) -> Result<Response<()>, Error> {
+    loop {
+        let made_progress = transport.await_input(timeout)?;
+        let buffers = transport.buffers();
+        let input = buffers.input();
+        let Some((used_input, response)) = try_parse_response::<100>(input)? else {
+            if !made_progress {
+                let reason = "proxy server did not respond".to_string();
+                return Err(Error::ConnectProxyFailed(reason));
+            }
+            continue;
+        };
+        buffers.input_consume(used_input);
+        return Ok(response);
+    }
+}
+
 impl TryFrom<&str> for Proto {
     type Error = Error;
 
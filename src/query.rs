@@ -29,6 +29,14 @@ impl<'a> QueryParam<'a> {
         }
     }
 
+    /// A parameter that is already url encoded, such as `key=some%20value`.
+    #[cfg(feature = "urlencoded")]
+    pub fn new_encoded(param: &str) -> QueryParam<'static> {
+        QueryParam {
+            source: Source::Owned(param.to_string()),
+        }
+    }
+
     fn as_str(&self) -> &str {
         match &self.source {
             Source::Borrowed(v) => v,
@@ -0,0 +1,122 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::timings::NextTimeout;
+use crate::Error;
+
+/// Limits the rate of requests for an agent.
+///
+/// A token bucket holding a single token, which means requests are spread out evenly
+/// without any allowance for bursts. Each request reserves the next free slot before
+/// waiting for it, so threads sharing the agent get slots in the order they ask.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    requests_per_second: f64,
+    /// None when the rate is too low for a `Duration`, which fails every request.
+    interval: Option<Duration>,
+    /// Earliest time the next request is allowed to start.
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter, provided the rate is a positive number.
+    pub fn new(requests_per_second: f64) -> Option<Self> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            return None;
+        }
+
+        Some(RateLimiter {
+            requests_per_second,
+            interval: Duration::try_from_secs_f64(1.0 / requests_per_second).ok(),
+            next: Mutex::new(None),
+        })
+    }
+
+    /// Block until the request is allowed to start.
+    ///
+    /// Fails without reserving a slot if the wait would exceed the timeout.
+    pub fn acquire(&self, timeout: NextTimeout) -> Result<(), Error> {
+        let too_low = || Error::InvalidRequestRate(self.requests_per_second);
+        let interval = self.interval.ok_or_else(too_low)?;
+
+        let wait = {
+            let mut next = self.next.lock().unwrap();
+
+            let now = Instant::now();
+            let slot = next.map(|n| n.max(now)).unwrap_or(now);
+            let wait = slot - now;
+
+            if wait > *timeout.after {
+                return Err(Error::Timeout(timeout.reason));
+            }
+
+            *next = Some(slot.checked_add(interval).ok_or_else(too_low)?);
+            wait
+        };
+
+        if !wait.is_zero() {
+            debug!("Rate limited, wait {:?}", wait);
+            thread::sleep(wait);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::time::Duration as TimeoutDuration;
+    use crate::Timeout;
+
+    fn no_timeout() -> NextTimeout {
+        NextTimeout {
+            after: TimeoutDuration::NotHappening,
+            reason: Timeout::Global,
+        }
+    }
+
+    #[test]
+    fn invalid_rate() {
+        assert!(RateLimiter::new(0.0).is_none());
+        assert!(RateLimiter::new(-1.0).is_none());
+        assert!(RateLimiter::new(f64::NAN).is_none());
+    }
+
+    #[test]
+    fn too_low_rate() {
+        for rate in [1e-300, 1e-19] {
+            let limiter = RateLimiter::new(rate).unwrap();
+            let err = limiter.acquire(no_timeout()).unwrap_err();
+            assert!(matches!(err, Error::InvalidRequestRate(r) if r == rate));
+        }
+    }
+
+    #[test]
+    fn spread_out() {
+        let limiter = RateLimiter::new(20.0).unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire(no_timeout()).unwrap();
+        }
+        // First is immediate, then two 50ms intervals.
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn wait_exceeds_timeout() {
+        let limiter = RateLimiter::new(1.0).unwrap();
+        limiter.acquire(no_timeout()).unwrap();
+
+        let timeout = NextTimeout {
+            after: TimeoutDuration::Exact(Duration::from_millis(10)),
+            reason: Timeout::Global,
+        };
+        let start = Instant::now();
+        let err = limiter.acquire(timeout).unwrap_err();
+        assert!(matches!(err, Error::Timeout(Timeout::Global)));
+        // Failing is immediate, there is no point in waiting.
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+}
@@ -1,19 +1,24 @@
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fmt;
+use std::fs::File;
 use std::marker::PhantomData;
+use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
+use std::path::Path;
 
-use http::{HeaderName, HeaderValue, Method, Request, Response, Uri, Version};
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, Uri, Version};
 
 use crate::body::Body;
 use crate::config::{Config, ConfigBuilder, RequestLevelConfig, RequestScope};
+use crate::pages::Pages;
 use crate::query::url_enc;
 use crate::query::{parse_query_params, QueryParam};
 use crate::send_body::AsSendBody;
 use crate::util::private::Private;
 use crate::util::HeaderMapExt;
 use crate::util::UriExt;
-use crate::{Agent, Error, SendBody};
+use crate::{Agent, Error, IntoUri, SendBody};
 
 /// Transparent wrapper around [`http::request::Builder`].
 ///
@@ -22,7 +27,9 @@ use crate::{Agent, Error, SendBody};
 pub struct RequestBuilder<B> {
     agent: Agent,
     builder: http::request::Builder,
-    query_extra: Vec<QueryParam<'static>>,
+    // Like the http builder, an error is held until the request is sent. Also holds
+    // an error converting the uri.
+    query_extra: Result<Vec<QueryParam<'static>>, Error>,
 
     // This is only used in case http::request::Builder contains an error
     // (such as URL parsing error), and the user wants a `.config()`.
@@ -62,6 +69,127 @@ impl<Any> RequestBuilder<Any> {
         self
     }
 
+    /// Sets the `Host` header, instead of deriving it from the URI.
+    ///
+    /// The connection is still made to the host of the URI, which makes this useful for
+    /// testing virtual hosts. Replaces any `Host` header set before.
+    ///
+    /// An empty value means sending no `Host` header at all. HTTP/1.1 servers must
+    /// reject such requests, this is meant for testing with HTTP/1.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let req = ureq::get("http://127.0.0.1:8080/")
+    ///     .host("example.test");
+    /// ```
+    pub fn host<V>(mut self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        if let Some(headers) = self.builder.headers_mut() {
+            headers.remove(header::HOST);
+        }
+        self.builder = self.builder.header(header::HOST, value);
+        self
+    }
+
+    /// Adds a `Prefer` header (RFC 7240), such as `return=minimal`.
+    ///
+    /// Each call adds another preference. Which of these the server honored can be read
+    /// with [`ResponseExt::preference_applied()`](crate::ResponseExt::preference_applied).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let req = ureq::patch("https://httpbin.org/patch")
+    ///     .prefer("return=minimal")
+    ///     .prefer("handling=strict");
+    ///
+    /// let prefer: Vec<_> = req.headers_ref().unwrap().get_all("prefer").iter().collect();
+    /// assert_eq!(prefer, ["return=minimal", "handling=strict"]);
+    /// ```
+    pub fn prefer<V>(mut self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.builder = self
+            .builder
+            .header(HeaderName::from_static("prefer"), value);
+        self
+    }
+
+    /// Adds an extension to this request.
+    ///
+    /// Extensions are typed values that travel with the request, such as a request id.
+    /// They can be read by [`Middleware`](crate::middleware::Middleware), and by a
+    /// custom [`Connector`](crate::transport::Connector) via
+    /// [`ConnectionDetails::extensions`](crate::transport::ConnectionDetails::extensions).
+    /// There is one value per type, a later value for the same type replaces the earlier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Clone)]
+    /// struct RequestId(u64);
+    ///
+    /// let req = ureq::get("https://httpbin.org/get")
+    ///     .extension(RequestId(42));
+    /// ```
+    pub fn extension<T>(mut self, value: T) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.builder = self.builder.extension(value);
+        self
+    }
+
+    /// Appends a client address to the forwarding headers.
+    ///
+    /// For use when ureq relays a request on behalf of a client, such as in a proxy or
+    /// gateway. The address is appended to both `X-Forwarded-For` and `Forwarded` (RFC 7239),
+    /// keeping any chain already present in the headers. IPv6 addresses are bracketed
+    /// and quoted in `Forwarded` as the RFC requires.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::IpAddr;
+    ///
+    /// let client: IpAddr = "2001:db8::1".parse().unwrap();
+    ///
+    /// let req = ureq::get("https://httpbin.org/get")
+    ///     // chain from the incoming request
+    ///     .header("X-Forwarded-For", "192.0.2.10")
+    ///     .forwarded_for(client);
+    ///
+    /// let headers = req.headers_ref().unwrap();
+    /// assert_eq!(headers["x-forwarded-for"], "192.0.2.10, 2001:db8::1");
+    /// assert_eq!(headers["forwarded"], "for=\"[2001:db8::1]\"");
+    /// ```
+    pub fn forwarded_for(mut self, ip: IpAddr) -> Self {
+        // If the builder has an error, it surfaces on call()/send().
+        let Some(headers) = self.builder.headers_mut() else {
+            return self;
+        };
+
+        let node = match ip {
+            IpAddr::V4(v) => format!("for={}", v),
+            IpAddr::V6(v) => format!("for=\"[{}]\"", v),
+        };
+
+        append_to_list(
+            headers,
+            HeaderName::from_static("x-forwarded-for"),
+            &ip.to_string(),
+        );
+        append_to_list(headers, header::FORWARDED, &node);
+
+        self
+    }
+
     /// Add a query parameter to the URL.
     ///
     /// Always appends a new parameter, also when using the name of
@@ -78,8 +206,9 @@ impl<Any> RequestBuilder<Any> {
         K: AsRef<str>,
         V: AsRef<str>,
     {
-        self.query_extra
-            .push(QueryParam::new_key_value(key.as_ref(), value.as_ref()));
+        if let Ok(query_extra) = &mut self.query_extra {
+            query_extra.push(QueryParam::new_key_value(key.as_ref(), value.as_ref()));
+        }
         self
     }
 
@@ -104,10 +233,87 @@ impl<Any> RequestBuilder<Any> {
         K: AsRef<str>,
         V: AsRef<str>,
     {
-        self.query_extra.extend(
-            iter.into_iter()
-                .map(|(k, v)| QueryParam::new_key_value(k.as_ref(), v.as_ref())),
-        );
+        if let Ok(query_extra) = &mut self.query_extra {
+            query_extra.extend(
+                iter.into_iter()
+                    .map(|(k, v)| QueryParam::new_key_value(k.as_ref(), v.as_ref())),
+            );
+        }
+        self
+    }
+
+    /// Set query parameters from a map, in key order.
+    ///
+    /// The parameters are appended, and encoded, like with [`query_pairs()`](Self::query_pairs).
+    /// Since a `BTreeMap` is sorted, the resulting query string is the same regardless of
+    /// insertion order, which is what request signing schemes that canonicalize the query
+    /// need.
+    ///
+    /// For example, to set `?a=1&b=2`
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut query = BTreeMap::new();
+    /// query.insert("b".to_string(), "2".to_string());
+    /// query.insert("a".to_string(), "1".to_string());
+    ///
+    /// let response = ureq::get("http://httpbin.org/get")
+    ///    .query_map(&query)
+    ///    .call()?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn query_map(self, map: &BTreeMap<String, String>) -> Self {
+        self.query_pairs(map)
+    }
+
+    /// Set query parameters from a struct.
+    ///
+    /// Requires the **urlencoded** feature.
+    ///
+    /// The data typically derives [`Serialize`](serde::Serialize), and is turned into
+    /// query parameters using serde_urlencoded. The parameters are appended like with
+    /// [`query_pairs()`](Self::query_pairs). Fields that are `None` are left out.
+    ///
+    /// Only flat structs, maps and sequences of pairs can be query parameters. Anything
+    /// else, such as a struct with a nested struct, fails with [`Error::UrlEncoded`]
+    /// when the request is sent.
+    ///
+    /// For example, to set `?state=open&per_page=50`
+    ///
+    /// ```
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Filter {
+    ///     state: String,
+    ///     per_page: u32,
+    ///     label: Option<String>,
+    /// }
+    ///
+    /// let filter = Filter {
+    ///     state: "open".to_string(),
+    ///     per_page: 50,
+    ///     label: None,
+    /// };
+    ///
+    /// let response = ureq::get("http://httpbin.org/get")
+    ///    .query_struct(&filter)
+    ///    .call()?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "urlencoded")]
+    pub fn query_struct(mut self, data: impl serde::ser::Serialize) -> Self {
+        self.query_extra = self.query_extra.and_then(|mut query_extra| {
+            let encoded = serde_urlencoded::to_string(data)?;
+            query_extra.extend(
+                encoded
+                    .split('&')
+                    .filter(|p| !p.is_empty())
+                    .map(QueryParam::new_encoded),
+            );
+            Ok(query_extra)
+        });
         self
     }
 
@@ -122,12 +328,11 @@ impl<Any> RequestBuilder<Any> {
     /// let req = ureq::get("https://www.google.com/")
     ///     .uri("https://httpbin.org/get");
     /// ```
-    pub fn uri<T>(mut self, uri: T) -> Self
-    where
-        Uri: TryFrom<T>,
-        <Uri as TryFrom<T>>::Error: Into<http::Error>,
-    {
-        self.builder = self.builder.uri(uri);
+    pub fn uri<T: IntoUri>(mut self, uri: T) -> Self {
+        match uri.into_uri() {
+            Ok(uri) => self.builder = self.builder.uri(uri),
+            Err(e) => self.query_extra = Err(e),
+        }
         self
     }
 
@@ -149,6 +354,30 @@ impl<Any> RequestBuilder<Any> {
         self
     }
 
+    /// Use a new connection for this request, instead of one from the pool.
+    ///
+    /// The connection is closed once the response is read, and is not returned to the
+    /// pool. This also goes for any redirects that are followed. Other requests are not
+    /// affected, pooled connections stay in the pool to be used by them.
+    ///
+    /// The request itself is unchanged, there is no `Connection: close` header. A server
+    /// that keeps the connection alive sees it closed by ureq after the response.
+    ///
+    /// This is useful when a server ties state to the connection, such as some
+    /// authentication schemes, and that state must not carry over.
+    ///
+    /// ```
+    /// let res = ureq::get("http://httpbin.org/get")
+    ///     .fresh_connection()
+    ///     .call()?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn fresh_connection(mut self) -> Self {
+        let config = self.request_level_config();
+        config.fresh_connection = true;
+        self
+    }
+
     /// Override agent level config on the request level.
     ///
     /// The agent config is copied and modified on request level.
@@ -202,15 +431,12 @@ impl<Any> RequestBuilder<Any> {
 }
 
 impl RequestBuilder<WithoutBody> {
-    pub(crate) fn new<T>(agent: Agent, method: Method, uri: T) -> Self
-    where
-        Uri: TryFrom<T>,
-        <Uri as TryFrom<T>>::Error: Into<http::Error>,
-    {
+    pub(crate) fn new<T: IntoUri>(agent: Agent, method: Method, uri: T) -> Self {
+        let (builder, query_extra) = request_builder(method, uri);
         Self {
             agent,
-            builder: Request::builder().method(method).uri(uri),
-            query_extra: vec![],
+            builder,
+            query_extra,
             dummy_config: None,
             _ph: PhantomData,
         }
@@ -218,7 +444,10 @@ impl RequestBuilder<WithoutBody> {
 
     /// Sends the request and blocks the caller until we receive a response.
     ///
-    /// It sends neither `Content-Length` nor `Transfer-Encoding`.
+    /// It sends neither `Content-Length` nor `Transfer-Encoding`. Use
+    /// [`force_send_body()`](Self::force_send_body) and
+    /// [`send_empty()`](RequestBuilder::send_empty) if the server requires
+    /// `Content-Length: 0`.
     ///
     /// ```
     /// let res = ureq::get("http://httpbin.org/get")
@@ -230,6 +459,33 @@ impl RequestBuilder<WithoutBody> {
         do_call(self.agent, request, self.query_extra, SendBody::none())
     }
 
+    /// Iterate over the pages of a paginated API.
+    ///
+    /// Sends the request when the iterator is first advanced, then keeps following the
+    /// `rel="next"` link of the `link` header, as used by GitHub-style APIs. See
+    /// [`Pages`] for details.
+    ///
+    /// ```no_run
+    /// let pages = ureq::get("https://api.example.test/repos")
+    ///     .header("Authorization", "Bearer secret")
+    ///     .pages();
+    ///
+    /// for page in pages {
+    ///     let text = page?.body_mut().read_to_string()?;
+    ///     println!("{}", text);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn pages(self) -> Pages {
+        let query_extra = self.query_extra;
+        let request = self
+            .builder
+            .body(())
+            .map_err(Error::from)
+            .and_then(|r| amend_query_extra(r, query_extra?));
+        Pages::new(self.agent, request)
+    }
+
     /// Force sending a body.
     ///
     /// This is an escape hatch to interact with broken services.
@@ -264,15 +520,12 @@ impl RequestBuilder<WithoutBody> {
 }
 
 impl RequestBuilder<WithBody> {
-    pub(crate) fn new<T>(agent: Agent, method: Method, uri: T) -> Self
-    where
-        Uri: TryFrom<T>,
-        <Uri as TryFrom<T>>::Error: Into<http::Error>,
-    {
+    pub(crate) fn new<T: IntoUri>(agent: Agent, method: Method, uri: T) -> Self {
+        let (builder, query_extra) = request_builder(method, uri);
         Self {
             agent,
-            builder: Request::builder().method(method).uri(uri),
-            query_extra: vec![],
+            builder,
+            query_extra,
             dummy_config: None,
             _ph: PhantomData,
         }
@@ -295,6 +548,31 @@ impl RequestBuilder<WithBody> {
         self
     }
 
+    /// Set the content-type header from the first bytes of the body.
+    ///
+    /// Requires the **content-sniff** feature.
+    ///
+    /// When the request has no `Content-Type`, the start of the body is matched against
+    /// the magic numbers of common formats, such as PNG, JPEG, PDF, gzip and zip. If one
+    /// matches, its mime type is sent as the content-type, otherwise none is sent. For
+    /// a reader body, only the first 16 bytes are read ahead to do this.
+    ///
+    /// ```
+    /// let png = b"\x89PNG\r\n\x1a\n...";
+    ///
+    /// // Sent with content-type: image/png
+    /// let res = ureq::post("http://httpbin.org/post")
+    ///     .sniff_content_type()
+    ///     .send(png)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "content-sniff")]
+    pub fn sniff_content_type(mut self) -> Self {
+        let config = self.request_level_config();
+        config.sniff_content_type = true;
+        self
+    }
+
     /// Send body data and blocks the caller until we receive response.
     ///
     /// ```
@@ -314,6 +592,11 @@ impl RequestBuilder<WithBody> {
     /// this function makes it explicit you want to send an empty body despite
     /// the method.
     ///
+    /// The request has the header `Content-Length: 0`. This differs from
+    /// [`call()`](RequestBuilder::call), which sends no framing header at all.
+    /// Some servers reject a POST without `Content-Length`, typically with
+    /// `411 Length Required`.
+    ///
     /// This is equivalent to `.send(&[])`.
     ///
     /// ```
@@ -325,6 +608,46 @@ impl RequestBuilder<WithBody> {
         self.send(&[])
     }
 
+    /// Send the contents of a file.
+    ///
+    /// The file is streamed, not read into memory. The `content-length` is set from
+    /// the file metadata, unless already set.
+    ///
+    /// Unless set with [`content_type()`](Self::content_type), the content-type is
+    /// guessed from the file extension with the **mime-guess** feature, such as
+    /// `image/png` for `.png`. Without the feature, or for an unknown extension, it is
+    /// `application/octet-stream`.
+    ///
+    /// Failing to open the file is an [`Error::Io`].
+    ///
+    /// ```no_run
+    /// let res = ureq::put("http://httpbin.org/put")
+    ///     .send_file("report.pdf")?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn send_file(self, path: impl AsRef<Path>) -> Result<Response<Body>, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut request = self.builder.body(())?;
+
+        if !request.headers().has_send_body_mode() {
+            request
+                .headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        }
+
+        if !request.headers().has_content_type() {
+            request
+                .headers_mut()
+                .append(header::CONTENT_TYPE, guess_content_type(path));
+        }
+
+        let body = SendBody::from_owned_reader(file);
+        do_call(self.agent, request, self.query_extra, body)
+    }
+
     /// Send form encoded data.
     ///
     /// Constructs a [form submission] with the content-type header
@@ -374,6 +697,55 @@ impl RequestBuilder<WithBody> {
         do_call(self.agent, request, self.query_extra, body.as_body())
     }
 
+    /// Send form encoded data from a struct.
+    ///
+    /// Requires the **urlencoded** feature.
+    ///
+    /// Like [`send_form()`](Self::send_form), but the data typically derives
+    /// [`Serialize`](serde::Serialize) and is encoded using serde_urlencoded. Will set
+    /// the content-type header `application/x-www-form-urlencoded`, and the body is
+    /// sent with `content-length`. Fields that are `None` are left out.
+    ///
+    /// Only flat structs, maps and sequences of pairs can be encoded. Anything else,
+    /// such as a struct with a nested struct, fails with [`Error::UrlEncoded`].
+    ///
+    /// ```
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Login {
+    ///     name: String,
+    ///     remember: bool,
+    /// }
+    ///
+    /// let form = Login {
+    ///     name: "martin".to_string(),
+    ///     remember: true,
+    /// };
+    ///
+    /// let response = ureq::post("http://httpbin.org/post")
+    ///    .send_form_struct(&form)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "urlencoded")]
+    pub fn send_form_struct(
+        self,
+        data: impl serde::ser::Serialize,
+    ) -> Result<Response<Body>, Error> {
+        let mut body = serde_urlencoded::to_string(data)?;
+
+        let mut request = self.builder.body(())?;
+
+        if !request.headers().has_content_type() {
+            request.headers_mut().append(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+        }
+
+        do_call(self.agent, request, self.query_extra, body.as_body())
+    }
+
     /// Send body data as JSON.
     ///
     /// Requires the **json** feature.
@@ -412,20 +784,147 @@ impl RequestBuilder<WithBody> {
 
         do_call(self.agent, request, self.query_extra, body)
     }
+
+    /// Send body data as JSON, formatted by a [`serde_json`] formatter.
+    ///
+    /// Requires the **json** feature.
+    ///
+    /// Like [`send_json()`](Self::send_json), but the formatter decides on whitespace and
+    /// indentation, for instance [`CompactFormatter`](serde_json::ser::CompactFormatter)
+    /// for APIs that sign or canonicalize the JSON, or a
+    /// [`PrettyFormatter`](serde_json::ser::PrettyFormatter) with a custom indent for
+    /// debugging. Keys are written in the order the data serializes them. Will set the
+    /// content-type header `application/json`.
+    ///
+    /// ```
+    /// use serde_json::ser::CompactFormatter;
+    ///
+    /// let body = serde_json::json!({ "thing": "yo" });
+    ///
+    /// let res = ureq::post("http://httpbin.org/post")
+    ///     .send_json_with(&body, CompactFormatter)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn send_json_with(
+        self,
+        data: impl serde::ser::Serialize,
+        formatter: impl serde_json::ser::Formatter,
+    ) -> Result<Response<Body>, Error> {
+        let mut request = self.builder.body(())?;
+        let body = SendBody::from_json_with(&data, formatter)?;
+
+        if !request.headers().has_content_type() {
+            request.headers_mut().append(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json; charset=utf-8"),
+            );
+        }
+
+        do_call(self.agent, request, self.query_extra, body)
+    }
+
+    /// Send body data as JSON, serialized while the request is being sent.
+    ///
+    /// Requires the **json** feature.
+    ///
+    /// Unlike [`send_json()`](Self::send_json), the JSON is never held in memory in its
+    /// entirety. It's serialized on a separate thread straight into the request body,
+    /// which is sent with `transfer-encoding: chunked`. This is worth it for large
+    /// values. The JSON is compact, not pretty printed. Will set the content-type
+    /// header `application/json`.
+    ///
+    /// If serializing fails halfway, the request is aborted and the error is
+    /// returned as [`Error::Json`].
+    ///
+    /// ```
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Row {
+    ///     id: u64,
+    /// }
+    ///
+    /// let rows: Vec<Row> = (0..100_000).map(|id| Row { id }).collect();
+    ///
+    /// let res = ureq::post("http://httpbin.org/post")
+    ///     .send_json_streaming(&rows)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn send_json_streaming(
+        self,
+        data: impl serde::ser::Serialize + Sync,
+    ) -> Result<Response<Body>, Error> {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let mut request = self.builder.body(())?;
+
+        if !request.headers().has_content_type() {
+            request.headers_mut().append(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json; charset=utf-8"),
+            );
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(4);
+        let body = SendBody::from_receiver(receiver);
+
+        let data = &data;
+        let agent = self.agent;
+        let query_extra = self.query_extra;
+
+        thread::scope(|s| {
+            let serializer = s.spawn(move || crate::send_body::json_to_channel(data, sender));
+
+            let result = do_call(agent, request, query_extra, body);
+
+            match serializer.join() {
+                // A serialization error makes the body end early, which surfaces as
+                // an io error in the result. The serializer knows the real reason.
+                Ok(Err(e)) if !e.is_io() => Err(Error::Json(e)),
+                Ok(_) => result,
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        })
+    }
+}
+
+fn request_builder(
+    method: Method,
+    uri: impl IntoUri,
+) -> (
+    http::request::Builder,
+    Result<Vec<QueryParam<'static>>, Error>,
+) {
+    let builder = Request::builder().method(method);
+    match uri.into_uri() {
+        Ok(uri) => (builder.uri(uri), Ok(vec![])),
+        Err(e) => (builder, Err(e)),
+    }
 }
 
 fn do_call(
     agent: Agent,
-    mut request: Request<()>,
-    query_extra: Vec<QueryParam<'static>>,
+    request: Request<()>,
+    query_extra: Result<Vec<QueryParam<'static>>, Error>,
     body: SendBody,
 ) -> Result<Response<Body>, Error> {
+    let request = amend_query_extra(request, query_extra?)?;
+    let response = agent.run_via_middleware(request, body)?;
+    Ok(response)
+}
+
+fn amend_query_extra(
+    mut request: Request<()>,
+    query_extra: Vec<QueryParam<'static>>,
+) -> Result<Request<()>, Error> {
     if !query_extra.is_empty() {
         request.uri().ensure_valid_url()?;
         request = amend_request_query(request, query_extra.into_iter());
     }
-    let response = agent.run_via_middleware(request, body)?;
-    Ok(response)
+    Ok(request)
 }
 
 fn amend_request_query(
@@ -471,6 +970,35 @@ fn amend_request_query(
     Request::from_parts(parts, body)
 }
 
+/// Append to a comma separated header, folding multiple header lines into one.
+fn append_to_list(headers: &mut HeaderMap, name: HeaderName, item: &str) {
+    let mut list: Vec<&[u8]> = headers
+        .get_all(&name)
+        .iter()
+        .map(|v| v.as_bytes())
+        .collect();
+    list.push(item.as_bytes());
+
+    let joined = list.join(&b", "[..]);
+
+    // Joining valid header values with ", " is also a valid header value.
+    if let Ok(value) = HeaderValue::from_bytes(&joined) {
+        headers.insert(name, value);
+    }
+}
+
+fn guess_content_type(path: &Path) -> HeaderValue {
+    #[cfg(feature = "mime-guess")]
+    if let Some(mime) = mime_guess::from_path(path).first_raw() {
+        return HeaderValue::from_static(mime);
+    }
+
+    #[cfg(not(feature = "mime-guess"))]
+    let _ = path;
+
+    HeaderValue::from_static("application/octet-stream")
+}
+
 impl<MethodLimit> Deref for RequestBuilder<MethodLimit> {
     type Target = http::request::Builder;
 
@@ -514,6 +1042,34 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn forwarded_for_ipv4() {
+        let req = get("https://foo/bar").forwarded_for("192.0.2.1".parse().unwrap());
+        let headers = req.headers_ref().unwrap();
+        assert_eq!(headers["x-forwarded-for"], "192.0.2.1");
+        assert_eq!(headers["forwarded"], "for=192.0.2.1");
+    }
+
+    #[test]
+    fn forwarded_for_keeps_chain() {
+        let req = get("https://foo/bar")
+            .header("x-forwarded-for", "192.0.2.1")
+            .header("x-forwarded-for", "192.0.2.2")
+            .header("forwarded", "for=192.0.2.1;proto=https")
+            .forwarded_for("192.0.2.3".parse().unwrap())
+            .forwarded_for("::1".parse().unwrap());
+
+        let headers = req.headers_ref().unwrap();
+
+        let xff: Vec<_> = headers.get_all("x-forwarded-for").iter().collect();
+        assert_eq!(xff, ["192.0.2.1, 192.0.2.2, 192.0.2.3, ::1"]);
+
+        assert_eq!(
+            headers["forwarded"],
+            "for=192.0.2.1;proto=https, for=192.0.2.3, for=\"[::1]\""
+        );
+    }
+
     #[test]
     fn disallow_empty_host() {
         let err = crate::get("file:///some/path").call().unwrap_err();
@@ -548,6 +1104,212 @@ mod test {
             .build();
     }
 
+    #[test]
+    #[cfg(feature = "urlencoded")]
+    fn query_struct_params() {
+        #[derive(serde::Serialize)]
+        struct Filter {
+            state: &'static str,
+            label: Option<&'static str>,
+            per_page: u32,
+        }
+
+        let filter = Filter {
+            state: "open now",
+            label: None,
+            per_page: 50,
+        };
+
+        let req = get("https://foo.bar/path?x=z")
+            .query("a", "b")
+            .query_struct(&filter);
+
+        let request = req.builder.body(()).unwrap();
+        let amended = amend_query_extra(request, req.query_extra.unwrap()).unwrap();
+
+        assert_eq!(
+            amended.uri(),
+            "https://foo.bar/path?x=z&a=b&state=open+now&per_page=50"
+        );
+    }
+
+    #[test]
+    fn query_map_sorted_and_encoded() {
+        let mut map = BTreeMap::new();
+        map.insert("z".to_string(), "last".to_string());
+        map.insert("a b".to_string(), "x&y".to_string());
+
+        let req = get("https://foo.bar/path?x=z").query_map(&map);
+
+        let request = req.builder.body(()).unwrap();
+        let amended = amend_query_extra(request, req.query_extra.unwrap()).unwrap();
+
+        let single = get("https://foo.bar/path?x=z")
+            .query("a b", "x&y")
+            .query("z", "last");
+        let request = single.builder.body(()).unwrap();
+        let expected = amend_query_extra(request, single.query_extra.unwrap()).unwrap();
+
+        assert_eq!(amended.uri(), expected.uri());
+    }
+
+    #[test]
+    #[cfg(feature = "urlencoded")]
+    fn query_struct_nested_fails_on_call() {
+        #[derive(serde::Serialize)]
+        struct Inner {
+            a: u32,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Outer {
+            inner: Inner,
+        }
+
+        let err = get("https://foo.bar/path")
+            .query_struct(Outer {
+                inner: Inner { a: 1 },
+            })
+            .query("later", "ignored")
+            .call()
+            .unwrap_err();
+
+        assert!(matches!(err, Error::UrlEncoded(_)));
+    }
+
+    #[test]
+    #[cfg(all(feature = "urlencoded", feature = "test-support"))]
+    fn send_form_struct_body() {
+        use crate::test_support::TestServer;
+
+        #[derive(serde::Serialize)]
+        struct Login {
+            name: &'static str,
+            remember: bool,
+            code: Option<u32>,
+        }
+
+        let server = TestServer::new().route("/form", |req| {
+            let headers = req.headers();
+            let echo = format!(
+                "{}|{}|{}",
+                headers["content-type"].to_str().unwrap(),
+                headers["content-length"].to_str().unwrap(),
+                String::from_utf8_lossy(req.body())
+            );
+            Response::new(echo.into_bytes())
+        });
+        let agent = server.agent(Config::default());
+
+        let text = agent
+            .post("http://example.test/form")
+            .send_form_struct(Login {
+                name: "blue footed",
+                remember: true,
+                code: None,
+            })
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+
+        assert_eq!(
+            text,
+            "application/x-www-form-urlencoded|30|name=blue+footed&remember=true"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "test-support")]
+    fn send_file_body() {
+        use crate::test_support::TestServer;
+
+        let path = std::env::temp_dir().join(format!("ureq-send-file-{}", std::process::id()));
+        std::fs::write(&path, b"file contents").unwrap();
+
+        let server = TestServer::new().route("/upload", |req| {
+            let headers = req.headers();
+            let echo = format!(
+                "{}|{}|{}",
+                headers["content-type"].to_str().unwrap(),
+                headers["content-length"].to_str().unwrap(),
+                String::from_utf8_lossy(req.body())
+            );
+            Response::new(echo.into_bytes())
+        });
+        let agent = server.agent(Config::default());
+
+        let text = agent
+            .put("http://example.test/upload")
+            .send_file(&path)
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(text, "application/octet-stream|13|file contents");
+    }
+
+    #[test]
+    fn guess_content_type_by_extension() {
+        let guess = |p: &str| guess_content_type(Path::new(p));
+
+        if cfg!(feature = "mime-guess") {
+            assert_eq!(guess("a/picture.png"), "image/png");
+            assert_eq!(guess("index.HTML"), "text/html");
+        } else {
+            assert_eq!(guess("a/picture.png"), "application/octet-stream");
+        }
+
+        assert_eq!(guess("no-extension"), "application/octet-stream");
+        assert_eq!(guess("file.unknown-ext"), "application/octet-stream");
+    }
+
+    #[test]
+    #[cfg(all(feature = "digest", feature = "test-support"))]
+    fn send_content_digest() {
+        use crate::test_support::TestServer;
+
+        let server = TestServer::new().route("/digest", |req| {
+            let digest = req.headers().get("content-digest");
+            let echo = digest.map(|v| v.as_bytes().to_vec()).unwrap_or_default();
+            Response::new(echo)
+        });
+        let agent = server.agent(Config::builder().send_content_digest(true).build());
+
+        let digest = agent
+            .post("http://example.test/digest")
+            .send("hello world")
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+        assert_eq!(
+            digest,
+            "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:"
+        );
+
+        // Not for a streamed body.
+        let digest = agent
+            .post("http://example.test/digest")
+            .send(SendBody::from_owned_reader(&b"hello world"[..]))
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+        assert_eq!(digest, "");
+    }
+
+    #[test]
+    fn send_file_missing() {
+        let err = crate::post("https://foo/bar")
+            .send_file("/no/such/ureq/file")
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+    }
+
     #[test]
     fn add_params_to_request_without_query() {
         let request = Request::builder()
@@ -0,0 +1,80 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::Write;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// The id sent in the request id header.
+///
+/// Inserted as an extension on responses when the agent is configured with
+/// [`ConfigBuilder::request_id()`](crate::config::ConfigBuilder::request_id) or
+/// [`ConfigBuilder::request_id_with()`](crate::config::ConfigBuilder::request_id_with).
+/// When the request already had the header, this is the value it had.
+///
+/// ```
+/// use ureq::{Agent, RequestId};
+/// use ureq::http::HeaderName;
+///
+/// let agent: Agent = Agent::config_builder()
+///     .request_id(HeaderName::from_static("x-request-id"))
+///     .build()
+///     .into();
+///
+/// let res = agent.get("http://httpbin.org/get").call()?;
+///
+/// if let Some(id) = res.extensions().get::<RequestId>() {
+///     println!("Request id: {}", id.as_str());
+/// }
+/// # Ok::<_, ureq::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub(crate) String);
+
+impl RequestId {
+    /// The id as sent in the header.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A new id of 32 hex digits.
+///
+/// The id is from the random keys std seeds hashers with, a process wide counter and
+/// the time. Unique enough to correlate requests, but not to be used as a secret.
+pub(crate) fn generate_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    let mut id = String::with_capacity(32);
+
+    for half in 0..2_u8 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(count);
+        hasher.write_u128(nanos);
+        hasher.write_u8(half);
+        // Writing to a String doesn't fail.
+        let _ = write!(id, "{:016x}", hasher.finish());
+    }
+
+    id
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generated_ids_differ() {
+        let a = generate_id();
+        let b = generate_id();
+
+        assert_eq!(a.len(), 32);
+        assert!(a.bytes().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+}
@@ -89,6 +89,11 @@ impl Resolver for DefaultResolver {
         let scheme = uri.scheme().unwrap();
         let authority = uri.authority().unwrap();
 
+        if let Some(result) = resolve_override(scheme, authority, config) {
+            debug!("Resolved by override: {:?}", result);
+            return Ok(result);
+        }
+
         if cfg!(feature = "_test") {
             let mut v = ArrayVec::from_fn(|_| "0.0.0.0:1".parse().unwrap());
             v.push(SocketAddr::V4(SocketAddrV4::new(
@@ -120,10 +125,6 @@ impl Resolver for DefaultResolver {
 
         let wanted = config.ip_family.keep_wanted(iter);
 
-        fn uninited_socketaddr() -> SocketAddr {
-            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0)
-        }
-
         let mut result: ResolvedSocketAddrs = ArrayVec::from_fn(|_| uninited_socketaddr());
         for addr in wanted.take(MAX_ADDRS) {
             result.push(addr);
@@ -139,6 +140,37 @@ impl Resolver for DefaultResolver {
     }
 }
 
+fn resolve_override(
+    scheme: &Scheme,
+    authority: &Authority,
+    config: &Config,
+) -> Option<ResolvedSocketAddrs> {
+    let port = authority.port_u16().or_else(|| scheme.default_port())?;
+    let host = authority.host();
+
+    let mut result: ResolvedSocketAddrs = ArrayVec::from_fn(|_| uninited_socketaddr());
+
+    let matching = config
+        .resolve_overrides
+        .iter()
+        .filter(|(h, p, _)| *p == port && h.eq_ignore_ascii_case(host))
+        .map(|(_, _, addr)| *addr);
+
+    for addr in matching.take(MAX_ADDRS) {
+        result.push(addr);
+    }
+
+    if result.is_empty() {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+fn uninited_socketaddr() -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0)
+}
+
 fn resolve_async(addr: String, timeout: NextTimeout) -> Result<IntoIter<SocketAddr>, Error> {
     // TODO(martin): On Linux we have getaddrinfo_a which is a libc async way of
     // doing host lookup. We should make a subcrate that uses a native async method
@@ -204,4 +236,38 @@ mod test {
         assert!(matches!(err, Error::BadUri(_)));
         assert_eq!(err.to_string(), "bad uri: unknown scheme: foo");
     }
+
+    #[test]
+    fn override_host_and_port() {
+        let a: SocketAddr = "192.0.2.1:8080".parse().unwrap();
+        let b: SocketAddr = "192.0.2.2:8080".parse().unwrap();
+        let c: SocketAddr = "192.0.2.3:443".parse().unwrap();
+
+        let config = Config::builder()
+            .resolve("example.test", 80, a)
+            .resolve("example.test", 80, b)
+            .resolve("example.test", 443, c)
+            .build();
+
+        let resolve = |uri: &str| {
+            let uri: Uri = uri.parse().unwrap();
+            let addrs = DefaultResolver::default()
+                .resolve(
+                    &uri,
+                    &config,
+                    NextTimeout {
+                        after: Duration::NotHappening,
+                        reason: crate::Timeout::Global,
+                    },
+                )
+                .unwrap();
+            addrs.to_vec()
+        };
+
+        assert_eq!(resolve("http://EXAMPLE.test/path"), vec![a, b]);
+        assert_eq!(resolve("https://example.test"), vec![c]);
+
+        // Not overridden, this is the fake resolve for tests.
+        assert_ne!(resolve("http://example.test:81"), vec![a, b]);
+    }
 }
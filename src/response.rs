@@ -0,0 +1,406 @@
+use std::time::SystemTime;
+
+use http::{HeaderMap, Response, StatusCode, Uri};
+
+use crate::http_date::parse_http_date;
+use crate::link::{parse_links, resolve, Link};
+use crate::set_cookie::{parse_set_cookies, SetCookie};
+use crate::util::private::Private;
+use crate::Body;
+
+/// Extension trait for [`http::Response<Body>`].
+///
+/// Adds shorthands for `Response<Body>`, which is a type from the http crate.
+///
+/// ```
+/// use ureq::ResponseExt;
+///
+/// let (status, headers, body) = ureq::get("http://httpbin.org/get")
+///     .call()?
+///     .split();
+///
+/// assert_eq!(status, 200);
+/// let body = body.into_with_config().limit(1024).read_to_string()?;
+/// # Ok::<_, ureq::Error>(())
+/// ```
+pub trait ResponseExt: Private {
+    /// The status, headers and body of the response.
+    ///
+    /// The body is not read, it can be streamed as usual. Other parts of the response,
+    /// such as the [extensions](http::Response::extensions), are dropped.
+    fn split(self) -> (StatusCode, HeaderMap, Body);
+
+    /// The preferences of the `Preference-Applied` header (RFC 7240).
+    ///
+    /// These are the preferences, sent with
+    /// [`RequestBuilder::prefer()`](crate::RequestBuilder::prefer), that the server
+    /// honored. Empty if the header is missing.
+    ///
+    /// ```
+    /// use ureq::ResponseExt;
+    ///
+    /// let res = ureq::patch("http://httpbin.org/patch")
+    ///     .prefer("return=minimal")
+    ///     .send_empty()?;
+    ///
+    /// if res.preference_applied().iter().any(|p| p == "return=minimal") {
+    ///     // no representation in the body
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn preference_applied(&self) -> Vec<String>;
+
+    /// The entity tag of the `etag` header, without quotes.
+    ///
+    /// For both of the below headers, we would get `Some("xyzzy")`:
+    ///
+    /// ```text
+    ///     ETag: "xyzzy"
+    ///     ETag: W/"xyzzy"
+    /// ```
+    ///
+    /// The weak indicator `W/` is dropped.
+    ///
+    /// ```no_run
+    /// use ureq::ResponseExt;
+    ///
+    /// let res = ureq::get("https://example.test/resource")
+    ///     .call()?;
+    ///
+    /// if let Some(etag) = res.etag() {
+    ///     println!("cache under: {}", etag);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn etag(&self) -> Option<&str>;
+
+    /// The time of the `last-modified` header.
+    ///
+    /// All three HTTP-date formats are understood. If the header is missing or the
+    /// date can't be parsed, this returns `None`.
+    ///
+    /// ```text
+    ///     Last-Modified: Sun, 06 Nov 1994 08:49:37 GMT
+    ///     Last-Modified: Sunday, 06-Nov-94 08:49:37 GMT
+    ///     Last-Modified: Sun Nov  6 08:49:37 1994
+    /// ```
+    ///
+    /// ```no_run
+    /// use ureq::ResponseExt;
+    ///
+    /// let res = ureq::get("https://example.test/resource")
+    ///     .call()?;
+    ///
+    /// if let Some(time) = res.last_modified() {
+    ///     println!("modified: {:?}", time);
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn last_modified(&self) -> Option<SystemTime>;
+
+    /// The links of the `link` headers.
+    ///
+    /// Both multiple headers and multiple links in one header are handled. Relative link
+    /// targets are resolved against the uri of the request.
+    ///
+    /// ```text
+    ///     Link: <https://api.example.test/repos?page=3>; rel="next",
+    ///           <https://api.example.test/repos?page=50>; rel="last"
+    /// ```
+    ///
+    /// ```no_run
+    /// use ureq::ResponseExt;
+    ///
+    /// let res = ureq::get("https://api.example.test/repos")
+    ///     .call()?;
+    ///
+    /// for link in res.links() {
+    ///     println!("{:?}: {}", link.rel(), link.uri());
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn links(&self) -> Vec<Link>;
+
+    /// The target of the link with `rel="next"`, if any.
+    ///
+    /// This is typically used to page through results, see also
+    /// [`RequestBuilder::pages()`](crate::RequestBuilder::pages).
+    ///
+    /// ```no_run
+    /// use ureq::http::Uri;
+    /// use ureq::ResponseExt;
+    ///
+    /// let mut next = Some(Uri::from_static("https://api.example.test/repos"));
+    ///
+    /// while let Some(uri) = next {
+    ///     let mut res = ureq::get(uri).call()?;
+    ///     let page = res.body_mut().read_to_string()?;
+    ///     println!("{}", page);
+    ///
+    ///     next = res.next_link();
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn next_link(&self) -> Option<Uri>;
+
+    /// The target of the `location` header, if any.
+    ///
+    /// A relative target is resolved against the uri of the request. This is mostly
+    /// of interest when not following redirects, see
+    /// [`ConfigBuilder::max_redirects()`](crate::config::ConfigBuilder::max_redirects).
+    ///
+    /// ```no_run
+    /// use ureq::{Agent, ResponseExt};
+    ///
+    /// let agent: Agent = Agent::config_builder()
+    ///     .max_redirects(0)
+    ///     .build()
+    ///     .into();
+    ///
+    /// let res = agent.get("https://example.test/old").call()?;
+    ///
+    /// if res.status().is_redirection() {
+    ///     println!("redirect to: {:?}", res.location());
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn location(&self) -> Option<Uri>;
+
+    /// The cookies of the `set-cookie` headers.
+    ///
+    /// This is available without the `cookies` feature, for handling cookies without a
+    /// cookie jar. Each header is one cookie, and the attributes are kept as sent.
+    ///
+    /// ```no_run
+    /// use ureq::ResponseExt;
+    ///
+    /// let res = ureq::get("https://example.test/login")
+    ///     .call()?;
+    ///
+    /// for cookie in res.set_cookies() {
+    ///     println!("{}: {:?}", cookie.name(), cookie.path());
+    /// }
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    fn set_cookies(&self) -> Vec<SetCookie>;
+}
+
+/// The uri of the request a response is for. The last one when following redirects.
+#[derive(Debug, Clone)]
+pub(crate) struct ResponseUri(pub Uri);
+
+impl ResponseExt for Response<Body> {
+    fn split(self) -> (StatusCode, HeaderMap, Body) {
+        let (parts, body) = self.into_parts();
+        (parts.status, parts.headers, body)
+    }
+
+    fn preference_applied(&self) -> Vec<String> {
+        self.headers()
+            .get_all("preference-applied")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn etag(&self) -> Option<&str> {
+        let etag = self.headers().get("etag")?.to_str().ok()?.trim();
+
+        // ETag: W/"xyzzy"
+        let etag = etag.strip_prefix("W/").unwrap_or(etag);
+
+        Some(
+            etag.strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(etag),
+        )
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.headers()
+            .get("last-modified")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_http_date)
+    }
+
+    fn links(&self) -> Vec<Link> {
+        parse_links(self.headers(), &request_uri(self))
+    }
+
+    fn next_link(&self) -> Option<Uri> {
+        self.links()
+            .into_iter()
+            .find(|l| l.has_rel("next"))
+            .map(|l| l.uri().clone())
+    }
+
+    fn location(&self) -> Option<Uri> {
+        let location = self.headers().get("location")?.to_str().ok()?;
+        resolve(&request_uri(self), location.trim())
+    }
+
+    fn set_cookies(&self) -> Vec<SetCookie> {
+        parse_set_cookies(self.headers())
+    }
+}
+
+/// The uri to resolve relative targets against.
+///
+/// A response that ureq didn't make has none, and only absolute targets are kept.
+fn request_uri(response: &Response<Body>) -> Uri {
+    response
+        .extensions()
+        .get::<ResponseUri>()
+        .map(|u| u.0.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "_test"))]
+mod test {
+    use super::*;
+    use crate::test::init_test_log;
+    use crate::transport::set_handler;
+    use crate::Agent;
+
+    #[test]
+    fn split_streams_body() {
+        set_handler("/split", 201, &[("content-length", "5")], b"hello");
+
+        let (status, headers, mut body) =
+            crate::get("https://my.test/split").call().unwrap().split();
+
+        assert_eq!(status, 201);
+        assert_eq!(headers["content-length"], "5");
+        assert_eq!(body.read_to_string().unwrap(), "hello");
+    }
+
+    #[test]
+    fn preference_applied() {
+        set_handler(
+            "/prefer",
+            200,
+            &[
+                ("content-length", "0"),
+                ("preference-applied", "return=minimal, respond-async"),
+                ("preference-applied", "handling=strict"),
+            ],
+            b"",
+        );
+
+        let res = crate::get("https://my.test/prefer")
+            .prefer("return=minimal")
+            .call()
+            .unwrap();
+
+        assert_eq!(
+            res.preference_applied(),
+            ["return=minimal", "respond-async", "handling=strict"]
+        );
+    }
+
+    #[test]
+    fn etag_and_last_modified() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[
+                ("etag", "W/\"xyzzy\""),
+                ("last-modified", "Sun, 06 Nov 1994 08:49:37 GMT"),
+            ],
+            b"",
+        );
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        assert_eq!(res.etag(), Some("xyzzy"));
+
+        let modified = res.last_modified().unwrap();
+        let secs = modified.duration_since(std::time::UNIX_EPOCH).unwrap();
+        assert_eq!(secs.as_secs(), 784_111_777);
+    }
+
+    #[test]
+    fn last_modified_unparsable() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[("etag", "\"abc\""), ("last-modified", "yesterday")],
+            b"",
+        );
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        assert_eq!(res.etag(), Some("abc"));
+        assert!(res.last_modified().is_none());
+    }
+
+    #[test]
+    fn set_cookies_from_headers() {
+        init_test_log();
+        set_handler(
+            "/get",
+            200,
+            &[
+                ("set-cookie", "a=1; Path=/"),
+                ("set-cookie", "b=2; Expires=Wed, 21 Oct 2015 07:28:00 GMT"),
+            ],
+            b"",
+        );
+
+        let res = crate::get("https://my.test/get").call().unwrap();
+        let cookies = res.set_cookies();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].path(), Some("/"));
+        assert_eq!(cookies[1].name(), "b");
+        assert!(cookies[1].expires().is_some());
+    }
+
+    #[test]
+    fn location_relative() {
+        init_test_log();
+        set_handler("/old/page", 302, &[("location", "../new?x=1")], b"");
+
+        let agent: Agent = Agent::config_builder().max_redirects(0).build().into();
+
+        let res = agent.get("https://my.test/old/page").call().unwrap();
+        assert_eq!(res.status(), 302);
+        assert_eq!(res.location().unwrap(), "https://my.test/new?x=1");
+    }
+
+    #[test]
+    fn next_link_relative() {
+        init_test_log();
+        set_handler(
+            "/items",
+            200,
+            &[(
+                "link",
+                "</items?page=2>; rel=\"next\", </items?page=9>; rel=last",
+            )],
+            b"",
+        );
+
+        let res = crate::get("https://my.test/items").call().unwrap();
+        assert_eq!(res.links().len(), 2);
+
+        let next = res.next_link().unwrap();
+        assert_eq!(next, "https://my.test/items?page=2");
+    }
+
+    #[test]
+    fn relative_targets_need_request_uri() {
+        let res = Response::builder()
+            .header("location", "/new")
+            .header("link", "</a>; rel=next, <https://my.test/b>; rel=last")
+            .body(Body::builder().data(""))
+            .unwrap();
+
+        assert!(res.location().is_none());
+        assert_eq!(res.links().len(), 1);
+        assert!(res.next_link().is_none());
+    }
+}
@@ -1,21 +1,34 @@
 use std::convert::TryFrom;
 use std::sync::Arc;
-use std::{io, mem};
+use std::time::Duration as StdDuration;
+use std::{io, mem, thread};
 
 use hoot::client::flow::state::{Await100, RecvBody, RecvResponse, Redirect, SendRequest};
 use hoot::client::flow::state::{Prepare, SendBody as SendBodyState};
+use hoot::client::flow::RedirectAuthHeaders;
 use hoot::client::flow::{Await100Result, RecvBodyResult, RecvResponseResult, SendRequestResult};
 use hoot::BodyMode;
 use http::uri::Scheme;
-use http::{header, HeaderValue, Request, Response, Uri};
-
-use crate::body::ResponseInfo;
-use crate::config::{Config, RequestLevelConfig};
+use http::{header, Extensions, HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use http::{Uri, Version};
+
+use crate::auth::Credentials;
+#[cfg(feature = "gzip")]
+use crate::body::EndGate;
+use crate::body::{ResponseInfo, TrailerParser, MAX_BODY_SIZE};
+use crate::config::{Config, RequestLevelConfig, RetryDecision};
+#[cfg(feature = "digest")]
+use crate::digest::DigestCheck;
+use crate::host_limit::HostPermit;
 use crate::pool::Connection;
-use crate::timings::{CallTimings, CurrentTime};
+use crate::request_id::RequestId;
+use crate::resolver::ResolvedSocketAddrs;
+use crate::response::ResponseUri;
+use crate::timings::{CallTimings, CurrentTime, NextTimeout};
 use crate::transport::time::{Duration, Instant};
 use crate::transport::ConnectionDetails;
-use crate::util::{DebugRequest, DebugResponse, DebugUri, HeaderMapExt, UriExt};
+use crate::uri::split_userinfo;
+use crate::util::{ArrayVec, DebugRequest, DebugResponse, DebugUri, HeaderMapExt, UriExt};
 use crate::{Agent, Body, Error, SendBody, Timeout};
 
 type Flow<T> = hoot::client::flow::Flow<(), T>;
@@ -29,6 +42,11 @@ pub(crate) fn run(
     mut body: SendBody,
 ) -> Result<Response<Body>, Error> {
     let mut redirect_count = 0;
+    let mut cross_host_count = 0;
+    let mut auth_retried = false;
+    let mut stale_retried = false;
+    let mut fresh_connection = false;
+    let mut attempts = 0;
 
     // Configuration on the request level overrides the agent level.
     let config = request
@@ -38,17 +56,24 @@ pub(crate) fn run(
         .map(Arc::new)
         .unwrap_or_else(|| agent.config.clone());
 
+    // Kept for the connector, since the flow doesn't carry them.
+    let extensions = mem::take(request.extensions_mut());
+
     let timeouts = config.timeouts;
 
     let mut timings = CallTimings::new(timeouts, CurrentTime::default());
 
+    apply_userinfo(&mut request);
+
+    let request_id = set_request_id(&config, request.headers_mut())?;
+
     let mut flow = Flow::new(request)?;
 
     if config.force_send_body {
         flow.send_body_despite_method();
     }
 
-    let (response, handler) = loop {
+    let (response, mut handler, uri) = loop {
         let timeout = timings.next_timeout(Timeout::Global);
         let timed_out = match timeout.after {
             Duration::Exact(v) => v.is_zero(),
@@ -58,28 +83,117 @@ pub(crate) fn run(
             return Err(Error::Timeout(Timeout::Global));
         }
 
-        match flow_run(
+        let method = flow.method().clone();
+        let uri = flow.uri().clone();
+        let auth_request = auth_request(&config, &flow, auth_retried);
+        let stale_request = stale_request(&config, &flow, stale_retried);
+        let replay_request = replay_request(&flow);
+        let retry_request = retry_request(&config, &flow);
+
+        let result = flow_run(
             agent,
             &config,
             flow,
             &mut body,
             redirect_count,
             &mut timings,
-        )? {
+            mem::take(&mut fresh_connection) || config.fresh_connection,
+            &extensions,
+        );
+
+        let result = match result {
+            Ok(v) => v,
+            Err(e) => {
+                let outcome = Err(&e);
+                let Some(retry) = retry_wait(&config, retry_request, attempts, outcome, &mut body)
+                else {
+                    return Err(e);
+                };
+
+                attempts += 1;
+                timings = mem::take(&mut timings).new_call();
+                flow = retry_flow(retry, &timings, &config)?;
+                continue;
+            }
+        };
+
+        match result {
             // Follow redirect
-            FlowResult::Redirect(rflow, rtimings) => {
+            FlowResult::Redirect(rflow, rtimings, location) => {
                 redirect_count += 1;
 
-                flow = handle_redirect(rflow, &config)?;
+                let previous = replay_request.zip(location);
+                flow = handle_redirect(rflow, previous, &method, &mut body, &config)?;
+                check_redirect(&config, &uri, flow.uri(), &mut cross_host_count)?;
                 timings = rtimings.new_call();
             }
 
-            // Return response
-            FlowResult::Response(response, handler) => break (response, handler),
+            FlowResult::Response(response, handler) => {
+                let retry = match auth_request {
+                    Some(r) => auth_retry(r, &method, &response, &mut body, &config)?,
+                    None => None,
+                };
+
+                let Some(retry) = retry else {
+                    let outcome = Ok(&response);
+                    let Some(retry) =
+                        retry_wait(&config, retry_request, attempts, outcome, &mut body)
+                    else {
+                        // Return response
+                        break (response, handler, uri);
+                    };
+
+                    attempts += 1;
+                    let prev = handler
+                        .drain_for_retry()
+                        .unwrap_or_else(|| mem::take(&mut timings));
+                    timings = prev.new_call();
+                    flow = retry_flow(retry, &timings, &config)?;
+                    continue;
+                };
+
+                // Retry with credentials
+                auth_retried = true;
+
+                let prev = handler
+                    .drain_for_retry()
+                    .unwrap_or_else(|| mem::take(&mut timings));
+
+                flow = retry;
+                timings = prev.new_call();
+            }
+
+            FlowResult::StaleConnection(error) => {
+                let Some(retry) = stale_retry(stale_request, &method, &mut body, &config)? else {
+                    let outcome = Err(&error);
+                    let Some(retry) =
+                        retry_wait(&config, retry_request, attempts, outcome, &mut body)
+                    else {
+                        return Err(error);
+                    };
+
+                    attempts += 1;
+                    timings = mem::take(&mut timings).new_call();
+                    flow = retry_flow(retry, &timings, &config)?;
+                    continue;
+                };
+
+                // Retry on a new connection
+                stale_retried = true;
+                fresh_connection = true;
+
+                flow = retry;
+                timings = mem::take(&mut timings).new_call();
+            }
         }
     };
 
-    let (parts, _) = response.into_parts();
+    let (mut parts, _) = response.into_parts();
+
+    if let Some(id) = request_id {
+        parts.extensions.insert(id);
+    }
+    parts.extensions.insert(ResponseUri(uri));
 
     let recv_body_mode = handler
         .flow
@@ -87,12 +201,63 @@ pub(crate) fn run(
         .map(|f| f.body_mode())
         .unwrap_or(BodyMode::NoBody);
 
-    let info = ResponseInfo::new(&parts.headers, recv_body_mode);
+    // No point in starting to read a body that is declared too large.
+    let too_large = match (config.max_response_size, recv_body_mode) {
+        (Some(max), BodyMode::LengthDelimited(len)) if len > max => Some(max),
+        _ => None,
+    };
+
+    if too_large.is_some() {
+        // Close the connection rather than drain the body.
+        handler.max_drain_on_drop = 0;
+    }
+
+    let info = ResponseInfo::new(&parts.headers, recv_body_mode)
+        .with_buffer_growth(config.body_buffer_capacity, config.body_buffer_max_growth)
+        .with_buffer_pool(agent.buffer_pool.clone());
+    remove_decoded_headers(&mut parts.headers, &info);
 
     let body = Body::new(handler, info);
 
     let response = Response::from_parts(parts, body);
 
+    let response = status_as_error(&config, response)?;
+
+    if let Some(max) = too_large {
+        return Err(Error::BodyExceedsLimit(max));
+    }
+
+    Ok(response)
+}
+
+/// Set the request id header, unless the request already has it.
+///
+/// Gives back the id either way, for the response.
+fn set_request_id(config: &Config, headers: &mut HeaderMap) -> Result<Option<RequestId>, Error> {
+    let Some(request_id) = &config.request_id else {
+        return Ok(None);
+    };
+
+    if let Some(v) = headers.get(&request_id.header) {
+        return Ok(v.to_str().ok().map(|v| RequestId(v.to_string())));
+    }
+
+    let id = request_id.generate();
+    let value = HeaderValue::from_str(&id).map_err(http::Error::from)?;
+    headers.insert(request_id.header.clone(), value);
+
+    Ok(Some(RequestId(id)))
+}
+
+/// The headers of a compressed body don't describe what is read once it's decoded.
+fn remove_decoded_headers(headers: &mut HeaderMap, info: &ResponseInfo) {
+    if info.is_decoded() {
+        headers.remove(header::CONTENT_LENGTH);
+        headers.remove(header::CONTENT_ENCODING);
+    }
+}
+
+fn status_as_error(config: &Config, response: Response<Body>) -> Result<Response<Body>, Error> {
     let status = response.status();
     let is_err = status.is_client_error() || status.is_server_error();
 
@@ -103,6 +268,215 @@ pub(crate) fn run(
     Ok(response)
 }
 
+/// Whether the requests can be sent pipelined on one connection.
+///
+/// Anything that might need a request sent again, or that can change the request
+/// per call, rules out pipelining.
+pub(crate) fn can_pipeline(config: &Config, requests: &[Request<()>]) -> bool {
+    let Some(first) = requests.first() else {
+        return false;
+    };
+
+    requests.len() > 1
+        && config.middleware.is_empty()
+        && config.auth_credentials.is_none()
+        && config.retry_predicate.is_none()
+        && requests.iter().all(|r| {
+            matches!(*r.method(), Method::GET | Method::HEAD)
+                && r.version() == Version::HTTP_11
+                && r.uri().scheme() == first.uri().scheme()
+                && r.uri().authority() == first.uri().authority()
+                && !r.headers().contains_key(header::EXPECT)
+                && r.extensions().get::<RequestLevelConfig>().is_none()
+        })
+}
+
+/// Run requests pipelined, sending all of them on one connection before reading the
+/// first response.
+///
+/// The results are in the order of the requests, but can be fewer than the requests.
+/// The ones left out need to be sent again without pipelining.
+pub(crate) fn run_pipeline(
+    agent: &Agent,
+    requests: &[Request<()>],
+) -> Vec<Result<Response<Body>, Error>> {
+    let mut responses = Vec::with_capacity(requests.len());
+
+    if let Err(e) = pipeline(agent, requests, &mut responses) {
+        debug!("Stop pipelining: {}", e);
+    }
+
+    responses
+        .into_iter()
+        .map(|r| status_as_error(&agent.config, r))
+        .collect()
+}
+
+fn pipeline(
+    agent: &Agent,
+    requests: &[Request<()>],
+    responses: &mut Vec<Response<Body>>,
+) -> Result<(), Error> {
+    let config = &agent.config;
+    let mut timings = CallTimings::new(config.timeouts, CurrentTime::default());
+
+    // All requests are for the same host.
+    let uri = requests[0].uri();
+    info!("Pipeline {} requests {:?}", requests.len(), &DebugUri(uri));
+
+    if config.https_only && uri.scheme() != Some(&Scheme::HTTPS) {
+        return Err(Error::RequireHttpsOnly(uri.to_string()));
+    }
+
+    let mut host_permit = match &agent.host_limiter {
+        Some(l) => {
+            let host = uri.host().unwrap_or_default();
+            Some(l.acquire(host, timings.next_timeout(Timeout::Global))?)
+        }
+        None => None,
+    };
+
+    let extensions = requests[0].extensions();
+    let mut connection = connect(agent, config, uri, &mut timings, false, extensions)?;
+
+    let mut flows = Vec::with_capacity(requests.len());
+    let mut request_ids = Vec::with_capacity(requests.len());
+
+    for request in requests {
+        if let Some(rate_limiter) = &agent.rate_limiter {
+            rate_limiter.acquire(timings.next_timeout(Timeout::Global))?;
+        }
+
+        // Each request is sent on the connection opened for the first.
+        timings.clear_from(Timeout::SendRequest);
+
+        let mut sent = request.clone();
+        request_ids.push(set_request_id(config, sent.headers_mut())?);
+
+        let mut flow = Flow::new(sent)?;
+        let mut added = HeaderMap::new();
+        add_headers(
+            &flow,
+            &mut added,
+            agent,
+            config,
+            &SendBody::none(),
+            request.uri(),
+        )?;
+        sign_request(&flow, &mut added, config, &SendBody::none())?;
+        set_headers(&mut flow, &added)?;
+        info!("{} {:?}", flow.method(), &DebugUri(flow.uri()));
+
+        let omit_host = omits_host(&flow);
+
+        let (result, _) = send_request(
+            flow.proceed(),
+            &mut connection,
+            config,
+            &mut timings,
+            false,
+            omit_host,
+        )?;
+
+        let SendRequestResult::RecvResponse(flow) = result else {
+            // Dropping the connection closes it, and all requests are sent again.
+            debug!("Request can't be pipelined");
+            return Ok(());
+        };
+
+        flows.push(flow);
+    }
+
+    // The server answers in the order the requests were sent.
+    for ((flow, request), request_id) in flows.into_iter().zip(requests).zip(request_ids) {
+        // The wait for each response counts from when the last request was sent.
+        timings.clear_from(Timeout::RecvResponse);
+        let (mut response, result) = recv_response(flow, &mut connection, config, &mut timings)?;
+
+        if let Some(id) = request_id {
+            response.extensions_mut().insert(id);
+        }
+        response
+            .extensions_mut()
+            .insert(ResponseUri(request.uri().clone()));
+
+        if let Some(p) = &mut host_permit {
+            p.succeeded();
+        }
+
+        let status = response.status();
+        if follows_redirect(config, status) && config.max_redirects > 0 {
+            // Redirects are followed without pipelining.
+            return Ok(());
+        }
+
+        add_connection_extensions(&mut response, &connection);
+
+        info!("{:?}", DebugResponse(&response));
+
+        store_cookies(agent, &response, request.uri());
+
+        let close_requested = closes_connection(&response);
+
+        let must_close = match result {
+            RecvResponseResult::RecvBody(flow) => {
+                // Each body is held in memory, so a large one is left to a plain call.
+                let max_size = config.max_response_size.unwrap_or(MAX_BODY_SIZE);
+                if let BodyMode::LengthDelimited(len) = flow.body_mode() {
+                    if len > max_size {
+                        debug!("Stop pipelining, body too large: {}", len);
+                        connection.close();
+                        return Ok(());
+                    }
+                }
+
+                let mut handler = BodyHandler::default();
+                handler.flow = Some(flow);
+                handler.connection = Some(connection);
+                handler.timings = mem::take(&mut timings);
+                handler.close_requested = close_requested;
+                handler.keep_connection = true;
+                handler.max_size = Some(max_size);
+                #[cfg(feature = "digest")]
+                {
+                    handler.digest = digest_check(config, response.headers());
+                }
+
+                // The next response can't be read until this body is.
+                let data = handler.read_to_vec()?;
+                timings = mem::take(&mut handler.timings);
+
+                let body_mode = BodyMode::LengthDelimited(data.len() as u64);
+                let info = ResponseInfo::new(response.headers(), body_mode);
+                remove_decoded_headers(response.headers_mut(), &info);
+                responses.push(response.map(|_| Body::buffered(data, info)));
+
+                // The handler only keeps a connection that can be used further.
+                let Some(c) = handler.connection.take() else {
+                    return Ok(());
+                };
+                connection = c;
+                continue;
+            }
+            RecvResponseResult::Redirect(flow) => flow.must_close_connection(),
+            RecvResponseResult::Cleanup(flow) => flow.must_close_connection(),
+        };
+
+        let info = ResponseInfo::new(response.headers(), BodyMode::NoBody);
+        responses.push(response.map(|_| Body::new(BodyHandler::default(), info)));
+
+        if must_close || close_requested {
+            connection.close();
+            return Ok(());
+        }
+    }
+
+    cleanup(connection, false, timings.now());
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 fn flow_run(
     agent: &Agent,
     config: &Config,
@@ -110,6 +484,8 @@ fn flow_run(
     body: &mut SendBody,
     redirect_count: u32,
     timings: &mut CallTimings,
+    fresh_connection: bool,
+    extensions: &Extensions,
 ) -> Result<FlowResult, Error> {
     let uri = flow.uri().clone();
     info!("{} {:?}", flow.method(), &DebugUri(flow.uri()));
@@ -118,9 +494,36 @@ fn flow_run(
         return Err(Error::RequireHttpsOnly(uri.to_string()));
     }
 
-    add_headers(&mut flow, agent, config, body, &uri)?;
+    if let Some(rate_limiter) = &agent.rate_limiter {
+        rate_limiter.acquire(timings.next_timeout(Timeout::Global))?;
+    }
+
+    let mut host_permit = match &agent.host_limiter {
+        Some(l) => {
+            let host = uri.host().unwrap_or_default();
+            Some(l.acquire(host, timings.next_timeout(Timeout::Global))?)
+        }
+        None => None,
+    };
+
+    let mut added = HeaderMap::new();
+    #[cfg(feature = "content-sniff")]
+    sniff_content_type(&flow, &mut added, config, body)?;
+    add_headers(&flow, &mut added, agent, config, body, &uri)?;
+    sign_request(&flow, &mut added, config, body)?;
+    set_headers(&mut flow, &added)?;
+
+    let mut connection = connect(agent, config, &uri, timings, fresh_connection, extensions)?;
+
+    let omit_host = omits_host(&flow);
 
-    let mut connection = connect(agent, config, &uri, timings)?;
+    // hoot closes every HTTP/1.0 connection. When keep-alive is asked for, the
+    // response decides instead. Without a Connection header of the user,
+    // add_headers() asks for keep-alive.
+    let headers = flow.headers();
+    let http10_keep_alive = flow.version() == Version::HTTP_10
+        && (!headers.has_connection() || has_connection_token(headers, "keep-alive"))
+        && !headers.contains_key(header::EXPECT);
 
     let mut flow = flow.proceed();
 
@@ -136,62 +539,85 @@ fn flow_run(
         info!("{:?}", r);
     }
 
-    let flow = match send_request(flow, &mut connection, timings)? {
-        SendRequestResult::Await100(flow) => match await_100(flow, &mut connection, timings)? {
-            Await100Result::SendBody(flow) => send_body(flow, body, &mut connection, timings)?,
-            Await100Result::RecvResponse(flow) => flow,
-        },
-        SendRequestResult::SendBody(flow) => send_body(flow, body, &mut connection, timings)?,
-        SendRequestResult::RecvResponse(flow) => flow,
+    let reused = connection.is_reused();
+
+    let result = send_and_recv(flow, body, &mut connection, config, timings, omit_host);
+
+    let (mut response, response_result) = match result {
+        Ok(v) => v,
+        Err(e) if reused && config.retry_stale_connection && is_stale_connection(&e) => {
+            debug!("Pooled connection is dead: {}", e);
+            return Ok(FlowResult::StaleConnection(e));
+        }
+        Err(e) => return Err(e),
     };
 
-    let (response, response_result) = recv_response(flow, &mut connection, config, timings)?;
+    if let Some(p) = &mut host_permit {
+        p.succeeded();
+    }
 
-    info!("{:?}", DebugResponse(&response));
+    add_connection_extensions(&mut response, &connection);
 
-    #[cfg(feature = "cookies")]
-    {
-        let mut jar = agent.cookie_jar_lock();
+    // A fresh connection is only for this request.
+    let close_requested = closes_connection(&response) || config.fresh_connection;
 
-        let iter = response
-            .headers()
-            .get_all(http::header::SET_COOKIE)
-            .iter()
-            .filter_map(|h| h.to_str().ok())
-            .filter_map(|s| crate::Cookie::parse(s, &uri).ok());
+    info!("{:?}", DebugResponse(&response));
 
-        jar.store_response_cookies(iter, &uri);
-    }
+    store_cookies(agent, &response, &uri);
 
     let ret = match response_result {
         RecvResponseResult::RecvBody(flow) => {
             let timings = mem::take(timings);
-            let mut handler = BodyHandler {
-                flow: Some(flow),
-                connection: Some(connection),
-                timings,
-                ..Default::default()
-            };
+            let trailer_parser =
+                matches!(flow.body_mode(), BodyMode::Chunked).then(TrailerParser::new);
+            // BodyHandler drains on drop, so it can't be built from ..Default::default().
+            let mut handler = BodyHandler::default();
+            handler.flow = Some(flow);
+            handler.connection = Some(connection);
+            handler.timings = timings;
+            handler.max_drain_on_drop = config.max_drain_on_drop;
+            handler.max_drain_time_on_drop = config.max_drain_time_on_drop;
+            handler.trailer_parser = trailer_parser;
+            handler.host_permit = host_permit;
+            handler.close_requested = close_requested;
+            handler.http10_keep_alive = http10_keep_alive;
+            handler.allow_extra_data = config.allow_extra_response_data;
+            handler.max_size = config.max_response_size;
+            #[cfg(feature = "digest")]
+            {
+                handler.digest = digest_check(config, response.headers());
+            }
 
-            if response.status().is_redirection() && redirect_count < config.max_redirects {
+            if follows_redirect(config, response.status()) && redirect_count < config.max_redirects
+            {
                 let flow = handler.consume_redirect_body()?;
+                let location = response.headers().get(header::LOCATION).cloned();
 
-                FlowResult::Redirect(flow, handler.timings)
+                FlowResult::Redirect(flow, mem::take(&mut handler.timings), location)
             } else {
                 FlowResult::Response(response, handler)
             }
         }
         RecvResponseResult::Redirect(flow) => {
-            cleanup(connection, flow.must_close_connection(), timings.now());
+            let hoot_close = flow.must_close_connection() && !http10_keep_alive;
+            let must_close = hoot_close || close_requested;
+            cleanup(connection, must_close, timings.now());
 
-            if redirect_count >= config.max_redirects {
+            if redirect_count >= config.max_redirects
+                || !follows_redirect(config, response.status())
+            {
                 FlowResult::Response(response, BodyHandler::default())
             } else {
-                FlowResult::Redirect(flow, mem::take(timings))
+                let location = response.headers().get(header::LOCATION).cloned();
+                FlowResult::Redirect(flow, mem::take(timings), location)
             }
         }
         RecvResponseResult::Cleanup(flow) => {
-            cleanup(connection, flow.must_close_connection(), timings.now());
+            let extra_data =
+                extra_response_data(&mut connection, config.allow_extra_response_data)?;
+            let hoot_close = flow.must_close_connection() && !http10_keep_alive;
+            let must_close = hoot_close || close_requested || extra_data;
+            cleanup(connection, must_close, timings.now());
             FlowResult::Response(response, BodyHandler::default())
         }
     };
@@ -199,18 +625,87 @@ fn flow_run(
     Ok(ret)
 }
 
+fn store_cookies(agent: &Agent, response: &Response<()>, uri: &Uri) {
+    #[cfg(not(feature = "cookies"))]
+    {
+        let _ = agent;
+        let _ = response;
+        let _ = uri;
+    }
+    #[cfg(feature = "cookies")]
+    {
+        let mut jar = agent.cookie_jar_lock();
+
+        let iter = response
+            .headers()
+            .get_all(http::header::SET_COOKIE)
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .filter_map(|s| crate::Cookie::parse(s, uri).ok());
+
+        jar.store_response_cookies(iter, uri);
+    }
+}
+
 /// Return type of [`flow_run`].
 #[allow(clippy::large_enum_variant)]
 enum FlowResult {
-    /// Flow resulted in a redirect.
-    Redirect(Flow<Redirect>, CallTimings),
+    /// Flow resulted in a redirect, with the `location` header of the response.
+    Redirect(Flow<Redirect>, CallTimings, Option<HeaderValue>),
 
     /// Flow resulted in a response.
     Response(Response<()>, BodyHandler),
+
+    /// A reused connection failed before the response.
+    StaleConnection(Error),
+}
+
+/// Send the request and body, and receive the response head.
+fn send_and_recv(
+    flow: Flow<SendRequest>,
+    body: &mut SendBody,
+    connection: &mut Connection,
+    config: &Config,
+    timings: &mut CallTimings,
+    omit_host: bool,
+) -> Result<(Response<()>, RecvResponseResult<()>), Error> {
+    // Small bodies are sent in the same write as the request head. So are larger
+    // in-memory bodies, using vectored IO.
+    let max_coalesce = config.max_coalesce_body_size as u64;
+    let coalesce = max_coalesce > 0
+        && matches!(
+            body.body_mode(),
+            BodyMode::LengthDelimited(n) if n <= max_coalesce || body.slice_len().is_some()
+        );
+
+    let (result, pending) = send_request(flow, connection, config, timings, coalesce, omit_host)?;
+
+    let flow = match result {
+        SendRequestResult::Await100(flow) => {
+            // The server must see the head to answer 100-continue.
+            transmit_pending(connection, pending, timings, Timeout::SendRequest)?;
+            match await_100(flow, connection, timings)? {
+                Await100Result::SendBody(flow) => {
+                    send_body(flow, body, connection, config, timings, 0)?
+                }
+                Await100Result::RecvResponse(flow) => flow,
+            }
+        }
+        SendRequestResult::SendBody(flow) => {
+            send_body(flow, body, connection, config, timings, pending)?
+        }
+        SendRequestResult::RecvResponse(flow) => {
+            transmit_pending(connection, pending, timings, Timeout::SendRequest)?;
+            flow
+        }
+    };
+
+    recv_response(flow, connection, config, timings)
 }
 
 fn add_headers(
-    flow: &mut Flow<Prepare>,
+    flow: &Flow<Prepare>,
+    added: &mut HeaderMap,
     agent: &Agent,
     config: &Config,
     body: &SendBody,
@@ -227,6 +722,15 @@ fn add_headers(
     let has_header_accept_enc = headers.has_accept_encoding();
     let has_header_ua = headers.has_user_agent();
     let has_header_accept = headers.has_accept();
+    let has_header_connection = headers.has_connection();
+
+    if let Some(trailers) = body.trailers() {
+        let is_chunked = match send_body_mode {
+            Some(mode) => matches!(mode, BodyMode::Chunked),
+            None => headers.is_chunked(),
+        };
+        check_trailers(headers, trailers, is_chunked)?;
+    }
 
     #[cfg(not(feature = "cookies"))]
     {
@@ -239,7 +743,7 @@ fn add_headers(
         if !value.is_empty() {
             let value = HeaderValue::from_str(&value)
                 .map_err(|_| Error::CookieValue("Cookie value is an invalid http-header"))?;
-            flow.header(header::COOKIE, value)?;
+            added.insert(header::COOKIE, value);
         }
     }
 
@@ -247,9 +751,9 @@ fn add_headers(
     {
         use once_cell::sync::Lazy;
         static ACCEPTS: Lazy<String> = Lazy::new(|| {
-            let mut value = String::with_capacity(10);
+            let mut value = String::with_capacity(20);
             #[cfg(feature = "gzip")]
-            value.push_str("gzip");
+            value.push_str("gzip, deflate");
             #[cfg(all(feature = "gzip", feature = "brotli"))]
             value.push_str(", ");
             #[cfg(feature = "brotli")]
@@ -259,7 +763,7 @@ fn add_headers(
         // unwrap is ok because above ACCEPTS will produce a valid value
         let value = HeaderValue::from_str(&ACCEPTS).unwrap();
         if !has_header_accept_enc {
-            flow.header(header::ACCEPT_ENCODING, value)?;
+            added.insert(header::ACCEPT_ENCODING, value);
         }
     }
 
@@ -267,36 +771,222 @@ fn add_headers(
         match send_body_mode {
             BodyMode::LengthDelimited(v) => {
                 let value = HeaderValue::from(v);
-                flow.header(header::CONTENT_LENGTH, value)?;
+                added.insert(header::CONTENT_LENGTH, value);
             }
             BodyMode::Chunked => {
                 let value = HeaderValue::from_static("chunked");
-                flow.header(header::TRANSFER_ENCODING, value)?;
+                added.insert(header::TRANSFER_ENCODING, value);
             }
             _ => {}
         }
     }
 
+    #[cfg(feature = "digest")]
+    if config.send_content_digest && !flow.headers().contains_key("content-digest") {
+        if let Some(data) = body.slice() {
+            let name = header::HeaderName::from_static("content-digest");
+            added.insert(name, crate::digest::content_digest(data));
+        }
+    }
+
     if !has_header_ua {
         // unwrap is ok because a user might override the agent, and if they
         // set bad values, it's not really a big problem.
         let value = HeaderValue::try_from(config.get_user_agent()).unwrap();
-        flow.header(header::USER_AGENT, value)?;
+        added.insert(header::USER_AGENT, value);
     }
 
     if !has_header_accept {
         let value = HeaderValue::from_static("*/*");
-        flow.header(header::ACCEPT, value)?;
+        added.insert(header::ACCEPT, value);
+    }
+
+    // HTTP/1.0 connections are closed after each response, unless the client
+    // asks to keep it and the server agrees.
+    if !has_header_connection && flow.version() == Version::HTTP_10 {
+        let value = HeaderValue::from_static("keep-alive");
+        added.insert(header::CONNECTION, value);
+    }
+
+    Ok(())
+}
+
+/// Set the content-type from the start of the body, unless the request has one.
+///
+/// See [`RequestBuilder::sniff_content_type`](crate::RequestBuilder::sniff_content_type).
+#[cfg(feature = "content-sniff")]
+fn sniff_content_type(
+    flow: &Flow<Prepare>,
+    added: &mut HeaderMap,
+    config: &Config,
+    body: &mut SendBody,
+) -> Result<(), Error> {
+    use crate::sniff::{sniff, SNIFF_LEN};
+
+    if !config.sniff_content_type || flow.headers().contains_key(header::CONTENT_TYPE) {
+        return Ok(());
+    }
+
+    if let Some(mime) = sniff(body.peek(SNIFF_LEN)?) {
+        added.insert(header::CONTENT_TYPE, HeaderValue::from_static(mime));
+    }
+
+    Ok(())
+}
+
+/// Add the headers of the configured request signer, once all other headers are known.
+///
+/// The signer sees the headers of the request together with those `added` by ureq.
+fn sign_request(
+    flow: &Flow<Prepare>,
+    added: &mut HeaderMap,
+    config: &Config,
+    body: &SendBody,
+) -> Result<(), Error> {
+    let Some(signer) = &config.request_signer else {
+        return Ok(());
+    };
+
+    let mut request = Request::builder()
+        .method(flow.method().clone())
+        .uri(flow.uri().clone())
+        .version(flow.version())
+        .body(())?;
+    *request.headers_mut() = flow.headers().clone();
+    for (name, value) in &*added {
+        request.headers_mut().append(name, value.clone());
+    }
+
+    // Without a body, the payload is known to be empty.
+    let data = match body.body_mode() {
+        BodyMode::NoBody => Some(&[][..]),
+        _ => body.slice(),
+    };
+
+    let headers = (signer.0)(&request, data)?;
+
+    for (name, value) in &headers {
+        added.append(name, value.clone());
+    }
+
+    Ok(())
+}
+
+/// Set the headers ureq `added` on the request.
+///
+/// hoot keeps these apart, `Flow::headers()` only has the headers of the request.
+fn set_headers(flow: &mut Flow<Prepare>, added: &HeaderMap) -> Result<(), Error> {
+    for (name, value) in added {
+        flow.header(name.clone(), value.clone())?;
+    }
+
+    Ok(())
+}
+
+fn check_trailers(
+    headers: &HeaderMap,
+    trailers: &HeaderMap,
+    is_chunked: bool,
+) -> Result<(), Error> {
+    if !is_chunked {
+        return Err(Error::InvalidTrailer(
+            "trailers require a chunked request body".into(),
+        ));
+    }
+
+    let announced: Vec<&str> = headers
+        .get_all(header::TRAILER)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim())
+        .collect();
+
+    for name in trailers.keys() {
+        if !announced
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(name.as_str()))
+        {
+            return Err(Error::InvalidTrailer(format!(
+                "{} is not announced in the trailer header",
+                name
+            )));
+        }
     }
 
     Ok(())
 }
 
-fn connect(
+/// Replace the terminating `0\r\n\r\n` of a chunked body with one carrying trailers.
+fn write_trailers(
+    output: &mut [u8],
+    output_used: usize,
+    trailers: &HeaderMap,
+) -> Result<usize, Error> {
+    const END: &[u8] = b"0\r\n\r\n";
+
+    if !output[..output_used].ends_with(END) {
+        return Err(Error::InvalidTrailer(
+            "body did not end with a last chunk".into(),
+        ));
+    }
+
+    // Keep the "0\r\n" and overwrite the final "\r\n".
+    let mut pos = output_used - 2;
+
+    let mut push = |bytes: &[u8]| {
+        let end = pos + bytes.len();
+        if end > output.len() {
+            return Err(Error::InvalidTrailer("trailers too large".into()));
+        }
+        output[pos..end].copy_from_slice(bytes);
+        pos = end;
+        Ok(())
+    };
+
+    for (name, value) in trailers {
+        push(name.as_str().as_bytes())?;
+        push(b": ")?;
+        push(value.as_bytes())?;
+        push(b"\r\n")?;
+    }
+    push(b"\r\n")?;
+
+    Ok(pos)
+}
+
+/// Expose details about the connection used for a response as response extensions.
+fn add_connection_extensions(response: &mut Response<()>, connection: &Connection) {
+    #[cfg(not(feature = "_tls"))]
+    {
+        let _ = response;
+        let _ = connection;
+    }
+    #[cfg(feature = "_tls")]
+    {
+        if let Some(alpn) = connection.alpn_protocol() {
+            response
+                .extensions_mut()
+                .insert(crate::tls::AlpnProtocol(alpn));
+        }
+        if let Some(certs) = connection.peer_certificates() {
+            response
+                .extensions_mut()
+                .insert(crate::tls::PeerCertificates::from_der(certs));
+        }
+        if let Some(info) = connection.tls_info() {
+            response.extensions_mut().insert(info);
+        }
+    }
+}
+
+pub(crate) fn connect(
     agent: &Agent,
     config: &Config,
     uri: &Uri,
     timings: &mut CallTimings,
+    fresh: bool,
+    extensions: &Extensions,
 ) -> Result<Connection, Error> {
     // If we're using a CONNECT proxy, we need to resolve that hostname.
     let maybe_connect_uri = config.connect_proxy_uri();
@@ -315,6 +1005,12 @@ fn connect(
 
     timings.record_time(Timeout::Resolve);
 
+    // Via a CONNECT proxy, the addresses are for the proxy.
+    let addrs = match config.connect_port {
+        Some(port) if maybe_connect_uri.is_none() => with_port(&addrs, port),
+        _ => addrs,
+    };
+
     let details = ConnectionDetails {
         uri,
         addrs,
@@ -322,20 +1018,50 @@ fn connect(
         config,
         now: timings.now(),
         timeout: timings.next_timeout(Timeout::Connect),
+        extensions,
     };
 
-    let connection = agent.pool.connect(&details, config.max_idle_age.into())?;
+    let connection = agent
+        .pool
+        .connect(&details, config.max_idle_age.into(), fresh)?;
 
     timings.record_time(Timeout::Connect);
 
     Ok(connection)
 }
 
+fn with_port(addrs: &ResolvedSocketAddrs, port: u16) -> ResolvedSocketAddrs {
+    let mut overridden = ArrayVec::from_fn(|_| "0.0.0.0:0".parse().unwrap());
+    for addr in addrs {
+        let mut addr = *addr;
+        addr.set_port(port);
+        overridden.push(addr);
+    }
+    overridden
+}
+
+/// Send the request head.
+///
+/// With `coalesce`, the last part of the head is left in the output buffer for the body
+/// to be appended. Returns how many bytes are pending in the output buffer.
 fn send_request(
     mut flow: Flow<SendRequest>,
     connection: &mut Connection,
+    config: &Config,
     timings: &mut CallTimings,
-) -> Result<SendRequestResult<()>, Error> {
+    coalesce: bool,
+    omit_host: bool,
+) -> Result<(SendRequestResult<()>, usize), Error> {
+    let header_casing = &config.header_casing;
+    let mut pending = 0;
+
+    // Only allocated when someone looks at it.
+    let mut captured = config.capture_request_head.as_ref().map(|_| Vec::new());
+
+    // A header can be split over several writes, so a rewrite needs the whole head.
+    let rewrite = omit_host || !header_casing.is_empty();
+    let mut head = Vec::new();
+
     loop {
         if flow.can_proceed() {
             break;
@@ -343,12 +1069,151 @@ fn send_request(
 
         let buffers = connection.buffers();
         let amount = flow.write(buffers.output())?;
-        let timeout = timings.next_timeout(Timeout::SendRequest);
-        connection.transmit_output(amount, timeout)?;
-    }
+
+        if rewrite {
+            head.extend_from_slice(&buffers.output()[..amount]);
+            continue;
+        }
+
+        if let Some(c) = &mut captured {
+            c.extend_from_slice(&buffers.output()[..amount]);
+        }
+
+        if coalesce && flow.can_proceed() {
+            pending = amount;
+            break;
+        }
+
+        let timeout = timings.next_timeout(Timeout::SendRequest);
+        connection.transmit_output(amount, timeout)?;
+    }
+
+    if rewrite {
+        if omit_host {
+            remove_host_header(&mut head);
+        }
+
+        if !header_casing.is_empty() {
+            recase_header_names(&mut head, header_casing);
+        }
+
+        if let Some(c) = &mut captured {
+            c.extend_from_slice(&head);
+        }
+
+        let mut rest = &head[..];
+        while !rest.is_empty() {
+            let output = connection.buffers().output();
+            let amount = rest.len().min(output.len());
+            output[..amount].copy_from_slice(&rest[..amount]);
+            rest = &rest[amount..];
+
+            if coalesce && rest.is_empty() {
+                pending = amount;
+                break;
+            }
+
+            let timeout = timings.next_timeout(Timeout::SendRequest);
+            connection.transmit_output(amount, timeout)?;
+        }
+    }
 
     timings.record_time(Timeout::SendRequest);
-    Ok(flow.proceed().unwrap())
+
+    if let (Some(capture), Some(mut head)) = (&config.capture_request_head, captured) {
+        if config.redact_captured_headers {
+            head = redact_head(&head);
+        }
+        (capture.0)(&head);
+    }
+
+    Ok((flow.proceed().unwrap(), pending))
+}
+
+/// Replace the values of sensitive headers in a written request head.
+fn redact_head(head: &[u8]) -> Vec<u8> {
+    const SENSITIVE: &[&[u8]] = &[b"authorization", b"proxy-authorization", b"cookie"];
+
+    let mut out = Vec::with_capacity(head.len());
+
+    for line in head.split_inclusive(|b| *b == b'\n') {
+        let colon = line.iter().position(|b| *b == b':');
+
+        match colon {
+            Some(i) if SENSITIVE.iter().any(|s| s.eq_ignore_ascii_case(&line[..i])) => {
+                out.extend_from_slice(&line[..i]);
+                out.extend_from_slice(b": ***\r\n");
+            }
+            _ => out.extend_from_slice(line),
+        }
+    }
+
+    out
+}
+
+/// Rewrite header names in the written request head to the configured casing.
+///
+/// Changing case doesn't change the length, which means this can be done in place.
+fn recase_header_names(head: &mut [u8], header_casing: &[String]) {
+    // The first line is the request line.
+    for line in head.split_mut(|b| *b == b'\n').skip(1) {
+        let Some(colon) = line.iter().position(|b| *b == b':') else {
+            continue;
+        };
+        let name = &mut line[..colon];
+        if let Some(casing) = header_casing
+            .iter()
+            .find(|c| c.as_bytes().eq_ignore_ascii_case(name))
+        {
+            name.copy_from_slice(casing.as_bytes());
+        }
+    }
+}
+
+/// Whether the request asks for no `host` header, by setting an empty one.
+///
+/// See [`RequestBuilder::host()`](crate::RequestBuilder::host).
+fn omits_host(flow: &Flow<Prepare>) -> bool {
+    flow.headers()
+        .get(header::HOST)
+        .map(|v| v.is_empty())
+        .unwrap_or(false)
+}
+
+/// Remove the `host` line from the written request head.
+fn remove_host_header(head: &mut Vec<u8>) {
+    const NAME: &[u8] = b"host:";
+
+    // The first line is the request line.
+    let Some(n) = head.iter().position(|b| *b == b'\n') else {
+        return;
+    };
+
+    let mut start = n + 1;
+    while let Some(n) = head[start..].iter().position(|b| *b == b'\n') {
+        let end = start + n + 1;
+
+        let line = &head[start..end];
+        if line.len() > NAME.len() && line[..NAME.len()].eq_ignore_ascii_case(NAME) {
+            head.drain(start..end);
+            return;
+        }
+
+        start = end;
+    }
+}
+
+fn transmit_pending(
+    connection: &mut Connection,
+    pending: usize,
+    timings: &mut CallTimings,
+    reason: Timeout,
+) -> Result<(), Error> {
+    if pending > 0 {
+        let timeout = timings.next_timeout(reason);
+        connection.transmit_output(pending, timeout)?;
+    }
+    Ok(())
 }
 
 fn await_100(
@@ -391,7 +1256,9 @@ fn send_body(
     mut flow: Flow<SendBodyState>,
     body: &mut SendBody,
     connection: &mut Connection,
+    config: &Config,
     timings: &mut CallTimings,
+    mut pending: usize,
 ) -> Result<Flow<RecvResponse>, Error> {
     loop {
         if flow.can_proceed() {
@@ -408,16 +1275,40 @@ fn send_body(
         assert!(input_len > overhead);
         let max_input = input_len - overhead;
 
-        let output_used = if overhead == 0 {
+        let large_slice = body
+            .slice_len()
+            .map(|n| n > config.max_coalesce_body_size)
+            .unwrap_or(false);
+
+        if overhead == 0 && large_slice {
+            // Large in-memory bodies are sent as is, instead of being copied to the
+            // output buffer.
+            let data = body.take_slice().unwrap_or_default();
+            flow.consume_direct_write(data.len())?;
+
+            let timeout = timings.next_timeout(Timeout::SendBody);
+            connection.transmit_output_vectored(pending, data, timeout)?;
+            pending = 0;
+
+            continue;
+        }
+
+        let output_used = if pending > 0 && (overhead > 0 || pending == output.len()) {
+            // Chunked bodies are not coalesced with the head, and there must be room
+            // left in the output for the body.
+            pending
+        } else if overhead == 0 {
             // overhead == 0 means we are not doing chunked transfer. The body can be written
             // directly to the output. This optimizes away a memcopy if we were to go via
             // flow.write().
-            let output_used = body.read(output)?;
+            //
+            // The body goes after any request head pending in the output.
+            let body_used = body.read(&mut output[pending..])?;
 
             // Size checking is still in the flow.
-            flow.consume_direct_write(output_used)?;
+            flow.consume_direct_write(body_used)?;
 
-            output_used
+            pending + body_used
         } else {
             let tmp = &mut tmp[..max_input];
             let n = body.read(tmp)?;
@@ -428,13 +1319,21 @@ fn send_body(
             // the entire input we read from the body should also be shipped to the output.
             assert!(input_used == n);
 
-            output_used
+            match body.trailers() {
+                Some(trailers) if n == 0 => write_trailers(output, output_used, trailers)?,
+                _ => output_used,
+            }
         };
 
+        pending = 0;
+
         let timeout = timings.next_timeout(Timeout::SendBody);
         connection.transmit_output(output_used, timeout)?;
     }
 
+    // The flow can be done without writing anything, such as for an empty body.
+    transmit_pending(connection, pending, timings, Timeout::SendBody)?;
+
     timings.record_time(Timeout::SendBody);
     Ok(flow.proceed().unwrap())
 }
@@ -471,24 +1370,486 @@ fn recv_response(
     };
 
     timings.record_time(Timeout::RecvResponse);
-    Ok((response, flow.proceed().unwrap()))
+
+    let result = flow.proceed().unwrap();
+
+    // Without a body to read, such as for HEAD, the framing headers don't matter.
+    let has_body = !matches!(result, RecvResponseResult::Cleanup(_));
+
+    if has_body && !config.allow_ambiguous_framing {
+        check_framing(response.headers())?;
+    }
+
+    Ok((response, result))
 }
 
-fn handle_redirect(mut flow: Flow<Redirect>, config: &Config) -> Result<Flow<Prepare>, Error> {
-    let maybe_new_flow = flow.as_new_flow(config.redirect_auth_headers)?;
+#[cfg(feature = "digest")]
+fn digest_check(config: &Config, headers: &HeaderMap) -> Option<DigestCheck> {
+    if !config.verify_content_digest {
+        return None;
+    }
+    DigestCheck::from_headers(headers)
+}
+
+/// Reject responses where the body length can be read in more than one way.
+fn check_framing(headers: &HeaderMap) -> Result<(), Error> {
+    // Several content-length, either as separate headers or comma separated, are only
+    // ok when they are all the same.
+    let mut lengths = headers
+        .get_all(header::CONTENT_LENGTH)
+        .iter()
+        .flat_map(|v| v.to_str().unwrap_or_default().split(','))
+        .map(str::trim);
+
+    let Some(first) = lengths.next() else {
+        return Ok(());
+    };
+
+    if lengths.any(|v| v != first) {
+        return Err(Error::AmbiguousFraming(
+            "differing content-length headers".into(),
+        ));
+    }
+
+    let chunked = headers
+        .get_all(header::TRANSFER_ENCODING)
+        .iter()
+        .flat_map(|v| v.to_str().unwrap_or_default().split(','))
+        .any(|v| v.trim().eq_ignore_ascii_case("chunked"));
+
+    if chunked {
+        return Err(Error::AmbiguousFraming(
+            "both content-length and transfer-encoding: chunked".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn handle_redirect(
+    mut flow: Flow<Redirect>,
+    previous: Option<((Uri, HeaderMap), HeaderValue)>,
+    method: &Method,
+    body: &mut SendBody,
+    config: &Config,
+) -> Result<Flow<Prepare>, Error> {
+    let maybe_new_flow = match flow.as_new_flow(config.redirect_auth_headers)? {
+        Some(v) => Some(v),
+        // hoot doesn't follow 307/308 for methods with a body.
+        None => replay_flow(previous, method, config)?,
+    };
     let status = flow.status();
 
-    if let Some(flow) = maybe_new_flow {
-        info!(
-            "Redirect ({}): {} {:?}",
-            status,
-            flow.method(),
-            DebugUri(flow.uri())
-        );
+    let Some(flow) = maybe_new_flow else {
+        return Err(Error::RedirectFailed);
+    };
 
-        Ok(flow)
+    let redirect_method = redirect_method(status, method, config.redirect_post_as_get);
+
+    if redirect_method == method {
+        // Same method means sending the same body again.
+        if !body.rewind() {
+            return Err(Error::RedirectFailed);
+        }
     } else {
-        Err(Error::RedirectFailed)
+        // Changing method drops the body.
+        body.clear();
+    }
+
+    let flow = if flow.method() != redirect_method {
+        with_method(flow, redirect_method, config)?
+    } else {
+        flow
+    };
+
+    info!(
+        "Redirect ({}): {} {:?}",
+        status,
+        flow.method(),
+        DebugUri(flow.uri())
+    );
+
+    Ok(flow)
+}
+
+/// Keep the request parts to follow a `307`/`308` redirect of a method with a body.
+fn replay_request(flow: &Flow<Prepare>) -> Option<(Uri, HeaderMap)> {
+    let method = flow.method();
+    if *method != Method::POST && *method != Method::PUT && *method != Method::PATCH {
+        return None;
+    }
+
+    Some((flow.uri().clone(), flow.headers().clone()))
+}
+
+/// Recreate the request for the redirect `location`, with the same method.
+///
+/// `None` means the redirect can't be followed.
+fn replay_flow(
+    previous: Option<((Uri, HeaderMap), HeaderValue)>,
+    method: &Method,
+    config: &Config,
+) -> Result<Option<Flow<Prepare>>, Error> {
+    let Some(((prev_uri, mut headers), location)) = previous else {
+        return Ok(None);
+    };
+
+    let Some(uri) = location
+        .to_str()
+        .ok()
+        .and_then(|l| crate::link::resolve(&prev_uri, l))
+    else {
+        return Ok(None);
+    };
+
+    let keep_auth_header = config.redirect_auth_headers == RedirectAuthHeaders::SameHost
+        && prev_uri.host() == uri.host()
+        && (prev_uri.scheme() == uri.scheme() || uri.scheme() == Some(&Scheme::HTTPS));
+
+    if !keep_auth_header {
+        headers.remove(header::AUTHORIZATION);
+    }
+    // Set again for the new request.
+    headers.remove(header::COOKIE);
+    headers.remove(header::CONTENT_LENGTH);
+
+    new_flow(uri, headers, method, config).map(Some)
+}
+
+/// Whether a response with this status is followed as a redirect.
+///
+/// See [`ConfigBuilder::redirect_statuses`](crate::config::ConfigBuilder::redirect_statuses).
+fn follows_redirect(config: &Config, status: StatusCode) -> bool {
+    status.is_redirection() && config.redirect_statuses.contains(&status.as_u16())
+}
+
+/// Apply the configured limits on where redirects may go.
+fn check_redirect(
+    config: &Config,
+    from: &Uri,
+    to: &Uri,
+    cross_host_count: &mut u32,
+) -> Result<(), Error> {
+    if from.authority() != to.authority() {
+        *cross_host_count += 1;
+
+        if let Some(max) = config.max_cross_host_redirects {
+            if *cross_host_count > max {
+                let reason = format!("more than {} cross-host redirects: {:?}", max, DebugUri(to));
+                return Err(Error::RedirectDenied(reason));
+            }
+        }
+    }
+
+    if let Some(check) = &config.redirect_check {
+        (check.0)(from, to)?;
+    }
+
+    Ok(())
+}
+
+/// The method to use when following a redirect.
+///
+/// See [`ConfigBuilder::redirect_post_as_get`](crate::config::ConfigBuilder::redirect_post_as_get).
+fn redirect_method(status: StatusCode, method: &Method, post_as_get: bool) -> &Method {
+    match status {
+        StatusCode::SEE_OTHER if method != Method::HEAD => &Method::GET,
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND
+            if post_as_get && method == Method::POST =>
+        {
+            &Method::GET
+        }
+        _ => method,
+    }
+}
+
+/// Recreate the redirect flow with another method.
+fn with_method(
+    flow: Flow<Prepare>,
+    method: &Method,
+    config: &Config,
+) -> Result<Flow<Prepare>, Error> {
+    let mut headers = flow.headers().clone();
+
+    if *method == Method::GET {
+        // The body is dropped with the method change.
+        headers.remove(header::CONTENT_LENGTH);
+        headers.remove(header::TRANSFER_ENCODING);
+        headers.remove(header::CONTENT_TYPE);
+    }
+
+    new_flow(flow.uri().clone(), headers, method, config)
+}
+
+/// Move userinfo of the uri to a Basic `Authorization` header.
+///
+/// The userinfo is removed even when there already is an explicit header.
+fn apply_userinfo(request: &mut Request<()>) {
+    let Some((uri, username, password)) = split_userinfo(request.uri()) else {
+        return;
+    };
+
+    *request.uri_mut() = uri;
+
+    if !request.headers().contains_key(header::AUTHORIZATION) {
+        let basic = Credentials::new(&username, &password).basic();
+        // unwrap is ok, base64 is always a valid header value.
+        let value = HeaderValue::from_str(&basic).unwrap();
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+    }
+}
+
+/// Keep the request parts needed to answer a `401` challenge with the configured credentials.
+fn auth_request(config: &Config, flow: &Flow<Prepare>, retried: bool) -> Option<(Uri, HeaderMap)> {
+    // Credentials are only used once, and never override an explicit header.
+    if retried
+        || config.auth_credentials.is_none()
+        || flow.headers().contains_key(header::AUTHORIZATION)
+    {
+        return None;
+    }
+
+    Some((flow.uri().clone(), flow.headers().clone()))
+}
+
+/// Recreate the flow with an `Authorization` header answering a `401` challenge.
+///
+/// `None` means the response should be returned as is.
+fn auth_retry(
+    (uri, mut headers): (Uri, HeaderMap),
+    method: &Method,
+    response: &Response<()>,
+    body: &mut SendBody,
+    config: &Config,
+) -> Result<Option<Flow<Prepare>>, Error> {
+    let Some(credentials) = &config.auth_credentials else {
+        return Ok(None);
+    };
+
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+
+    let Some(value) = credentials.authorization(response.headers(), method, &uri) else {
+        debug!("No supported challenge in 401 response");
+        return Ok(None);
+    };
+
+    if !body.rewind() {
+        debug!("Not answering 401 challenge, request body can't be sent again");
+        return Ok(None);
+    }
+
+    headers.insert(header::AUTHORIZATION, value);
+
+    let flow = new_flow(uri, headers, method, config)?;
+
+    info!(
+        "Answer 401 challenge: {} {:?}",
+        method,
+        DebugUri(flow.uri())
+    );
+
+    Ok(Some(flow))
+}
+
+/// Keep what is needed for sending the request again if the connection is stale.
+fn stale_request(config: &Config, flow: &Flow<Prepare>, retried: bool) -> Option<(Uri, HeaderMap)> {
+    if retried || !config.retry_stale_connection || !is_idempotent(flow.method()) {
+        return None;
+    }
+
+    Some((flow.uri().clone(), flow.headers().clone()))
+}
+
+/// Recreate the flow after a pooled connection failed.
+///
+/// `None` means the error should be returned as is.
+fn stale_retry(
+    stale_request: Option<(Uri, HeaderMap)>,
+    method: &Method,
+    body: &mut SendBody,
+    config: &Config,
+) -> Result<Option<Flow<Prepare>>, Error> {
+    let Some((uri, headers)) = stale_request else {
+        return Ok(None);
+    };
+
+    if !body.rewind() {
+        debug!("Not retrying on new connection, request body can't be sent again");
+        return Ok(None);
+    }
+
+    let flow = new_flow(uri, headers, method, config)?;
+
+    info!(
+        "Retry on new connection: {} {:?}",
+        method,
+        DebugUri(flow.uri())
+    );
+
+    Ok(Some(flow))
+}
+
+/// Keep the request for the retry predicate, if there is one.
+fn retry_request(config: &Config, flow: &Flow<Prepare>) -> Option<Request<()>> {
+    config.retry_predicate.as_ref()?;
+
+    let mut request = Request::builder()
+        .method(flow.method().clone())
+        .uri(flow.uri().clone())
+        .body(())
+        .ok()?;
+    *request.headers_mut() = flow.headers().clone();
+
+    Some(request)
+}
+
+/// Ask the retry predicate about the outcome of an attempt.
+///
+/// `None` means the outcome is the result of the call.
+fn retry_wait(
+    config: &Config,
+    retry_request: Option<Request<()>>,
+    attempts: u32,
+    outcome: Result<&Response<()>, &Error>,
+    body: &mut SendBody,
+) -> Option<(StdDuration, Request<()>)> {
+    let predicate = config.retry_predicate.as_ref()?;
+    let request = retry_request?;
+
+    let RetryDecision::Retry(wait) = (predicate.0)(attempts + 1, &request, outcome) else {
+        return None;
+    };
+
+    if !body.rewind() {
+        debug!("Not retrying, request body can't be sent again");
+        return None;
+    }
+
+    Some((wait, request))
+}
+
+/// Wait, then recreate the flow for another attempt.
+fn retry_flow(
+    (wait, request): (StdDuration, Request<()>),
+    timings: &CallTimings,
+    config: &Config,
+) -> Result<Flow<Prepare>, Error> {
+    if let Duration::Exact(remaining) = timings.next_timeout(Timeout::Global).after {
+        if wait >= remaining {
+            debug!("Not waiting {:?} to retry, past the global timeout", wait);
+            return Err(Error::Timeout(Timeout::Global));
+        }
+    }
+
+    thread::sleep(wait);
+
+    let (parts, _) = request.into_parts();
+    let flow = new_flow(parts.uri, parts.headers, &parts.method, config)?;
+
+    info!(
+        "Retry after {:?}: {} {:?}",
+        wait,
+        parts.method,
+        DebugUri(flow.uri())
+    );
+
+    Ok(flow)
+}
+
+fn new_flow(
+    uri: Uri,
+    headers: HeaderMap,
+    method: &Method,
+    config: &Config,
+) -> Result<Flow<Prepare>, Error> {
+    let mut request = Request::builder()
+        .method(method.clone())
+        .uri(uri)
+        .body(())?;
+    *request.headers_mut() = headers;
+
+    let mut flow = Flow::new(request)?;
+
+    if config.force_send_body {
+        flow.send_body_despite_method();
+    }
+
+    Ok(flow)
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Whether the error looks like the server had closed the connection.
+fn is_stale_connection(error: &Error) -> bool {
+    let Error::Io(e) = error else {
+        return false;
+    };
+
+    matches!(
+        e.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+    )
+}
+
+/// Whether the server wants the connection closed after the response.
+///
+/// That is either an explicit `Connection: close`, or an HTTP/1.0 response without
+/// `Connection: keep-alive`, since 1.0 connections are not persistent by default.
+fn closes_connection(response: &Response<()>) -> bool {
+    let headers = response.headers();
+
+    if has_connection_token(headers, "close") {
+        return true;
+    }
+
+    response.version() == Version::HTTP_10 && !has_connection_token(headers, "keep-alive")
+}
+
+fn has_connection_token(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| v.trim().eq_ignore_ascii_case(token))
+}
+
+/// Check for data after the end of a response, which means the server is misbehaving.
+///
+/// Returns whether the data was ignored, in which case the connection must be closed.
+fn extra_response_data(connection: &mut Connection, allow: bool) -> Result<bool, Error> {
+    let extra = connection.buffers().input().len();
+
+    if extra == 0 {
+        return Ok(false);
+    }
+
+    if !allow {
+        return Err(Error::ExtraResponseData(extra));
+    }
+
+    warn!(
+        "Ignore {} bytes after the response, close connection",
+        extra
+    );
+    Ok(true)
+}
+
+/// Bodies without a declared length are checked against max_response_size as they are read.
+fn check_max_size(received: u64, max_size: Option<u64>) -> Result<(), Error> {
+    match max_size {
+        Some(max) if received > max => Err(Error::BodyExceedsLimit(max)),
+        _ => Ok(()),
     }
 }
 
@@ -507,13 +1868,58 @@ pub(crate) struct BodyHandler {
     timings: CallTimings,
     remote_closed: bool,
     redirect: Option<Flow<Redirect>>,
+    max_drain_on_drop: usize,
+    max_drain_time_on_drop: StdDuration,
+    trailer_parser: Option<TrailerParser>,
+    trailers: Option<HeaderMap>,
+    host_permit: Option<HostPermit>,
+    close_requested: bool,
+    /// An HTTP/1.0 request asking for keep-alive, where the response decides whether
+    /// to close rather than hoot.
+    http10_keep_alive: bool,
+    /// Keep the connection once the body ends, instead of returning it to the pool.
+    keep_connection: bool,
+    /// Body bytes read so far, as sent by the server.
+    received: u64,
+    allow_extra_data: bool,
+    max_size: Option<u64>,
+    #[cfg(feature = "digest")]
+    digest: Option<DigestCheck>,
+    /// Holds off the end of the body until the gzip decoder has verified the stream.
+    #[cfg(feature = "gzip")]
+    end_gate: Option<EndGate>,
+    /// Set while draining, no read waits past this.
+    drain_deadline: Option<Instant>,
 }
 
 impl BodyHandler {
+    pub(crate) fn trailers(&self) -> Option<&HeaderMap> {
+        self.trailers.as_ref()
+    }
+
+    #[cfg(feature = "gzip")]
+    pub(crate) fn gate_end(&mut self) -> EndGate {
+        let gate = EndGate::default();
+        self.end_gate = Some(gate.clone());
+        gate
+    }
+
+    /// Whether the end of the body is held off by a gzip `EndGate`.
+    fn end_held(&self) -> bool {
+        #[cfg(feature = "gzip")]
+        if let Some(gate) = &self.end_gate {
+            return !gate.is_open();
+        }
+        false
+    }
+
     fn do_read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
-        let (Some(flow), Some(connection), timings) =
-            (&mut self.flow, &mut self.connection, &mut self.timings)
-        else {
+        let (Some(flow), Some(connection), timings, trailer_parser) = (
+            &mut self.flow,
+            &mut self.connection,
+            &mut self.timings,
+            &mut self.trailer_parser,
+        ) else {
             return Ok(0);
         };
 
@@ -528,7 +1934,9 @@ impl BodyHandler {
             };
 
             if body_fulfilled {
-                self.ended()?;
+                if !self.end_held() {
+                    self.ended()?;
+                }
                 return Ok(0);
             }
 
@@ -538,9 +1946,18 @@ impl BodyHandler {
             if has_buffered_input {
                 let input = connection.buffers().input();
                 let (input_used, output_used) = flow.read(input, buf)?;
+                if let Some(p) = trailer_parser {
+                    p.feed(&input[..input_used]);
+                }
                 connection.consume_input(input_used);
 
                 if output_used > 0 {
+                    self.received += output_used as u64;
+                    check_max_size(self.received, self.max_size)?;
+                    #[cfg(feature = "digest")]
+                    if let Some(d) = &mut self.digest {
+                        d.update(&buf[..output_used]);
+                    }
                     return Ok(output_used);
                 }
 
@@ -556,7 +1973,20 @@ impl BodyHandler {
                 return Ok(0);
             }
 
-            let timeout = timings.next_timeout(Timeout::RecvBody);
+            let mut timeout = timings.next_timeout(Timeout::RecvBody);
+
+            if let Some(deadline) = self.drain_deadline {
+                let after = deadline.duration_since(timings.now());
+                if after.is_zero() {
+                    return Err(Error::Timeout(Timeout::RecvBody));
+                }
+                if after < timeout.after {
+                    timeout = NextTimeout {
+                        after,
+                        reason: Timeout::RecvBody,
+                    };
+                }
+            }
 
             let made_progress = match connection.await_input(timeout) {
                 Ok(v) => v,
@@ -576,9 +2006,18 @@ impl BodyHandler {
             let input_ended = input.is_empty();
 
             let (input_used, output_used) = flow.read(input, buf)?;
+            if let Some(p) = trailer_parser {
+                p.feed(&input[..input_used]);
+            }
             connection.consume_input(input_used);
 
             if output_used > 0 {
+                self.received += output_used as u64;
+                check_max_size(self.received, self.max_size)?;
+                #[cfg(feature = "digest")]
+                if let Some(d) = &mut self.digest {
+                    d.update(&buf[..output_used]);
+                }
                 return Ok(output_used);
             } else if input_ended {
                 self.ended()?;
@@ -601,10 +2040,15 @@ impl BodyHandler {
         let flow = self.flow.take().expect("ended() called with body");
 
         if !flow.can_proceed() {
-            return Err(Error::disconnected());
+            return Err(self.truncated(flow.body_mode()));
         }
 
-        let must_close_connection = match flow.proceed().unwrap() {
+        // Trailers must be in place before the connection goes back to the pool.
+        self.trailers = self.trailer_parser.take().and_then(|p| p.into_trailers());
+
+        let close_delimited = matches!(flow.body_mode(), BodyMode::CloseDelimited);
+
+        let hoot_close = match flow.proceed().unwrap() {
             RecvBodyResult::Redirect(flow) => {
                 let c = flow.must_close_connection();
                 self.redirect = Some(flow);
@@ -613,12 +2057,70 @@ impl BodyHandler {
             RecvBodyResult::Cleanup(v) => v.must_close_connection(),
         };
 
-        let connection = self.connection.take().expect("ended() called with body");
-        cleanup(connection, must_close_connection, self.timings.now());
+        // For HTTP/1.0 keep-alive, hoot's reason to close is that the version is 1.0.
+        let must_close_connection = if self.http10_keep_alive {
+            close_delimited
+        } else {
+            hoot_close
+        };
+
+        let mut must_close = must_close_connection || self.close_requested;
+
+        let mut connection = self.connection.take().expect("ended() called with body");
+
+        // When pipelining, the data after the body is the next response.
+        if !self.keep_connection {
+            must_close |= extra_response_data(&mut connection, self.allow_extra_data)?;
+        }
+
+        if self.keep_connection && !must_close {
+            self.connection = Some(connection);
+        } else {
+            cleanup(connection, must_close, self.timings.now());
+        }
+
+        // The request is no longer in flight.
+        self.host_permit = None;
+
+        #[cfg(feature = "digest")]
+        if let Some(d) = self.digest.take() {
+            d.verify()?;
+        }
 
         Ok(())
     }
 
+    /// Error for a body where the connection ended before the end of the body.
+    fn truncated(&self, body_mode: BodyMode) -> Error {
+        let reason = match body_mode {
+            // The flow counts down the length still to come.
+            BodyMode::LengthDelimited(left) => format!(
+                "Peer disconnected after {} of {} body bytes",
+                self.received,
+                self.received + left
+            ),
+            BodyMode::Chunked => format!(
+                "Peer disconnected after {} body bytes, before the last chunk",
+                self.received
+            ),
+            _ => return Error::disconnected(),
+        };
+        io::Error::new(io::ErrorKind::UnexpectedEof, reason).into()
+    }
+
+    fn read_to_vec(&mut self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        let mut buf = vec![0; 8192];
+        loop {
+            let amount = self.do_read(&mut buf)?;
+            if amount == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..amount]);
+        }
+        Ok(data)
+    }
+
     fn consume_redirect_body(&mut self) -> Result<Flow<Redirect>, Error> {
         let mut buf = vec![0; 1024];
         loop {
@@ -633,6 +2135,78 @@ impl BodyHandler {
         let redirect = self.redirect.take();
         Ok(redirect.expect("remote to have signaled redirect"))
     }
+
+    /// Drain the body before the request is sent again.
+    ///
+    /// Gives back the timings, unless there never was a body to read.
+    fn drain_for_retry(mut self) -> Option<CallTimings> {
+        self.flow.as_ref()?;
+        self.drain();
+        // Whatever wasn't drained is closed with the connection.
+        self.flow = None;
+        Some(mem::take(&mut self.timings))
+    }
+
+    fn drain(&mut self) {
+        let Some(flow) = &self.flow else {
+            // Body was read to end, or there never was one.
+            return;
+        };
+
+        // A close delimited body can't be drained to reuse the connection.
+        if self.max_drain_on_drop == 0 || matches!(flow.body_mode(), BodyMode::CloseDelimited) {
+            return;
+        }
+
+        if let BodyMode::LengthDelimited(len) = flow.body_mode() {
+            if len.saturating_sub(self.received) > self.max_drain_on_drop as u64 {
+                debug!("Close connection, body too large to drain");
+                return;
+            }
+        }
+
+        #[cfg(feature = "gzip")]
+        if let Some(gate) = &self.end_gate {
+            // A broken gzip stream means we can't trust the connection.
+            if gate.is_failed() {
+                return;
+            }
+            gate.open_unless_failed();
+        }
+
+        // Nobody is waiting for this data, a stalled server must not hold up the drop.
+        self.drain_deadline =
+            Some(self.timings.now() + Duration::Exact(self.max_drain_time_on_drop));
+
+        let mut buf = [0; 1024];
+        let mut drained = 0;
+
+        // ended() takes the flow once the body is fully read, and returns the
+        // connection to the pool.
+        while self.flow.is_some() {
+            let left = self.max_drain_on_drop - drained;
+            if left == 0 {
+                debug!("Close connection, body too large to drain");
+                break;
+            }
+            let max = left.min(buf.len());
+            match self.do_read(&mut buf[..max]) {
+                // End of input without ended() taking the flow, nothing more will come.
+                Ok(0) => break,
+                Ok(n) => drained += n,
+                Err(e) => {
+                    debug!("Close connection, failed to drain body: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for BodyHandler {
+    fn drop(&mut self) {
+        self.drain();
+    }
 }
 
 impl io::Read for BodyHandler {
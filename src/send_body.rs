@@ -1,6 +1,9 @@
+use std::fmt;
 use std::fs::File;
 use std::io::{self, Read, Stdin};
+use std::mem;
 use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, SyncSender};
 
 use crate::body::{Body, BodyReader};
 use crate::util::private::Private;
@@ -18,6 +21,11 @@ use crate::util::private::Private;
 pub struct SendBody<'a> {
     inner: BodyInner<'a>,
     ended: bool,
+    trailers: Option<HeaderMap>,
+    /// The entire body, for bodies that can be sent again.
+    replay: Option<&'a [u8]>,
+    /// Start of a reader body that was read ahead, to be sent first.
+    prefix: Option<io::Cursor<Vec<u8>>>,
 }
 
 impl<'a> SendBody<'a> {
@@ -36,6 +44,76 @@ impl<'a> SendBody<'a> {
         BodyInner::OwnedReader(Box::new(reader)).into()
     }
 
+    /// Creates a body from chunks arriving on a channel.
+    ///
+    /// This allows the body to be produced on another thread while it is being sent.
+    /// Each chunk is sent as it arrives, using `transfer-encoding: chunked`.
+    ///
+    /// The producer signals the end of the body by sending an empty `Vec`. If the
+    /// channel disconnects before that, such as when the producing thread panics,
+    /// the request fails with [`Error::Io`](crate::Error::Io) rather than sending a
+    /// truncated body as if it was complete.
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    /// use ureq::SendBody;
+    ///
+    /// let (tx, rx) = mpsc::sync_channel(4);
+    ///
+    /// thread::spawn(move || {
+    ///     for line in ["one\n", "two\n", "three\n"] {
+    ///         tx.send(line.as_bytes().to_vec()).unwrap();
+    ///     }
+    ///     // End of body
+    ///     tx.send(vec![]).unwrap();
+    /// });
+    ///
+    /// let response = ureq::post("https://httpbin.org/post")
+    ///     .send(SendBody::from_receiver(rx))?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn from_receiver(receiver: Receiver<Vec<u8>>) -> SendBody<'static> {
+        Self::from_owned_reader(ReceiverReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+            ended: false,
+        })
+    }
+
+    /// Creates a body that is written to using a [`BodyWriter`].
+    ///
+    /// The writer is typically moved to another thread, which produces the body
+    /// while it's being sent. The body is sent using `transfer-encoding: chunked`.
+    ///
+    /// See [`BodyWriter`] for how writes turn into chunks.
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use std::thread;
+    /// use ureq::SendBody;
+    ///
+    /// let (mut writer, body) = SendBody::writer();
+    ///
+    /// thread::spawn(move || {
+    ///     for line in ["one\n", "two\n", "three\n"] {
+    ///         writer.write_all(line.as_bytes())?;
+    ///         // Send each line right away.
+    ///         writer.flush()?;
+    ///     }
+    ///     writer.finish()
+    /// });
+    ///
+    /// let response = ureq::post("https://httpbin.org/post")
+    ///     .send(body)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn writer() -> (BodyWriter, SendBody<'static>) {
+        let (sender, receiver) = mpsc::sync_channel(4);
+        (BodyWriter::new(sender), Self::from_receiver(receiver))
+    }
+
     /// Creates a body to send as JSON from any [`Serialize`](serde::ser::Serialize) value.
     #[cfg(feature = "json")]
     pub fn from_json(
@@ -45,7 +123,139 @@ impl<'a> SendBody<'a> {
         Ok(Self::from_owned_reader(io::Cursor::new(json)))
     }
 
+    /// Creates a body to send as JSON, formatted by the given formatter.
+    ///
+    /// See [`RequestBuilder::send_json_with()`](crate::RequestBuilder::send_json_with).
+    #[cfg(feature = "json")]
+    pub fn from_json_with(
+        value: &impl serde::ser::Serialize,
+        formatter: impl serde_json::ser::Formatter,
+    ) -> Result<SendBody<'static>, crate::Error> {
+        let mut json = Vec::new();
+        let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+        value.serialize(&mut serializer)?;
+        Ok(Self::from_owned_reader(io::Cursor::new(json)))
+    }
+
+    /// Sends trailers after the last chunk of the body.
+    ///
+    /// Trailers can only be sent with a chunked body, and each trailer name must
+    /// be announced in a `Trailer` request header. Sending the request fails with
+    /// [`Error::InvalidTrailer`](crate::Error::InvalidTrailer) otherwise.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use ureq::http::HeaderMap;
+    /// use ureq::SendBody;
+    ///
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("x-checksum", "abc123".parse().unwrap());
+    ///
+    /// let body = SendBody::from_owned_reader(Cursor::new(b"data".to_vec()))
+    ///     .with_trailers(trailers);
+    ///
+    /// let response = ureq::post("https://httpbin.org/post")
+    ///     .header("trailer", "x-checksum")
+    ///     .send(body)?;
+    /// # Ok::<_, ureq::Error>(())
+    /// ```
+    pub fn with_trailers(mut self, trailers: HeaderMap) -> Self {
+        self.trailers = Some(trailers);
+        self
+    }
+
+    pub(crate) fn trailers(&self) -> Option<&HeaderMap> {
+        self.trailers.as_ref()
+    }
+
+    /// Rewind the body to send it again, such as when following a redirect.
+    ///
+    /// Returns `false` if the body can't be sent again.
+    pub(crate) fn rewind(&mut self) -> bool {
+        if matches!(self.inner, BodyInner::None) {
+            return true;
+        }
+
+        let Some(v) = self.replay else {
+            return false;
+        };
+
+        self.inner = BodyInner::ByteSlice(v);
+        self.ended = false;
+        true
+    }
+
+    /// Drop the body, such as when a redirect changes the method.
+    pub(crate) fn clear(&mut self) {
+        self.inner = BodyInner::None;
+        self.ended = false;
+        self.trailers = None;
+        self.replay = None;
+        self.prefix = None;
+    }
+
+    /// Take the rest of an in-memory body, to send it without copying.
+    pub(crate) fn take_slice(&mut self) -> Option<&'a [u8]> {
+        let BodyInner::ByteSlice(v) = &mut self.inner else {
+            return None;
+        };
+        self.ended = true;
+        Some(mem::take(v))
+    }
+
+    /// The rest of an in-memory body.
+    pub(crate) fn slice(&self) -> Option<&[u8]> {
+        match self.inner {
+            BodyInner::ByteSlice(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Length of the rest of an in-memory body.
+    pub(crate) fn slice_len(&self) -> Option<usize> {
+        match self.inner {
+            BodyInner::ByteSlice(v) => Some(v.len()),
+            _ => None,
+        }
+    }
+
+    /// The first `len` bytes of the body, or fewer if the body is shorter.
+    ///
+    /// A reader body is read from, and what was read is kept to be sent first.
+    #[cfg(feature = "content-sniff")]
+    pub(crate) fn peek(&mut self, len: usize) -> io::Result<&[u8]> {
+        if let BodyInner::ByteSlice(v) = self.inner {
+            return Ok(&v[..len.min(v.len())]);
+        }
+
+        if self.prefix.is_none() {
+            let mut buf = vec![0; len];
+            let mut n = 0;
+            while n < len {
+                let amount = self.read(&mut buf[n..])?;
+                if amount == 0 {
+                    break;
+                }
+                n += amount;
+            }
+            buf.truncate(n);
+            self.prefix = Some(io::Cursor::new(buf));
+        }
+
+        // unwrap is ok, because it was set above.
+        let prefix = self.prefix.as_ref().unwrap();
+        Ok(&prefix.get_ref()[prefix.position() as usize..])
+    }
+
     pub(crate) fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(prefix) = &mut self.prefix {
+            let n = prefix.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            self.prefix = None;
+        }
+
         let n = match &mut self.inner {
             BodyInner::None => {
                 return Ok(0);
@@ -76,7 +286,7 @@ impl<'a> SendBody<'a> {
 }
 
 use hoot::BodyMode;
-use http::Response;
+use http::{HeaderMap, Response};
 
 /// Trait for common types to send in POST, PUT or PATCH.
 ///
@@ -92,6 +302,8 @@ use http::Response;
 /// * `&File`
 /// * `&TcpStream`
 /// * `&[u8]`
+/// * `&mut dyn Read`
+/// * `Box<dyn Read>`
 /// * `Response<Body>`
 /// * `String`
 /// * `Vec<u8>`
@@ -100,6 +312,7 @@ use http::Response;
 /// * `TcpStream`
 /// * `UnixStream` (not on windows)
 /// * `&[u8; N]`
+/// * `Cursor<T>`, where `T: AsRef<[u8]>`, from the cursor position
 /// * `()`
 ///
 /// # Example
@@ -144,6 +357,9 @@ impl<'a> AsSendBody for SendBody<'a> {
                 BodyInner::OwnedReader(v) => BodyInner::Reader(v),
             },
             ended: self.ended,
+            trailers: self.trailers.clone(),
+            replay: self.replay,
+            prefix: self.prefix.take(),
         }
     }
 }
@@ -168,6 +384,138 @@ impl<'a> BodyInner<'a> {
     }
 }
 
+/// Reads the chunks of a channel until the empty end marker.
+struct ReceiverReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    ended: bool,
+}
+
+impl Read for ReceiverReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            if self.ended {
+                return Ok(0);
+            }
+
+            let Ok(chunk) = self.receiver.recv() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "body channel disconnected before end of body",
+                ));
+            };
+
+            self.ended = chunk.is_empty();
+            self.chunk = chunk;
+            self.pos = 0;
+        }
+
+        let rest = &self.chunk[self.pos..];
+        let max = rest.len().min(buf.len());
+        buf[..max].copy_from_slice(&rest[..max]);
+        self.pos += max;
+
+        Ok(max)
+    }
+}
+
+/// Serialize JSON into a channel read by [`SendBody::from_receiver()`].
+///
+/// Sends the end marker once the value is serialized.
+#[cfg(feature = "json")]
+pub(crate) fn json_to_channel(
+    value: &impl serde::ser::Serialize,
+    sender: SyncSender<Vec<u8>>,
+) -> Result<(), serde_json::Error> {
+    // serde_json does many small writes, which the writer buffers.
+    let mut writer = BodyWriter::new(sender);
+
+    serde_json::to_writer(&mut writer, value)?;
+
+    // If this fails, the request has failed already.
+    let _ = writer.finish();
+
+    Ok(())
+}
+
+/// Size at which a [`BodyWriter`] sends a chunk without waiting for a flush.
+const BODY_WRITER_CAPACITY: usize = 16 * 1024;
+
+/// Writer producing a request body created by [`SendBody::writer()`].
+///
+/// Writes are buffered, and [`flush()`](io::Write::flush) sends what is buffered
+/// so far as one HTTP chunk, right away. Use it where the server should see the data
+/// promptly, such as for interactive protocols. Without flushing, a chunk is sent
+/// each time 16KB has been buffered.
+///
+/// The body ends with [`BodyWriter::finish()`], which flushes the rest. If the writer
+/// is dropped without finishing, the request fails with
+/// [`Error::Io`](crate::Error::Io) rather than sending a truncated body as if it was
+/// complete.
+///
+/// Writes fail with [`io::ErrorKind::BrokenPipe`] once the request is no longer sent,
+/// such as after a timeout.
+pub struct BodyWriter {
+    sender: SyncSender<Vec<u8>>,
+    buf: Vec<u8>,
+}
+
+impl BodyWriter {
+    fn new(sender: SyncSender<Vec<u8>>) -> Self {
+        BodyWriter {
+            sender,
+            buf: Vec::with_capacity(BODY_WRITER_CAPACITY),
+        }
+    }
+
+    /// Send any buffered data and end the body.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.send_buffered()?;
+        // An empty chunk is the end marker for the receiver.
+        self.send(vec![])
+    }
+
+    fn send_buffered(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = mem::replace(&mut self.buf, Vec::with_capacity(BODY_WRITER_CAPACITY));
+        self.send(chunk)
+    }
+
+    fn send(&self, chunk: Vec<u8>) -> io::Result<()> {
+        self.sender
+            .send(chunk)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "request body no longer sent"))
+    }
+}
+
+impl io::Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() == BODY_WRITER_CAPACITY {
+            self.send_buffered()?;
+        }
+
+        let n = (BODY_WRITER_CAPACITY - self.buf.len()).min(buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffered()
+    }
+}
+
+impl fmt::Debug for BodyWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyWriter")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
 macro_rules! impl_into_body_slice {
     ($t:ty) => {
         impl Private for $t {}
@@ -186,6 +534,16 @@ impl_into_body_slice!(Vec<u8>);
 impl_into_body_slice!(&String);
 impl_into_body_slice!(&Vec<u8>);
 
+// The rest of the data after the position is sent, with a known length.
+impl<T: AsRef<[u8]>> Private for io::Cursor<T> {}
+impl<T: AsRef<[u8]>> AsSendBody for io::Cursor<T> {
+    fn as_body(&mut self) -> SendBody {
+        let data = self.get_ref().as_ref();
+        let start = self.position().min(data.len() as u64) as usize;
+        BodyInner::ByteSlice(&data[start..]).into()
+    }
+}
+
 macro_rules! impl_into_body {
     ($t:ty, $s:tt) => {
         impl Private for $t {}
@@ -202,6 +560,8 @@ impl_into_body!(&TcpStream, Reader);
 impl_into_body!(File, Reader);
 impl_into_body!(TcpStream, Reader);
 impl_into_body!(Stdin, Reader);
+impl_into_body!(&mut dyn Read, Reader);
+impl_into_body!(Box<dyn Read>, Reader);
 
 // MSRV 1.78
 // impl_into_body!(&Stdin, Reader);
@@ -214,9 +574,17 @@ impl_into_body!(UnixStream, Reader);
 
 impl<'a> From<BodyInner<'a>> for SendBody<'a> {
     fn from(inner: BodyInner<'a>) -> Self {
+        let replay = match inner {
+            BodyInner::ByteSlice(v) => Some(v),
+            _ => None,
+        };
+
         SendBody {
             inner,
             ended: false,
+            trailers: None,
+            replay,
+            prefix: None,
         }
     }
 }
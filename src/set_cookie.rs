@@ -0,0 +1,185 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use http::header::{HeaderMap, SET_COOKIE};
+
+use crate::http_date::parse_http_date;
+
+/// A cookie from a `set-cookie` response header.
+///
+/// See [`ResponseExt::set_cookies()`](crate::ResponseExt::set_cookies). This is only the parsed
+/// header, no cookie jar rules such as domain matching or expiry are applied.
+///
+/// ```text
+///     Set-Cookie: session=abc123; Path=/; Max-Age=3600; Secure; HttpOnly
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl SetCookie {
+    /// The cookie name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The cookie value, as is. Any surrounding quotes are kept.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The value of an attribute, such as `"Path"`.
+    ///
+    /// Attribute names are compared ignoring ASCII case. Attributes without value,
+    /// such as `Secure`, give an empty string. If the attribute is given more than
+    /// once, the last one counts.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .rev()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The `Domain` attribute.
+    pub fn domain(&self) -> Option<&str> {
+        self.attribute("domain")
+    }
+
+    /// The `Path` attribute.
+    pub fn path(&self) -> Option<&str> {
+        self.attribute("path")
+    }
+
+    /// The `Expires` attribute, `None` if missing or not a valid date.
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.attribute("expires").and_then(parse_http_date)
+    }
+
+    /// The `Max-Age` attribute in seconds, `None` if missing or not a number.
+    ///
+    /// Zero or negative means the cookie is to be removed.
+    pub fn max_age(&self) -> Option<i64> {
+        self.attribute("max-age").and_then(|v| v.parse().ok())
+    }
+
+    /// Whether there is a `Secure` attribute.
+    pub fn secure(&self) -> bool {
+        self.attribute("secure").is_some()
+    }
+
+    /// Whether there is a `HttpOnly` attribute.
+    pub fn http_only(&self) -> bool {
+        self.attribute("httponly").is_some()
+    }
+
+    /// The `SameSite` attribute, such as `"Lax"`.
+    pub fn same_site(&self) -> Option<&str> {
+        self.attribute("samesite")
+    }
+}
+
+impl fmt::Debug for SetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The value is often a secret.
+        f.debug_struct("SetCookie")
+            .field("name", &self.name)
+            .field("attributes", &self.attributes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parse all `set-cookie` headers. Headers without a cookie name are skipped.
+pub(crate) fn parse_set_cookies(headers: &HeaderMap) -> Vec<SetCookie> {
+    headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(parse_set_cookie)
+        .collect()
+}
+
+/// As per RFC 6265 section 5.2. Unlike other headers, set-cookie can't be combined
+/// with commas, since the `Expires` date contains one.
+fn parse_set_cookie(s: &str) -> Option<SetCookie> {
+    let mut parts = s.split(';');
+
+    // unwrap is ok, split always gives at least one part.
+    let (name, value) = parts.next().unwrap().split_once('=')?;
+    let name = name.trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let attributes = parts
+        .filter_map(|a| {
+            let (n, v) = a.split_once('=').unwrap_or((a, ""));
+            let n = n.trim();
+            (!n.is_empty()).then(|| (n.to_string(), v.trim().to_string()))
+        })
+        .collect();
+
+    Some(SetCookie {
+        name: name.to_string(),
+        value: value.trim().to_string(),
+        attributes,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cookies(values: &[&str]) -> Vec<SetCookie> {
+        let mut headers = HeaderMap::new();
+        for v in values {
+            headers.append(SET_COOKIE, v.parse().unwrap());
+        }
+        parse_set_cookies(&headers)
+    }
+
+    #[test]
+    fn multiple_headers() {
+        let c = cookies(&[
+            "session=abc123; Path=/; Max-Age=3600; Secure; HttpOnly",
+            "theme=\"dark\"; Expires=Wed, 21 Oct 2015 07:28:00 GMT; SameSite=Lax",
+        ]);
+        assert_eq!(c.len(), 2);
+
+        assert_eq!(c[0].name(), "session");
+        assert_eq!(c[0].value(), "abc123");
+        assert_eq!(c[0].path(), Some("/"));
+        assert_eq!(c[0].max_age(), Some(3600));
+        assert!(c[0].secure());
+        assert!(c[0].http_only());
+        assert!(c[0].domain().is_none());
+
+        assert_eq!(c[1].value(), "\"dark\"");
+        assert!(c[1].expires().is_some());
+        assert_eq!(c[1].same_site(), Some("Lax"));
+        assert!(!c[1].secure());
+    }
+
+    #[test]
+    fn attribute_case_and_last_wins() {
+        let c = cookies(&["a=1; path=/x; PATH=/y; domain=Example.TEST"]);
+        assert_eq!(c[0].path(), Some("/y"));
+        assert_eq!(c[0].attribute("Domain"), Some("Example.TEST"));
+    }
+
+    #[test]
+    fn empty_value_is_ok() {
+        let c = cookies(&["gone=; Max-Age=0"]);
+        assert_eq!(c[0].value(), "");
+        assert_eq!(c[0].max_age(), Some(0));
+    }
+
+    #[test]
+    fn malformed_is_skipped() {
+        assert!(cookies(&["no equals sign", "=nameless", ""]).is_empty());
+    }
+}
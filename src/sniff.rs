@@ -0,0 +1,104 @@
+//! Content-type from the magic numbers at the start of a body.
+
+/// How much of the body is needed to recognize all of the types below.
+pub(crate) const SNIFF_LEN: usize = 16;
+
+/// Magic bytes, where `None` matches any byte, and the resulting mime type.
+const MAGIC: &[(&[Option<u8>], &str)] = &[
+    (&bytes(b"\x89PNG\r\n\x1a\n"), "image/png"),
+    (&bytes(b"\xff\xd8\xff"), "image/jpeg"),
+    (&bytes(b"GIF87a"), "image/gif"),
+    (&bytes(b"GIF89a"), "image/gif"),
+    (&riff(b"WEBP"), "image/webp"),
+    (&riff(b"WAVE"), "audio/wav"),
+    (&bytes(b"%PDF-"), "application/pdf"),
+    (&bytes(b"\x1f\x8b"), "application/gzip"),
+    (&bytes(b"PK\x03\x04"), "application/zip"),
+    (&bytes(b"BZh"), "application/x-bzip2"),
+    (&bytes(b"\xfd7zXZ\x00"), "application/x-xz"),
+    (&bytes(b"7z\xbc\xaf\x27\x1c"), "application/x-7z-compressed"),
+    (&bytes(b"\x28\xb5\x2f\xfd"), "application/zstd"),
+    (&bytes(b"\x00asm"), "application/wasm"),
+    (&bytes(b"OggS"), "application/ogg"),
+    (&bytes(b"fLaC"), "audio/flac"),
+    (&bytes(b"ID3"), "audio/mpeg"),
+    (&ftyp(), "video/mp4"),
+];
+
+/// The mime type of a body starting with `prefix`, if recognized.
+pub(crate) fn sniff(prefix: &[u8]) -> Option<&'static str> {
+    MAGIC
+        .iter()
+        .find(|(magic, _)| {
+            prefix.len() >= magic.len()
+                && magic
+                    .iter()
+                    .zip(prefix)
+                    .all(|(m, b)| m.is_none() || *m == Some(*b))
+        })
+        .map(|(_, mime)| *mime)
+}
+
+const fn bytes<const N: usize>(v: &[u8; N]) -> [Option<u8>; N] {
+    let mut out = [None; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = Some(v[i]);
+        i += 1;
+    }
+    out
+}
+
+/// A RIFF container, `RIFF`, the size and then the form type.
+const fn riff(form: &[u8; 4]) -> [Option<u8>; 12] {
+    let mut out = [None; 12];
+    let riff = b"RIFF";
+    let mut i = 0;
+    while i < 4 {
+        out[i] = Some(riff[i]);
+        out[8 + i] = Some(form[i]);
+        i += 1;
+    }
+    out
+}
+
+/// An ISO base media file (mp4), the size of the first box and then `ftyp`.
+const fn ftyp() -> [Option<u8>; 8] {
+    let mut out = [None; 8];
+    let ftyp = b"ftyp";
+    let mut i = 0;
+    while i < 4 {
+        out[4 + i] = Some(ftyp[i]);
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sniff_magic() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff(b"\x1f\x8b\x08\0"), Some("application/gzip"));
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"\0\0\0\x20ftypisom"), Some("video/mp4"));
+    }
+
+    #[test]
+    fn sniff_unknown() {
+        assert_eq!(sniff(b"hello world"), None);
+        assert_eq!(sniff(b""), None);
+        // Too short to tell.
+        assert_eq!(sniff(b"\x89PN"), None);
+        assert_eq!(sniff(b"RIFF\x24\0\0\0"), None);
+    }
+
+    #[test]
+    fn magic_fits_prefix() {
+        assert!(MAGIC.iter().all(|(m, _)| m.len() <= SNIFF_LEN));
+    }
+}
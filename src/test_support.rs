@@ -0,0 +1,819 @@
+//! In-process test server for code using ureq.
+//!
+//! _Requires the **test-support** feature._
+//!
+//! [`TestServer`] answers requests with scripted responses, without opening any
+//! sockets. It plugs into an [`Agent`] as the [`Connector`] and [`Resolver`], which
+//! means the code under test runs the regular ureq request machinery: headers,
+//! body framing, redirects, connection pooling and so forth.
+//!
+//! ```
+//! use ureq::http::Response;
+//! use ureq::test_support::TestServer;
+//!
+//! let server = TestServer::new()
+//!     .route("/hello", |req| {
+//!         assert_eq!(req.method(), "POST");
+//!         assert_eq!(req.body(), b"hi");
+//!         Response::new(b"hello there".to_vec())
+//!     });
+//!
+//! let agent = server.agent(Default::default());
+//!
+//! let text = agent
+//!     .post("http://example.test/hello")
+//!     .send("hi")?
+//!     .body_mut()
+//!     .read_to_string()?;
+//!
+//! assert_eq!(text, "hello there");
+//! # Ok::<_, ureq::Error>(())
+//! ```
+//!
+//! [`framed_body()`] makes a [`Body`] of raw bytes with a chosen framing, for testing
+//! code that consumes bodies against truncated or otherwise broken responses.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use http::{header, Method, Request, Response, StatusCode, Uri};
+
+use crate::config::Config;
+use crate::resolver::{ResolvedSocketAddrs, Resolver};
+use crate::transport::{Buffers, ConnectionDetails, Connector, LazyBuffers};
+use crate::transport::{NextTimeout, Transport};
+use crate::util::ArrayVec;
+use crate::{Agent, Body, Error};
+
+type Handler = Arc<dyn Fn(&Request<Vec<u8>>) -> Response<Vec<u8>> + Send + Sync>;
+
+/// In-process server answering requests with scripted responses.
+///
+/// Requests are routed on the exact path, ignoring host and query. Requests without
+/// a route get a `404 Not Found`.
+///
+/// The response body is framed with `Content-Length`, unless the handler sets the
+/// framing headers. A handler setting `Connection: close` makes the server
+/// close the connection after the response.
+///
+/// Cloning the server shares the routes.
+#[derive(Clone, Default)]
+pub struct TestServer {
+    routes: Arc<Mutex<Vec<(String, Handler)>>>,
+    /// Bumped by close_idle_connections(). Connections opened before are closed.
+    epoch: Arc<AtomicUsize>,
+}
+
+impl TestServer {
+    /// Creates a server without any routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a handler for requests to `path`.
+    ///
+    /// A later route for the same path replaces an earlier one.
+    pub fn route(
+        self,
+        path: &str,
+        handler: impl Fn(&Request<Vec<u8>>) -> Response<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        {
+            let mut routes = self.routes.lock().unwrap();
+            routes.retain(|(p, _)| p != path);
+            routes.push((path.to_string(), Arc::new(handler)));
+        }
+        self
+    }
+
+    /// Closes the connections opened so far, like a server timing out idle keep-alive
+    /// connections.
+    ///
+    /// ureq sees the connections as closed when probing them, and a request that is
+    /// sent on one anyway gets no response.
+    pub fn close_idle_connections(&self) {
+        self.epoch.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn epoch(&self) -> usize {
+        self.epoch.load(Ordering::Relaxed)
+    }
+
+    /// Creates an agent where all requests go to this server.
+    pub fn agent(&self, config: Config) -> Agent {
+        Agent::with_parts(config, self.clone(), self.clone())
+    }
+
+    fn respond(&self, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+        let handler = {
+            let routes = self.routes.lock().unwrap();
+            routes
+                .iter()
+                .find(|(p, _)| p == request.uri().path())
+                .map(|(_, h)| h.clone())
+        };
+
+        match handler {
+            Some(h) => h(request),
+            None => {
+                let mut res = Response::new(b"Not Found".to_vec());
+                *res.status_mut() = StatusCode::NOT_FOUND;
+                res
+            }
+        }
+    }
+}
+
+impl Connector for TestServer {
+    fn connect(
+        &self,
+        details: &ConnectionDetails,
+        _chained: Option<Box<dyn Transport>>,
+    ) -> Result<Option<Box<dyn Transport>>, Error> {
+        let config = details.config;
+
+        let transport = TestServerTransport {
+            server: self.clone(),
+            buffers: LazyBuffers::new(config.input_buffer_size, config.output_buffer_size),
+            received: Vec::new(),
+            pending: Vec::new(),
+            open: true,
+            epoch: self.epoch(),
+        };
+
+        Ok(Some(Box::new(transport)))
+    }
+}
+
+impl Resolver for TestServer {
+    fn resolve(
+        &self,
+        _uri: &Uri,
+        _config: &Config,
+        _timeout: NextTimeout,
+    ) -> Result<ResolvedSocketAddrs, Error> {
+        // The address is never used, but the pool needs something.
+        let mut addrs = ArrayVec::from_fn(|_| "0.0.0.0:1".parse().unwrap());
+        addrs.push(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 80)));
+        Ok(addrs)
+    }
+}
+
+struct TestServerTransport {
+    server: TestServer,
+    buffers: LazyBuffers,
+    /// Request data sent by ureq, not yet handled.
+    received: Vec<u8>,
+    /// Response data not yet handed to ureq.
+    pending: Vec<u8>,
+    open: bool,
+    /// The server epoch when the connection was opened.
+    epoch: usize,
+}
+
+impl TestServerTransport {
+    fn closed_by_server(&self) -> bool {
+        self.epoch != self.server.epoch()
+    }
+
+    /// Answer the request in `received`, if it is complete.
+    fn handle_request(&mut self) -> Result<bool, Error> {
+        let Some((head_len, request)) = hoot::parser::try_parse_request::<100>(&self.received)?
+        else {
+            return Ok(false);
+        };
+
+        let Some((body, body_len)) = request_body(&request, &self.received[head_len..])? else {
+            return Ok(false);
+        };
+
+        let uri = request_target(&self.received)?;
+        self.received.drain(..head_len + body_len);
+
+        let (mut parts, _) = request.into_parts();
+        parts.uri = uri;
+        let request = Request::from_parts(parts, body);
+
+        let response = self.server.respond(&request);
+        let close = write_response(&mut self.pending, &response, request.method());
+
+        if close {
+            self.open = false;
+        }
+
+        Ok(true)
+    }
+}
+
+impl Transport for TestServerTransport {
+    fn buffers(&mut self) -> &mut dyn Buffers {
+        &mut self.buffers
+    }
+
+    fn transmit_output(&mut self, amount: usize, _timeout: NextTimeout) -> Result<(), Error> {
+        let output = &self.buffers.output()[..amount];
+        self.received.extend_from_slice(output);
+        Ok(())
+    }
+
+    fn await_input(&mut self, timeout: NextTimeout) -> Result<bool, Error> {
+        if self.closed_by_server() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        if self.pending.is_empty() && !self.handle_request()? {
+            // Nothing will arrive until ureq sends more, such as when
+            // awaiting 100-continue.
+            return Err(Error::Timeout(timeout.reason));
+        }
+
+        let input = self.buffers.input_append_buf();
+        let n = input.len().min(self.pending.len());
+        input[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        self.buffers.input_appended(n);
+
+        Ok(n > 0)
+    }
+
+    fn is_open(&mut self) -> bool {
+        self.open && !self.closed_by_server()
+    }
+}
+
+/// The target of the request line, which the request parser doesn't keep.
+fn request_target(input: &[u8]) -> Result<Uri, Error> {
+    let line = input.split(|b| *b == b'\r').next().unwrap_or_default();
+    let target = line.split(|b| *b == b' ').nth(1).unwrap_or_default();
+    Uri::try_from(target).map_err(|e| Error::Http(e.into()))
+}
+
+/// The request body and how much of the input it used, if the body is complete.
+fn request_body(request: &Request<()>, input: &[u8]) -> Result<Option<(Vec<u8>, usize)>, Error> {
+    let headers = request.headers();
+
+    let chunked = headers
+        .get(header::TRANSFER_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("chunked"))
+        .unwrap_or(false);
+
+    if chunked {
+        return Ok(decode_chunked(input));
+    }
+
+    let len = match headers.get(header::CONTENT_LENGTH) {
+        Some(v) => v
+            .to_str()
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .ok_or_else(|| {
+                Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "test server: bad content-length",
+                ))
+            })?,
+        None => 0,
+    };
+
+    if input.len() < len {
+        return Ok(None);
+    }
+
+    Ok(Some((input[..len].to_vec(), len)))
+}
+
+/// Decode a complete chunked body. Chunk extensions and trailers are ignored.
+fn decode_chunked(input: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut body = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        let line_end = pos + find_crlf(&input[pos..])?;
+        let line = std::str::from_utf8(&input[pos..line_end]).ok()?;
+        let size = line.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        pos = line_end + 2;
+
+        if size == 0 {
+            // Skip trailers until the empty line.
+            loop {
+                let end = pos + find_crlf(&input[pos..])?;
+                let empty = end == pos;
+                pos = end + 2;
+                if empty {
+                    return Some((body, pos));
+                }
+            }
+        }
+
+        let data = input.get(pos..pos + size)?;
+        body.extend_from_slice(data);
+        pos += size;
+
+        // CRLF after the chunk data.
+        input.get(pos..pos + 2)?;
+        pos += 2;
+    }
+}
+
+fn find_crlf(input: &[u8]) -> Option<usize> {
+    input.windows(2).position(|w| w == b"\r\n")
+}
+
+/// Serialize the response. Returns whether the connection is to be closed.
+fn write_response(out: &mut Vec<u8>, response: &Response<Vec<u8>>, method: &Method) -> bool {
+    let status = response.status();
+    let headers = response.headers();
+
+    // unwraps are ok, writing to a Vec can't fail.
+    write!(
+        out,
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    )
+    .unwrap();
+
+    for (name, value) in headers {
+        write!(out, "{}: ", name).unwrap();
+        out.extend_from_slice(value.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+
+    let has_framing = headers.contains_key(header::CONTENT_LENGTH)
+        || headers.contains_key(header::TRANSFER_ENCODING);
+    let no_body = status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED;
+
+    if !has_framing && !no_body {
+        write!(out, "content-length: {}\r\n", response.body().len()).unwrap();
+    }
+
+    out.extend_from_slice(b"\r\n");
+
+    if method != Method::HEAD && !no_body {
+        out.extend_from_slice(response.body());
+    }
+
+    headers
+        .get(header::CONNECTION)
+        .map(|v| v.as_bytes().eq_ignore_ascii_case(b"close"))
+        .unwrap_or(false)
+}
+
+/// Framing of the raw bytes given to [`framed_body()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Framing {
+    /// `Transfer-Encoding: chunked`. The bytes are chunk encoded, sizes and all.
+    Chunked,
+    /// `Content-Length` with the given length, which may differ from the number of bytes.
+    ContentLength(u64),
+    /// Neither, and `Connection: close`. The body ends where the bytes end.
+    CloseDelimited,
+}
+
+/// A response body decoded from `raw` as if received with the given framing.
+///
+/// The bytes go through the same decoding as a body received from a server. The
+/// connection closes after the last byte, which means a chunked body without the
+/// last chunk, or a body shorter than the content-length, errors when read.
+///
+/// ```
+/// use ureq::test_support::{framed_body, Framing};
+///
+/// let mut body = framed_body(Framing::Chunked, "5\r\nhello\r\n0\r\n\r\n")?;
+/// assert_eq!(body.read_to_string()?, "hello");
+///
+/// // Truncated in the middle of a chunk.
+/// let mut body = framed_body(Framing::Chunked, "5\r\nhel")?;
+/// assert!(body.read_to_string().is_err());
+/// # Ok::<_, ureq::Error>(())
+/// ```
+pub fn framed_body(framing: Framing, raw: impl Into<Vec<u8>>) -> Result<Body, Error> {
+    let mut response = b"HTTP/1.1 200 OK\r\n".to_vec();
+
+    // unwraps are ok, writing to a Vec can't fail.
+    match framing {
+        Framing::Chunked => write!(response, "transfer-encoding: chunked\r\n").unwrap(),
+        Framing::ContentLength(len) => write!(response, "content-length: {}\r\n", len).unwrap(),
+        Framing::CloseDelimited => write!(response, "connection: close\r\n").unwrap(),
+    }
+
+    response.extend_from_slice(b"\r\n");
+    response.extend_from_slice(&raw.into());
+
+    let agent = Agent::with_parts(
+        Config::default(),
+        FramedConnector(Arc::new(response)),
+        TestServer::new(),
+    );
+
+    let response = agent.get("http://framed.test/").call()?;
+
+    Ok(response.into_body())
+}
+
+#[derive(Debug)]
+struct FramedConnector(Arc<Vec<u8>>);
+
+impl Connector for FramedConnector {
+    fn connect(
+        &self,
+        details: &ConnectionDetails,
+        _chained: Option<Box<dyn Transport>>,
+    ) -> Result<Option<Box<dyn Transport>>, Error> {
+        let config = details.config;
+
+        let transport = FramedTransport {
+            buffers: LazyBuffers::new(config.input_buffer_size, config.output_buffer_size),
+            pending: self.0.to_vec(),
+        };
+
+        Ok(Some(Box::new(transport)))
+    }
+}
+
+/// Transport handing out a fixed response, then behaving as closed.
+struct FramedTransport {
+    buffers: LazyBuffers,
+    pending: Vec<u8>,
+}
+
+impl Transport for FramedTransport {
+    fn buffers(&mut self) -> &mut dyn Buffers {
+        &mut self.buffers
+    }
+
+    fn transmit_output(&mut self, _amount: usize, _timeout: NextTimeout) -> Result<(), Error> {
+        // The request is irrelevant.
+        Ok(())
+    }
+
+    fn await_input(&mut self, _timeout: NextTimeout) -> Result<bool, Error> {
+        let input = self.buffers.input_append_buf();
+        let n = input.len().min(self.pending.len());
+        input[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        self.buffers.input_appended(n);
+
+        Ok(n > 0)
+    }
+
+    fn is_open(&mut self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+impl fmt::Debug for FramedTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FramedTransport")
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl fmt::Debug for TestServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes = self.routes.lock().unwrap();
+        let paths: Vec<_> = routes.iter().map(|(p, _)| p).collect();
+        f.debug_struct("TestServer")
+            .field("routes", &paths)
+            .finish()
+    }
+}
+
+impl fmt::Debug for TestServerTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestServerTransport")
+            .field("open", &self.open)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn not_found() {
+        let server = TestServer::new();
+        let agent = server.agent(Config::default());
+        let err = agent.get("http://example.test/nope").call().unwrap_err();
+        assert!(matches!(err, Error::StatusCode(404)));
+    }
+
+    #[test]
+    fn echo_chunked_body() {
+        let server = TestServer::new().route("/echo", |req| Response::new(req.body().clone()));
+        let agent = server.agent(Config::default());
+
+        let mut data: &[u8] = b"streamed body";
+        let reader: &mut dyn std::io::Read = &mut data;
+
+        let text = agent
+            .put("http://example.test/echo")
+            .send(reader)
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+
+        assert_eq!(text, "streamed body");
+    }
+
+    #[test]
+    fn reuses_connection() {
+        let server = TestServer::new().route("/a", |_| Response::new(b"a".to_vec()));
+        let agent = server.agent(Config::default());
+
+        for _ in 0..2 {
+            let text = agent
+                .get("http://example.test/a?x=1")
+                .call()
+                .unwrap()
+                .body_mut()
+                .read_to_string()
+                .unwrap();
+            assert_eq!(text, "a");
+        }
+
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn idle_connections_closed() {
+        use std::thread;
+        use std::time::Duration;
+
+        let server = TestServer::new().route("/a", |_| Response::new(b"a".to_vec()));
+        let config = Config::builder()
+            .max_idle_age(Duration::from_millis(50))
+            .build();
+        let agent = server.agent(config);
+
+        let mut res = agent.get("http://one.test/a").call().unwrap();
+        res.body_mut().read_to_string().unwrap();
+        assert_eq!(agent.pool_count(), 1);
+
+        thread::sleep(Duration::from_millis(100));
+
+        // Another host, which means the idle connection isn't reused. The request
+        // still sweeps it from the pool.
+        let mut res = agent.get("http://two.test/a").call().unwrap();
+        res.body_mut().read_to_string().unwrap();
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn closed_idle_connection_probed() {
+        let server = TestServer::new().route("/a", |_| Response::new(b"a".to_vec()));
+        let agent = server.agent(Config::default());
+
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        res.body_mut().read_to_string().unwrap();
+        assert_eq!(agent.pool_count(), 1);
+
+        server.close_idle_connections();
+
+        // The probe finds the pooled connection dead, and the request opens a new one.
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "a");
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn closed_idle_connection_not_probed() {
+        let server = TestServer::new().route("/a", |_| Response::new(b"a".to_vec()));
+        let config = Config::builder().probe_pooled_connections(false).build();
+        let agent = server.agent(config);
+
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        res.body_mut().read_to_string().unwrap();
+
+        server.close_idle_connections();
+
+        let err = agent.get("http://example.test/a").call().unwrap_err();
+        assert!(matches!(err, Error::Io(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn pool_stats_idle_and_in_use() {
+        let server = TestServer::new().route("/a", |_| Response::new(b"a".to_vec()));
+        let agent = server.agent(Config::default());
+
+        let mut res1 = agent.get("http://one.test/a").call().unwrap();
+        let mut res2 = agent.get("http://two.test/a").call().unwrap();
+        let res3 = agent.get("http://two.test/a").call().unwrap();
+
+        let stats = agent.pool_stats();
+        assert_eq!(stats.in_use(), 3);
+        assert_eq!(stats.idle(), 0);
+        let hosts: Vec<_> = stats
+            .hosts()
+            .iter()
+            .map(|h| (h.host(), h.idle(), h.in_use()))
+            .collect();
+        assert_eq!(hosts, [("one.test", 0, 1), ("two.test", 0, 2)]);
+
+        res1.body_mut().read_to_string().unwrap();
+        res2.body_mut().read_to_string().unwrap();
+        // Dropping drains the body, which also returns the connection.
+        drop(res3);
+
+        let stats = agent.pool_stats();
+        assert_eq!(stats.in_use(), 0);
+        assert_eq!(stats.idle(), 3);
+        let hosts: Vec<_> = stats
+            .hosts()
+            .iter()
+            .map(|h| (h.host(), h.idle(), h.in_use()))
+            .collect();
+        assert_eq!(hosts, [("one.test", 1, 0), ("two.test", 2, 0)]);
+    }
+
+    #[test]
+    fn empty_bodies() {
+        let server = TestServer::new()
+            .route("/no-content", |_| {
+                Response::builder()
+                    .status(204)
+                    // Wrong, but must not make us wait for a body.
+                    .header("content-length", "5")
+                    .body(vec![])
+                    .unwrap()
+            })
+            .route("/a", |_| Response::new(b"a".to_vec()))
+            .route("/zero", |_| Response::new(vec![]));
+        let agent = server.agent(Config::default());
+
+        let mut res = agent.get("http://example.test/no-content").call().unwrap();
+        assert!(res.body().is_empty());
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "");
+        assert_eq!(agent.pool_count(), 1);
+
+        let res = agent.head("http://example.test/a").call().unwrap();
+        assert!(res.body().is_empty());
+
+        let mut res = agent.get("http://example.test/zero").call().unwrap();
+        assert!(res.body().is_empty());
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "");
+
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        assert!(!res.body().is_empty());
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "a");
+
+        // All on the one connection.
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn not_modified_ignores_framing() {
+        let not_modified = |name: &'static str, value: &'static str| {
+            move |_: &Request<Vec<u8>>| {
+                Response::builder()
+                    .status(304)
+                    .header(name, value)
+                    .body(vec![])
+                    .unwrap()
+            }
+        };
+
+        let server = TestServer::new()
+            .route("/length", not_modified("content-length", "100"))
+            .route("/chunked", not_modified("transfer-encoding", "chunked"))
+            .route("/a", |_| Response::new(b"a".to_vec()));
+        let agent = server.agent(Config::default());
+
+        for path in ["/length", "/chunked"] {
+            let uri = format!("http://example.test{}", path);
+            let mut res = agent.get(&uri).call().unwrap();
+            assert_eq!(res.status(), 304);
+            assert!(res.body().is_empty());
+            assert_eq!(res.body_mut().read_to_string().unwrap(), "");
+        }
+
+        // The connection is still good for the next request.
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "a");
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "_test")]
+    fn fresh_connection_bypasses_pool() {
+        let server = TestServer::new().route("/a", |_| Response::new(b"a".to_vec()));
+        let agent = server.agent(Config::default());
+
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        res.body_mut().read_to_string().unwrap();
+        assert_eq!(agent.pool_count(), 1);
+
+        // Neither takes the pooled connection, nor adds another.
+        let mut res = agent
+            .get("http://example.test/a")
+            .fresh_connection()
+            .call()
+            .unwrap();
+        res.body_mut().read_to_string().unwrap();
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn count_bytes() {
+        let server = TestServer::new().route("/a", |_| Response::new(vec![b'a'; 1000]));
+        let agent = server.agent(Config::builder().count_bytes(true).build());
+
+        let mut res = agent.post("http://example.test/a").send(&[0; 500]).unwrap();
+        res.body_mut().read_to_vec().unwrap();
+
+        // Body plus some head.
+        let counts = agent.clone().byte_counts();
+        assert!(counts.sent() > 500 && counts.sent() < 1000);
+        assert!(counts.received() > 1000 && counts.received() < 1500);
+
+        assert_eq!(agent.reset_byte_counts(), counts);
+        assert_eq!(agent.byte_counts().sent(), 0);
+    }
+
+    #[test]
+    fn head_has_no_body() {
+        let server = TestServer::new().route("/a", |_| Response::new(b"abc".to_vec()));
+        let agent = server.agent(Config::default());
+
+        let res = agent.head("http://example.test/a").call().unwrap();
+        assert_eq!(res.headers()["content-length"], "3");
+    }
+
+    #[test]
+    fn head_chunked_has_no_body() {
+        let server = TestServer::new().route("/a", |_| {
+            Response::builder()
+                .header("transfer-encoding", "chunked")
+                .body(b"3\r\nabc\r\n0\r\n\r\n".to_vec())
+                .unwrap()
+        });
+        let agent = server.agent(Config::default());
+
+        let mut res = agent.head("http://example.test/a").call().unwrap();
+        assert_eq!(res.headers()["transfer-encoding"], "chunked");
+        assert_eq!(res.body().content_length(), None);
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "");
+
+        // The connection is reusable, with nothing left of the HEAD response.
+        assert_eq!(agent.pool_count(), 1);
+        let mut res = agent.get("http://example.test/a").call().unwrap();
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "abc");
+        assert_eq!(agent.pool_count(), 1);
+    }
+
+    #[test]
+    fn framed_chunked() {
+        let mut body = framed_body(Framing::Chunked, "3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n").unwrap();
+        assert_eq!(body.read_to_string().unwrap(), "abcde");
+
+        let mut body = framed_body(Framing::Chunked, "0\r\n\r\n").unwrap();
+        assert_eq!(body.read_to_string().unwrap(), "");
+
+        // Complete chunks, but no last chunk.
+        let mut body = framed_body(Framing::Chunked, "3\r\nabc\r\n").unwrap();
+        let err = body.read_to_string().unwrap_err();
+        assert!(err.to_string().contains("before the last chunk"), "{}", err);
+    }
+
+    #[test]
+    fn framed_content_length() {
+        let mut body = framed_body(Framing::ContentLength(3), "abc").unwrap();
+        assert_eq!(body.read_to_string().unwrap(), "abc");
+
+        let mut body = framed_body(Framing::ContentLength(0), "").unwrap();
+        assert_eq!(body.read_to_string().unwrap(), "");
+
+        let mut body = framed_body(Framing::ContentLength(5), "abc").unwrap();
+        let err = body.read_to_string().unwrap_err();
+        assert!(err.to_string().contains("3 of 5"), "{}", err);
+    }
+
+    #[test]
+    fn framed_close_delimited() {
+        let mut body = framed_body(Framing::CloseDelimited, "until close").unwrap();
+        assert_eq!(body.content_length(), None);
+        assert_eq!(body.read_to_string().unwrap(), "until close");
+    }
+
+    #[test]
+    fn decode_chunks() {
+        let input = b"3;ext\r\nabc\r\n2\r\nde\r\n0\r\nx-trailer: 1\r\n\r\nnext";
+        let (body, used) = decode_chunked(input).unwrap();
+        assert_eq!(body, b"abcde");
+        assert_eq!(&input[used..], b"next");
+
+        assert!(decode_chunked(b"3\r\nabc\r\n0\r\n").is_none());
+    }
+}
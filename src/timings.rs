@@ -59,12 +59,6 @@ impl Timeout {
         prev.iter().copied()
     }
 
-    /// All timeouts to check
-    fn timeouts_to_check(&self) -> impl Iterator<Item = Timeout> {
-        // Always check Global and PerCall
-        self.preceeding().chain([Timeout::Global, Timeout::PerCall])
-    }
-
     /// Get the corresponding configured timeout
     fn configured_timeout(&self, timeouts: &Timeouts) -> Option<Duration> {
         match self {
@@ -86,7 +80,12 @@ impl Timeout {
 pub(crate) struct CallTimings {
     timeouts: Timeouts,
     current_time: CurrentTime,
-    times: ArrayVec<(Timeout, Instant), 8>,
+    /// Room for each Timeout to be recorded once.
+    times: ArrayVec<(Timeout, Instant), 9>,
+    /// When the entire call must be finished, by the global timeout or the deadline.
+    deadline: Instant,
+    /// When the current call must be finished, by the per call timeout.
+    call_deadline: Instant,
 }
 
 impl Default for CallTimings {
@@ -95,11 +94,13 @@ impl Default for CallTimings {
             timeouts: Default::default(),
             current_time: Default::default(),
             times: empty_times(),
+            deadline: Instant::NotHappening,
+            call_deadline: Instant::NotHappening,
         }
     }
 }
 
-fn empty_times() -> ArrayVec<(Timeout, Instant), 8> {
+fn empty_times() -> ArrayVec<(Timeout, Instant), 9> {
     ArrayVec::from_fn(|_| (Timeout::Global, Instant::AlreadyHappened))
 }
 
@@ -111,21 +112,39 @@ impl CallTimings {
         times.push((Timeout::Global, now));
         times.push((Timeout::PerCall, now));
 
+        // The relative global timeout is counted from the start, and folded into the
+        // absolute deadline.
+        let global = Timeout::Global
+            .configured_timeout(&timeouts)
+            .map(|t| now + t);
+        let deadline = global
+            .into_iter()
+            .chain(timeouts.deadline.map(Instant::Exact))
+            .min()
+            .unwrap_or(Instant::NotHappening);
+
+        let call_deadline = call_deadline(&timeouts, now);
+
         CallTimings {
             timeouts,
             current_time,
             times,
+            deadline,
+            call_deadline,
         }
     }
 
     pub(crate) fn new_call(mut self) -> CallTimings {
+        let now = self.current_time.now();
         self.times.truncate(1); // Global is in position 0.
-        self.times.push((Timeout::PerCall, self.current_time.now()));
+        self.times.push((Timeout::PerCall, now));
 
         CallTimings {
+            call_deadline: call_deadline(&self.timeouts, now),
             timeouts: self.timeouts,
             current_time: self.current_time,
             times: self.times,
+            deadline: self.deadline,
         }
     }
 
@@ -154,20 +173,38 @@ impl CallTimings {
         self.times.push((timeout, self.current_time.now()));
     }
 
+    /// Forget the time of `timeout`, and of the phases recorded after it, to record them
+    /// again for the next request on the same connection.
+    pub(crate) fn clear_from(&mut self, timeout: Timeout) {
+        if let Some(i) = self.times.iter().position(|x| x.0 == timeout) {
+            self.times.truncate(i);
+        }
+    }
+
     fn time_of(&self, timeout: Timeout) -> Option<Instant> {
         self.times.iter().find(|x| x.0 == timeout).map(|x| x.1)
     }
 
     pub(crate) fn next_timeout(&self, timeout: Timeout) -> NextTimeout {
-        let (reason, at) = timeout
-            .timeouts_to_check()
-            .filter_map(|to_check| {
-                let time = self.time_of(to_check)?;
-                let timeout = to_check.configured_timeout(&self.timeouts)?;
-                Some((to_check, time + timeout))
-            })
-            .min_by(|a, b| a.1.cmp(&b.1))
-            .unwrap_or((Timeout::Global, Instant::NotHappening));
+        // The timeout of the current phase counts from when the (latest) preceeding
+        // phase was finished.
+        let phase = timeout.preceeding().filter_map(|p| self.time_of(p)).max();
+        let current = phase.and_then(|start| {
+            let after = timeout.configured_timeout(&self.timeouts)?;
+            Some((timeout, start + after))
+        });
+
+        // Always check Global and PerCall. The remaining time is counted from the
+        // earliest point in time.
+        let (reason, at) = [
+            (Timeout::Global, self.deadline),
+            (Timeout::PerCall, self.call_deadline),
+        ]
+        .iter()
+        .copied()
+        .chain(current)
+        .min_by(|a, b| a.1.cmp(&b.1))
+        .unwrap_or((Timeout::Global, Instant::NotHappening));
 
         let now = self.now();
         let after = at.duration_since(now);
@@ -176,6 +213,14 @@ impl CallTimings {
     }
 }
 
+/// The per call timeout as a point in time, for a call starting `now`.
+fn call_deadline(timeouts: &Timeouts, now: Instant) -> Instant {
+    Timeout::PerCall
+        .configured_timeout(timeouts)
+        .map(|t| now + t)
+        .unwrap_or(Instant::NotHappening)
+}
+
 #[derive(Clone)]
 pub(crate) struct CurrentTime(Arc<dyn Fn() -> Instant + Send + Sync + 'static>);
 
@@ -234,3 +279,147 @@ impl fmt::Display for Timeout {
         write!(f, "{}", r)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timings(timeouts: Timeouts) -> CallTimings {
+        // A frozen clock.
+        let now = Instant::now();
+        CallTimings::new(timeouts, CurrentTime(Arc::new(move || now)))
+    }
+
+    fn secs(v: u64) -> Option<std::time::Duration> {
+        Some(std::time::Duration::from_secs(v))
+    }
+
+    #[test]
+    fn resolve_timeout() {
+        let timings = timings(Timeouts {
+            resolve: secs(2),
+            connect: secs(5),
+            ..Default::default()
+        });
+
+        let next = timings.next_timeout(Timeout::Resolve);
+        assert_eq!(next.reason, Timeout::Resolve);
+        assert_eq!(next.after, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn phase_timeout_after_preceeding() {
+        let mut timings = timings(Timeouts {
+            resolve: secs(2),
+            connect: secs(5),
+            per_call: secs(3),
+            ..Default::default()
+        });
+
+        timings.record_time(Timeout::Resolve);
+
+        // The per call timeout is closer than connect.
+        let next = timings.next_timeout(Timeout::Connect);
+        assert_eq!(next.reason, Timeout::PerCall);
+        assert_eq!(next.after, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn clear_from_keeps_connection() {
+        let mut timings = timings(Timeouts::default());
+
+        timings.record_time(Timeout::Resolve);
+        timings.record_time(Timeout::Connect);
+        timings.record_time(Timeout::SendRequest);
+        timings.record_time(Timeout::RecvResponse);
+
+        timings.clear_from(Timeout::SendRequest);
+        assert!(timings.time_of(Timeout::Connect).is_some());
+        assert!(timings.time_of(Timeout::RecvResponse).is_none());
+
+        // Recorded again for the next request.
+        timings.record_time(Timeout::SendRequest);
+    }
+
+    #[test]
+    fn recv_body_is_total_time() {
+        let start = std::time::Instant::now();
+        let elapsed = Arc::new(std::sync::Mutex::new(std::time::Duration::ZERO));
+        let clock = elapsed.clone();
+        let current_time = CurrentTime(Arc::new(move || {
+            Instant::Exact(start + *clock.lock().unwrap())
+        }));
+
+        let timeouts = Timeouts {
+            recv_body: secs(10),
+            ..Default::default()
+        };
+        let mut timings = CallTimings::new(timeouts, current_time);
+
+        timings.record_time(Timeout::Resolve);
+        timings.record_time(Timeout::Connect);
+        timings.record_time(Timeout::SendRequest);
+        timings.record_time(Timeout::RecvResponse);
+
+        // Reading some of the body doesn't move the deadline.
+        *elapsed.lock().unwrap() = secs(4).unwrap();
+        let next = timings.next_timeout(Timeout::RecvBody);
+        assert_eq!(next.reason, Timeout::RecvBody);
+        assert_eq!(next.after, Duration::from_secs(6));
+
+        *elapsed.lock().unwrap() = secs(11).unwrap();
+        let next = timings.next_timeout(Timeout::RecvBody);
+        assert_eq!(next.after, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn global_counts_down_from_start() {
+        let start = std::time::Instant::now();
+        let elapsed = Arc::new(std::sync::Mutex::new(std::time::Duration::ZERO));
+        let clock = elapsed.clone();
+        let current_time = CurrentTime(Arc::new(move || {
+            Instant::Exact(start + *clock.lock().unwrap())
+        }));
+
+        let timeouts = Timeouts {
+            global: secs(10),
+            per_call: secs(8),
+            ..Default::default()
+        };
+        let timings = CallTimings::new(timeouts, current_time);
+
+        *elapsed.lock().unwrap() = secs(5).unwrap();
+        let next = timings.next_timeout(Timeout::Resolve);
+        assert_eq!(next.reason, Timeout::PerCall);
+        assert_eq!(next.after, Duration::from_secs(3));
+
+        // A new call restarts the per call timeout, but not the global one.
+        let timings = timings.new_call();
+        let next = timings.next_timeout(Timeout::Resolve);
+        assert_eq!(next.reason, Timeout::Global);
+        assert_eq!(next.after, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn earliest_of_global_and_deadline() {
+        let now = std::time::Instant::now();
+        let frozen = CurrentTime(Arc::new(move || Instant::Exact(now)));
+
+        let timeouts = Timeouts {
+            global: secs(10),
+            deadline: Some(now + std::time::Duration::from_secs(4)),
+            ..Default::default()
+        };
+        let next = CallTimings::new(timeouts, frozen.clone()).next_timeout(Timeout::Connect);
+        assert_eq!(next.reason, Timeout::Global);
+        assert_eq!(next.after, Duration::from_secs(4));
+
+        let timeouts = Timeouts {
+            global: secs(2),
+            deadline: Some(now + std::time::Duration::from_secs(4)),
+            ..Default::default()
+        };
+        let next = CallTimings::new(timeouts, frozen).next_timeout(Timeout::Connect);
+        assert_eq!(next.after, Duration::from_secs(2));
+    }
+}
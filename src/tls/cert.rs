@@ -69,6 +69,12 @@ impl<'a> Certificate<'a> {
             der: CertDer::Owned(self.der.as_ref().to_vec()),
         }
     }
+
+    pub(crate) fn from_der_owned(der: Vec<u8>) -> Certificate<'static> {
+        Certificate {
+            der: CertDer::Owned(der),
+        }
+    }
 }
 
 /// A private key used in client certificate auth.
@@ -6,11 +6,16 @@ use std::sync::Arc;
 mod cert;
 pub use cert::{parse_pem, Certificate, PemItem, PrivateKey};
 
+#[cfg(feature = "rustls")]
+mod ocsp;
 #[cfg(feature = "rustls")]
 mod rustls;
 #[cfg(feature = "rustls")]
 pub use self::rustls::RustlsConnector;
 
+#[cfg(all(test, feature = "rustls"))]
+pub(crate) mod test_server;
+
 #[cfg(feature = "native-tls")]
 mod native_tls;
 #[cfg(feature = "native-tls")]
@@ -98,6 +103,28 @@ pub struct TlsConfig {
     /// Defaults to `false`.
     pub(crate) use_rpk: bool,
 
+    /// Max number of TLS sessions to remember for resumption.
+    ///
+    /// Resuming a previous session makes repeated connections to the same host
+    /// skip the full handshake. Set to `0` to disable resumption altogether.
+    /// Currently only supported by [`TlsProvider::Rustls`].
+    ///
+    /// Defaults to `256`.
+    pub(crate) session_cache_size: usize,
+
+    /// Application protocols to advertise using ALPN, in order of preference.
+    ///
+    /// An empty list means ALPN is not used.
+    ///
+    /// Defaults to `["http/1.1"]`.
+    pub(crate) alpn_protocols: Arc<Vec<String>>,
+
+    /// Whether to check a stapled OCSP response from the server.
+    /// Currently only supported by [`TlsProvider::Rustls`].
+    ///
+    /// Defaults to [`OcspStapling::Off`].
+    pub(crate) ocsp_stapling: OcspStapling,
+
     /// **WARNING** Disable all server certificate verification.
     ///
     /// This breaks encryption and leaks secrets. Must never be enabled for code where
@@ -149,6 +176,11 @@ impl TlsConfigBuilder {
     /// This is used by the server to determine which domain/certificate we are connecting
     /// to for servers where multiple domains/sites are hosted on the same IP.
     ///
+    /// Turning SNI off does not turn off certificate verification, the certificate
+    /// is still checked against the host of the url. For IP addresses, such as
+    /// `https://10.0.0.5/`, SNI is never sent and the certificate must have a
+    /// matching IP SAN.
+    ///
     /// Defaults to `true`.
     pub fn use_sni(mut self, v: bool) -> Self {
         self.config.use_sni = v;
@@ -164,6 +196,59 @@ impl TlsConfigBuilder {
         self
     }
 
+    /// Max number of TLS sessions to remember for resumption.
+    ///
+    /// Resuming a previous session makes repeated connections to the same host
+    /// skip the full handshake. Set to `0` to disable resumption altogether.
+    /// Currently only supported by [`TlsProvider::Rustls`].
+    ///
+    /// Defaults to `256`.
+    pub fn session_cache_size(mut self, v: usize) -> Self {
+        self.config.session_cache_size = v;
+        self
+    }
+
+    /// Application protocols to advertise using ALPN, in order of preference.
+    ///
+    /// The protocol selected by the server is available as the response extension
+    /// [`AlpnProtocol`]. An empty list means ALPN is not used.
+    ///
+    /// ```
+    /// use ureq::tls::TlsConfig;
+    ///
+    /// let tls_config = TlsConfig::builder()
+    ///     .alpn_protocols(&["http/1.1"])
+    ///     .build();
+    /// ```
+    ///
+    /// Defaults to `["http/1.1"]`.
+    pub fn alpn_protocols(mut self, v: &[&str]) -> Self {
+        self.config.alpn_protocols = Arc::new(v.iter().map(|p| p.to_string()).collect());
+        self
+    }
+
+    /// Whether to check a stapled OCSP response from the server.
+    ///
+    /// A failed check aborts the handshake with [`Error::Tls`](crate::Error::Tls).
+    ///
+    /// Only supported by [`TlsProvider::Rustls`]. With [`TlsProvider::NativeTls`], any
+    /// setting other than [`OcspStapling::Off`] fails the connection with
+    /// [`Error::Tls`](crate::Error::Tls).
+    ///
+    /// ```
+    /// use ureq::tls::{OcspStapling, TlsConfig};
+    ///
+    /// let tls_config = TlsConfig::builder()
+    ///     .ocsp_stapling(OcspStapling::Require)
+    ///     .build();
+    /// ```
+    ///
+    /// Defaults to [`OcspStapling::Off`].
+    pub fn ocsp_stapling(mut self, v: OcspStapling) -> Self {
+        self.config.ocsp_stapling = v;
+        self
+    }
+
     /// **WARNING** Disable all server certificate verification.
     ///
     /// This breaks encryption and leaks secrets. Must never be enabled for code where
@@ -179,6 +264,33 @@ impl TlsConfigBuilder {
     }
 }
 
+/// Setting for checking stapled OCSP responses.
+///
+/// With OCSP stapling the server sends a recent, signed statement from the certificate
+/// authority saying the server certificate is not revoked. The statement must be signed
+/// by the issuer of the server certificate, and the issuer must be part of the certificate
+/// chain sent by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OcspStapling {
+    /// Don't check OCSP responses.
+    ///
+    /// This is the default.
+    Off,
+
+    /// Check the OCSP response if the server staples one, but accept servers that don't.
+    Prefer,
+
+    /// Require the server to staple a valid OCSP response.
+    Require,
+}
+
+impl Default for OcspStapling {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
 /// A client certificate.
 #[derive(Debug, Clone)]
 pub struct ClientCert(pub Arc<(Vec<Certificate<'static>>, PrivateKey<'static>)>);
@@ -226,6 +338,132 @@ impl<I: IntoIterator<Item = Certificate<'static>>> From<I> for RootCerts {
     }
 }
 
+/// Application protocol negotiated with ALPN during the TLS handshake.
+///
+/// Inserted as an extension on HTTPS responses where the server selected one of
+/// the [advertised protocols](TlsConfigBuilder::alpn_protocols).
+///
+/// ```no_run
+/// use ureq::tls::AlpnProtocol;
+///
+/// let res = ureq::get("https://www.google.com/").call()?;
+///
+/// if let Some(alpn) = res.extensions().get::<AlpnProtocol>() {
+///     println!("Negotiated: {:?}", alpn.as_str());
+/// }
+/// # Ok::<_, ureq::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlpnProtocol(pub(crate) Vec<u8>);
+
+impl AlpnProtocol {
+    /// The protocol identifier as sent on the wire. Typically `b"http/1.1"`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The protocol identifier as a string, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+}
+
+/// Certificates presented by the server during the TLS handshake.
+///
+/// Inserted as an extension on HTTPS responses. The end-entity certificate comes
+/// first, followed by any intermediates the server sent. With
+/// [`TlsProvider::NativeTls`] only the end-entity certificate is available.
+///
+/// ```no_run
+/// use ureq::tls::PeerCertificates;
+///
+/// let res = ureq::get("https://www.google.com/").call()?;
+///
+/// if let Some(certs) = res.extensions().get::<PeerCertificates>() {
+///     for cert in certs.certificates() {
+///         println!("{} bytes", cert.der().len());
+///     }
+/// }
+/// # Ok::<_, ureq::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PeerCertificates(Vec<Certificate<'static>>);
+
+impl PeerCertificates {
+    pub(crate) fn from_der(certs: Vec<Vec<u8>>) -> Self {
+        PeerCertificates(certs.into_iter().map(Certificate::from_der_owned).collect())
+    }
+
+    /// The certificate chain, starting with the end-entity certificate.
+    pub fn certificates(&self) -> &[Certificate<'static>] {
+        &self.0
+    }
+
+    /// The end-entity certificate identifying the server.
+    pub fn end_entity(&self) -> Option<&Certificate<'static>> {
+        self.0.first()
+    }
+}
+
+/// Parameters negotiated during the TLS handshake.
+///
+/// Inserted as an extension on HTTPS responses, useful for diagnostics or verifying
+/// that the [`TlsConfig`] took effect. Each value is `None` when the TLS provider
+/// does not report it. [`TlsProvider::NativeTls`] only reports ALPN.
+///
+/// ```no_run
+/// use ureq::tls::TlsInfo;
+///
+/// let res = ureq::get("https://www.google.com/").call()?;
+///
+/// if let Some(info) = res.extensions().get::<TlsInfo>() {
+///     println!("{:?} {:?}", info.protocol_version(), info.cipher_suite());
+/// }
+/// # Ok::<_, ureq::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsInfo {
+    protocol_version: Option<String>,
+    cipher_suite: Option<String>,
+    alpn_protocol: Option<AlpnProtocol>,
+}
+
+impl TlsInfo {
+    /// `None` if there is nothing to report, which is the case for plain HTTP.
+    pub(crate) fn new(
+        protocol_version: Option<String>,
+        cipher_suite: Option<String>,
+        alpn_protocol: Option<Vec<u8>>,
+    ) -> Option<Self> {
+        if protocol_version.is_none() && cipher_suite.is_none() && alpn_protocol.is_none() {
+            return None;
+        }
+
+        Some(TlsInfo {
+            protocol_version,
+            cipher_suite,
+            alpn_protocol: alpn_protocol.map(AlpnProtocol),
+        })
+    }
+
+    /// The protocol version, such as `"TLSv1_3"`.
+    pub fn protocol_version(&self) -> Option<&str> {
+        self.protocol_version.as_deref()
+    }
+
+    /// The cipher suite, such as `"TLS13_AES_256_GCM_SHA384"`.
+    pub fn cipher_suite(&self) -> Option<&str> {
+        self.cipher_suite.as_deref()
+    }
+
+    /// The application protocol negotiated with ALPN.
+    ///
+    /// This is the same as the [`AlpnProtocol`] extension.
+    pub fn alpn_protocol(&self) -> Option<&AlpnProtocol> {
+        self.alpn_protocol.as_ref()
+    }
+}
+
 impl Default for TlsConfig {
     fn default() -> Self {
         let provider = TlsProvider::default();
@@ -235,6 +473,9 @@ impl Default for TlsConfig {
             root_certs: RootCerts::WebPki,
             use_sni: true,
             use_rpk: false,
+            session_cache_size: 256,
+            alpn_protocols: Arc::new(vec!["http/1.1".to_string()]),
+            ocsp_stapling: OcspStapling::default(),
             disable_verification: false,
         }
     }
@@ -254,6 +495,9 @@ impl fmt::Debug for TlsConfig {
             .field("root_certs", &self.root_certs)
             .field("use_sni", &self.use_sni)
             .field("use_rpk", &self.use_rpk)
+            .field("session_cache_size", &self.session_cache_size)
+            .field("alpn_protocols", &self.alpn_protocols)
+            .field("ocsp_stapling", &self.ocsp_stapling)
             .field("disable_verification", &self.disable_verification)
             .finish()
     }
@@ -3,7 +3,7 @@ use std::fmt;
 use std::io::{Read, Write};
 use std::sync::Arc;
 
-use crate::tls::{RootCerts, TlsProvider};
+use crate::tls::{OcspStapling, RootCerts, TlsProvider};
 use crate::{transport::*, Error};
 use der::pem::LineEnding;
 use der::Document;
@@ -47,6 +47,12 @@ impl Connector for NativeTlsConnector {
 
         let tls_config = &details.config.tls_config;
 
+        // native-tls can't check a stapled OCSP response. Fail rather than connect
+        // without the revocation check that was asked for.
+        if tls_config.ocsp_stapling != OcspStapling::Off {
+            return Err(Error::Tls("OCSP stapling is not supported by native-tls"));
+        }
+
         // Initialize the connector on first run.
         let connector_ref = match self.connector.get() {
             Some(v) => v,
@@ -60,11 +66,18 @@ impl Connector for NativeTlsConnector {
         };
         let connector = connector_ref.clone(); // cheap clone due to Arc
 
-        let domain = details
+        let host = details
             .uri
             .authority()
             .expect("uri authority for tls")
-            .host()
+            .host();
+
+        // IPv6 hosts come bracketed from the uri. Without brackets, native-tls
+        // recognizes the IP and verifies against the IP SANs without sending SNI.
+        let domain = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host)
             .to_string();
 
         let adapter = TransportAdapter::new(transport);
@@ -127,6 +140,11 @@ fn build_connector(tls_config: &TlsConfig) -> Result<Arc<TlsConnector>, Error> {
         builder.identity(identity);
     }
 
+    if !tls_config.alpn_protocols.is_empty() {
+        let protocols: Vec<&str> = tls_config.alpn_protocols.iter().map(|p| &**p).collect();
+        builder.request_alpns(&protocols);
+    }
+
     builder.use_sni(tls_config.use_sni);
 
     if !tls_config.use_sni {
@@ -183,6 +201,7 @@ impl Transport for NativeTlsTransport {
 
         let output = &self.buffers.output()[..amount];
         stream.write_all(output)?;
+        stream.flush()?;
 
         Ok(())
     }
@@ -209,9 +228,35 @@ impl Transport for NativeTlsTransport {
             .unwrap_or(false)
     }
 
+    fn shutdown(&mut self) {
+        // No handshake means nothing to shut down.
+        let LazyStream::Started(v) = &mut self.stream else {
+            return;
+        };
+        if let Err(e) = v.shutdown() {
+            debug!("Failed to send close_notify: {}", e);
+        }
+    }
+
     fn is_tls(&self) -> bool {
         true
     }
+
+    fn alpn_protocol(&self) -> Option<Vec<u8>> {
+        match &self.stream {
+            LazyStream::Started(v) => v.negotiated_alpn().ok().flatten(),
+            LazyStream::Unstarted(_) => None,
+        }
+    }
+
+    fn peer_certificates(&self) -> Option<Vec<Vec<u8>>> {
+        // native-tls only gives access to the end-entity certificate.
+        let LazyStream::Started(v) = &self.stream else {
+            return None;
+        };
+        let cert = v.peer_certificate().ok().flatten()?;
+        Some(vec![cert.to_der().ok()?])
+    }
 }
 
 /// Helper to delay the handshake until we are starting IO.
@@ -0,0 +1,268 @@
+//! Checking of stapled OCSP responses (RFC 6960).
+//!
+//! The response is decoded with `x509-ocsp`, the CertID hashes are computed with the
+//! `sha1` crate, and the signature is checked with the algorithms of the rustls crypto
+//! provider. The response must be signed directly by the issuer of the end entity
+//! certificate, and that issuer must be part of the chain presented by the server.
+//! Responses from delegated responders, with a certificate of their own, are rejected.
+//! The CertID must use SHA-1, which is what responders use in practice (RFC 5019).
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use der::asn1::ObjectIdentifier;
+use der::{Decode, Encode};
+use rustls_pki_types::{CertificateDer, SignatureVerificationAlgorithm, UnixTime};
+use sha1::{Digest, Sha1};
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+use x509_ocsp::{BasicOcspResponse, CertStatus, OcspResponse, OcspResponseStatus, ResponderId};
+
+/// Reason for rejecting a stapled OCSP response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OcspError(pub &'static str);
+
+impl fmt::Display for OcspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl StdError for OcspError {}
+
+const MALFORMED: OcspError = OcspError("OCSP response is malformed");
+
+/// id-pkix-ocsp-basic
+const OID_OCSP_BASIC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.48.1.1");
+
+/// id-sha1
+const OID_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+
+/// Verify that `staple` is a valid OCSP response saying `end_entity` is not revoked.
+pub(crate) fn verify_staple(
+    end_entity: &CertificateDer<'_>,
+    intermediates: &[CertificateDer<'_>],
+    staple: &[u8],
+    now: UnixTime,
+    algs: &[&dyn SignatureVerificationAlgorithm],
+) -> Result<(), OcspError> {
+    let leaf = Certificate::from_der(end_entity)
+        .map_err(|_| OcspError("Server certificate is malformed"))?
+        .tbs_certificate;
+
+    let issuer = intermediates
+        .iter()
+        .filter_map(|c| Certificate::from_der(c).ok())
+        .map(|c| c.tbs_certificate)
+        .find(|c| c.subject == leaf.issuer)
+        .ok_or(OcspError(
+            "OCSP check requires the issuer in the certificate chain",
+        ))?;
+
+    let response = OcspResponse::from_der(staple).map_err(|_| MALFORMED)?;
+    if !matches!(response.response_status, OcspResponseStatus::Successful) {
+        return Err(OcspError("OCSP response status is not successful"));
+    }
+
+    let bytes = response.response_bytes.ok_or(MALFORMED)?;
+    if bytes.response_type != OID_OCSP_BASIC {
+        return Err(OcspError("OCSP response type is not supported"));
+    }
+
+    let basic = BasicOcspResponse::from_der(bytes.response.as_bytes()).map_err(|_| MALFORMED)?;
+    let data = &basic.tbs_response_data;
+
+    let issuer_spki = &issuer.subject_public_key_info;
+    let issuer_key = issuer_spki.subject_public_key.as_bytes().ok_or(MALFORMED)?;
+    let issuer_key_hash = Sha1::digest(issuer_key);
+
+    let signed_by_issuer = match &data.responder_id {
+        ResponderId::ByName(name) => *name == issuer.subject,
+        ResponderId::ByKey(hash) => hash.as_bytes() == issuer_key_hash.as_slice(),
+    };
+    if !signed_by_issuer {
+        return Err(OcspError(
+            "OCSP responses from delegated responders are not supported",
+        ));
+    }
+
+    let key_alg = alg_id(&issuer_spki.algorithm)?;
+    let signature_alg = alg_id(&basic.signature_algorithm)?;
+
+    let alg = algs
+        .iter()
+        .find(|a| {
+            a.public_key_alg_id().as_ref() == key_alg
+                && a.signature_alg_id().as_ref() == signature_alg
+        })
+        .ok_or(OcspError(
+            "OCSP response signature algorithm is not supported",
+        ))?;
+
+    // DER is canonical, so encoding the decoded tbsResponseData again gives the
+    // signed bytes.
+    let tbs = data.to_der().map_err(|_| MALFORMED)?;
+    let signature = basic.signature.as_bytes().ok_or(MALFORMED)?;
+
+    alg.verify_signature(issuer_key, &tbs, signature)
+        .map_err(|_| OcspError("OCSP response signature is invalid"))?;
+
+    // The serial is only unique per issuer, the CertID also identifies the issuer.
+    let issuer_name = issuer.subject.to_der().map_err(|_| MALFORMED)?;
+    let issuer_name_hash = Sha1::digest(issuer_name);
+
+    let single = data
+        .responses
+        .iter()
+        .find(|s| {
+            let id = &s.cert_id;
+            id.hash_algorithm.oid == OID_SHA1
+                && id.issuer_name_hash.as_bytes() == issuer_name_hash.as_slice()
+                && id.issuer_key_hash.as_bytes() == issuer_key_hash.as_slice()
+                && id.serial_number == leaf.serial_number
+        })
+        .ok_or(OcspError("OCSP response is not for the server certificate"))?;
+
+    let now = now.as_secs();
+
+    if single.this_update.0.to_unix_duration().as_secs() > now {
+        return Err(OcspError("OCSP response is not yet valid"));
+    }
+    let next_update = single.next_update.as_ref();
+    if next_update.map(|t| t.0.to_unix_duration().as_secs() < now) == Some(true) {
+        return Err(OcspError("OCSP response is expired"));
+    }
+
+    match single.cert_status {
+        CertStatus::Good(_) => Ok(()),
+        CertStatus::Revoked(_) => Err(OcspError("Server certificate is revoked (OCSP)")),
+        CertStatus::Unknown(_) => Err(OcspError(
+            "OCSP responder does not know the server certificate",
+        )),
+    }
+}
+
+/// An AlgorithmIdentifier without the outer SEQUENCE, which is how rustls identifies
+/// algorithms.
+fn alg_id(alg: &AlgorithmIdentifierOwned) -> Result<Vec<u8>, OcspError> {
+    let mut v = alg.oid.to_der().map_err(|_| MALFORMED)?;
+    if let Some(params) = &alg.parameters {
+        v.extend(params.to_der().map_err(|_| MALFORMED)?);
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tls::test_server::{ocsp_staple, server_chain, server_key};
+    use der::asn1::{BitString, OctetString};
+    use rustls::SignatureScheme;
+
+    fn verify_for(chain: &[CertificateDer<'_>], staple: &[u8], now: u64) -> Result<(), OcspError> {
+        let algs = rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .all;
+        verify_staple(
+            &chain[0],
+            &chain[1..],
+            staple,
+            UnixTime::since_unix_epoch(std::time::Duration::from_secs(now)),
+            algs,
+        )
+    }
+
+    fn verify(staple: &[u8], now: u64) -> Result<(), OcspError> {
+        verify_for(&server_chain(), staple, now)
+    }
+
+    /// Change the good staple, and sign it with the server key instead of the CA key.
+    fn sign_with_server_key(change: impl FnOnce(&mut BasicOcspResponse)) -> Vec<u8> {
+        let mut response = OcspResponse::from_der(&ocsp_staple(false)).unwrap();
+        let bytes = response.response_bytes.as_mut().unwrap();
+        let mut basic = BasicOcspResponse::from_der(bytes.response.as_bytes()).unwrap();
+
+        change(&mut basic);
+
+        let key = rustls::crypto::ring::sign::any_ecdsa_type(&server_key()).unwrap();
+        let signer = key
+            .choose_scheme(&[SignatureScheme::ECDSA_NISTP256_SHA256])
+            .unwrap();
+        let tbs = basic.tbs_response_data.to_der().unwrap();
+        basic.signature = BitString::from_bytes(&signer.sign(&tbs).unwrap()).unwrap();
+
+        bytes.response = OctetString::new(basic.to_der().unwrap()).unwrap();
+        response.to_der().unwrap()
+    }
+
+    // 2030-01-01T00:00:00Z
+    const NOW: u64 = 1893456000;
+
+    // 2130-01-01T00:00:00Z, after the nextUpdate of the test staples.
+    const AFTER_NEXT_UPDATE: u64 = 5049129600;
+
+    #[test]
+    fn staple_good() {
+        assert_eq!(verify(&ocsp_staple(false), NOW), Ok(()));
+    }
+
+    #[test]
+    fn staple_revoked() {
+        let err = verify(&ocsp_staple(true), NOW).unwrap_err();
+        assert_eq!(err.0, "Server certificate is revoked (OCSP)");
+    }
+
+    #[test]
+    fn staple_not_yet_valid() {
+        let err = verify(&ocsp_staple(false), 0).unwrap_err();
+        assert_eq!(err.0, "OCSP response is not yet valid");
+    }
+
+    #[test]
+    fn staple_expired() {
+        let err = verify(&ocsp_staple(false), AFTER_NEXT_UPDATE).unwrap_err();
+        assert_eq!(err.0, "OCSP response is expired");
+    }
+
+    #[test]
+    fn staple_wrong_signer() {
+        // Claims to be from the CA, but is signed by the server.
+        let staple = sign_with_server_key(|_| {});
+        let err = verify(&staple, NOW).unwrap_err();
+        assert_eq!(err.0, "OCSP response signature is invalid");
+    }
+
+    #[test]
+    fn staple_delegated_responder() {
+        let chain = server_chain();
+        let responder = Certificate::from_der(&chain[0]).unwrap();
+
+        let staple = sign_with_server_key(|basic| {
+            let name = responder.tbs_certificate.subject.clone();
+            basic.tbs_response_data.responder_id = ResponderId::ByName(name);
+            basic.certs = Some(vec![responder.clone()]);
+        });
+
+        let err = verify(&staple, NOW).unwrap_err();
+        assert_eq!(
+            err.0,
+            "OCSP responses from delegated responders are not supported"
+        );
+    }
+
+    #[test]
+    fn staple_cert_id_mismatch() {
+        // The CA certificate is issued by the CA, which signed the staple, but the
+        // staple is for the server certificate.
+        let chain = server_chain();
+        let ca_chain = [chain[1].clone(), chain[1].clone()];
+
+        let err = verify_for(&ca_chain, &ocsp_staple(false), NOW).unwrap_err();
+        assert_eq!(err.0, "OCSP response is not for the server certificate");
+    }
+
+    #[test]
+    fn staple_garbage() {
+        assert_eq!(verify(b"\x30\x03\x0a\x01", NOW), Err(MALFORMED));
+    }
+}
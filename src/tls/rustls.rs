@@ -1,18 +1,23 @@
 use std::convert::TryInto;
 use std::fmt;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use once_cell::sync::OnceCell;
 use rustls::sign::CertifiedKey;
-use rustls::client::AlwaysResolvesClientRawPublicKeys;
+use rustls::client::{AlwaysResolvesClientRawPublicKeys, Resumption};
 use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
-use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned, ALL_VERSIONS};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::CryptoProvider;
+use rustls::{CertificateError, ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use rustls::{OtherError, ALL_VERSIONS};
 use rustls_pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer};
-use rustls_pki_types::{PrivateSec1KeyDer, ServerName};
+use rustls_pki_types::{PrivateSec1KeyDer, ServerName, SignatureVerificationAlgorithm, UnixTime};
 
 use crate::tls::cert::KeyKind;
-use crate::tls::{RootCerts, TlsProvider};
+use crate::tls::ocsp::{self, OcspError};
+use crate::tls::{OcspStapling, RootCerts, TlsProvider};
 use crate::transport::{Buffers, ConnectionDetails, Connector, LazyBuffers};
 use crate::transport::{NextTimeout, Transport, TransportAdapter};
 use crate::Error;
@@ -54,21 +59,16 @@ impl Connector for RustlsConnector {
         let tls_config = &details.config.tls_config;
 
         // Initialize the config on first run.
-        let config_ref = self.config.get_or_init(|| build_config(tls_config));
+        let config_ref = self.config.get_or_try_init(|| build_config(tls_config))?;
         let config = config_ref.clone(); // cheap clone due to Arc
 
-        let name_borrowed: ServerName<'_> = details
+        let host = details
             .uri
             .authority()
             .expect("uri authority for tls")
-            .host()
-            .try_into()
-            .map_err(|e| {
-                warn!("rustls invalid dns name: {}", e);
-                Error::Tls("Rustls invalid dns name error")
-            })?;
+            .host();
 
-        let name = name_borrowed.to_owned();
+        let name = server_name(host)?;
 
         let conn = ClientConnection::new(config, name)?;
         let stream = StreamOwned {
@@ -89,7 +89,29 @@ impl Connector for RustlsConnector {
     }
 }
 
-fn build_config(tls_config: &TlsConfig) -> Arc<ClientConfig> {
+/// The name to verify the server certificate against.
+///
+/// IP literals become [`ServerName::IpAddress`], which rustls checks against the IP SANs
+/// of the certificate and never sends as SNI. IPv6 hosts come bracketed from the uri.
+fn server_name(host: &str) -> Result<ServerName<'static>, Error> {
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+
+    if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+        return Ok(ServerName::IpAddress(ip.into()));
+    }
+
+    let name: ServerName<'_> = host.try_into().map_err(|e| {
+        warn!("rustls invalid dns name: {}", e);
+        Error::Tls("Rustls invalid dns name error")
+    })?;
+
+    Ok(name.to_owned())
+}
+
+fn build_config(tls_config: &TlsConfig) -> Result<Arc<ClientConfig>, Error> {
     // Improve chances of ureq working out-of-the-box by not requiring the user
     // to select a default crypto provider.
     let provider = rustls::crypto::CryptoProvider::get_default()
@@ -106,32 +128,30 @@ fn build_config(tls_config: &TlsConfig) -> Arc<ClientConfig> {
             .dangerous()
             .with_custom_certificate_verifier(Arc::new(DisabledVerifier))
     } else {
-        match &tls_config.root_certs {
-            RootCerts::Specific(certs) => {
-                let root_certs = certs.iter().map(|c| CertificateDer::from(c.der()));
-
-                let mut root_store = RootCertStore::empty();
-                let (added, ignored) = root_store.add_parsable_certificates(root_certs);
-                debug!("Added {} and ignored {} root certs", added, ignored);
-
-                builder.with_root_certificates(root_store)
-            }
-            #[cfg(not(feature = "platform-verifier"))]
-            RootCerts::PlatformVerifier => {
-                panic!("Rustls + PlatformVerifier requires feature: platform-verifier");
-            }
-            #[cfg(feature = "platform-verifier")]
-            RootCerts::PlatformVerifier => builder
+        match (root_store(tls_config), tls_config.ocsp_stapling) {
+            (Some(root_store), OcspStapling::Off) => builder.with_root_certificates(root_store),
+            (None, OcspStapling::Off) => builder
                 // This actually not dangerous. The rustls_platform_verifier is safe.
                 .dangerous()
-                .with_custom_certificate_verifier(Arc::new(
-                    rustls_platform_verifier::Verifier::new().with_provider(provider),
-                )),
-            RootCerts::WebPki => {
-                let root_store = RootCertStore {
-                    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+                .with_custom_certificate_verifier(platform_verifier(&provider)),
+            (root_store, mode) => {
+                debug!("Check stapled OCSP responses: {:?}", mode);
+
+                let inner = match root_store {
+                    Some(root_store) => webpki_verifier(root_store, &provider)?,
+                    None => platform_verifier(&provider),
                 };
-                builder.with_root_certificates(root_store)
+
+                let verifier = Arc::new(OcspVerifier {
+                    inner,
+                    required: mode == OcspStapling::Require,
+                    algs: provider.signature_verification_algorithms.all,
+                });
+
+                builder
+                    // The verifier is not dangerous, this is just how to set it.
+                    .dangerous()
+                    .with_custom_certificate_verifier(verifier)
             }
         }
     };
@@ -175,7 +195,72 @@ fn build_config(tls_config: &TlsConfig) -> Arc<ClientConfig> {
         debug!("Disable SNI");
     }
 
-    Arc::new(config)
+    config.alpn_protocols = tls_config
+        .alpn_protocols
+        .iter()
+        .map(|p| p.as_bytes().to_vec())
+        .collect();
+
+    config.resumption = if tls_config.session_cache_size > 0 {
+        Resumption::in_memory_sessions(tls_config.session_cache_size)
+    } else {
+        debug!("Disable TLS session resumption");
+        Resumption::disabled()
+    };
+
+    Ok(Arc::new(config))
+}
+
+/// The root certificates to verify against, `None` for the platform verifier.
+fn root_store(tls_config: &TlsConfig) -> Option<RootCertStore> {
+    match &tls_config.root_certs {
+        RootCerts::Specific(certs) => {
+            let root_certs = certs.iter().map(|c| CertificateDer::from(c.der()));
+
+            let mut root_store = RootCertStore::empty();
+            let (added, ignored) = root_store.add_parsable_certificates(root_certs);
+            debug!("Added {} and ignored {} root certs", added, ignored);
+
+            Some(root_store)
+        }
+        #[cfg(not(feature = "platform-verifier"))]
+        RootCerts::PlatformVerifier => {
+            panic!("Rustls + PlatformVerifier requires feature: platform-verifier");
+        }
+        #[cfg(feature = "platform-verifier")]
+        RootCerts::PlatformVerifier => None,
+        RootCerts::WebPki => Some(RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        }),
+    }
+}
+
+#[cfg(feature = "platform-verifier")]
+fn platform_verifier(provider: &Arc<CryptoProvider>) -> Arc<dyn ServerCertVerifier> {
+    Arc::new(rustls_platform_verifier::Verifier::new().with_provider(provider.clone()))
+}
+
+#[cfg(not(feature = "platform-verifier"))]
+fn platform_verifier(_provider: &Arc<CryptoProvider>) -> Arc<dyn ServerCertVerifier> {
+    unreachable!("root_store() panics for PlatformVerifier without the feature")
+}
+
+/// The verifier for the OCSP check to wrap.
+///
+/// Unlike `with_root_certificates()`, this verifier can't be built without any roots.
+fn webpki_verifier(
+    root_store: RootCertStore,
+    provider: &Arc<CryptoProvider>,
+) -> Result<Arc<dyn ServerCertVerifier>, Error> {
+    let verifier =
+        WebPkiServerVerifier::builder_with_provider(Arc::new(root_store), provider.clone())
+            .build()
+            .map_err(|e| {
+                warn!("rustls failed to build verifier: {}", e);
+                Error::Tls("Rustls OCSP check requires root certificates")
+            })?;
+
+    Ok(verifier)
 }
 
 struct RustlsTransport {
@@ -192,7 +277,11 @@ impl Transport for RustlsTransport {
         self.stream.get_mut().set_timeout(timeout);
 
         let output = &self.buffers.output()[..amount];
-        self.stream.write_all(output)?;
+        self.stream.write_all(output).map_err(map_io_error)?;
+
+        // Writes leave TLS records buffered if the socket fails, flush makes sure
+        // they reach it.
+        self.stream.flush().map_err(map_io_error)?;
 
         Ok(())
     }
@@ -205,7 +294,7 @@ impl Transport for RustlsTransport {
         self.stream.get_mut().set_timeout(timeout);
 
         let input = self.buffers.input_append_buf();
-        let amount = self.stream.read(input)?;
+        let amount = self.stream.read(input).map_err(map_io_error)?;
         self.buffers.input_appended(amount);
 
         Ok(amount > 0)
@@ -215,9 +304,118 @@ impl Transport for RustlsTransport {
         self.stream.get_mut().get_mut().is_open()
     }
 
+    fn shutdown(&mut self) {
+        self.stream.conn.send_close_notify();
+        if let Err(e) = self.stream.flush() {
+            debug!("Failed to send close_notify: {}", e);
+        }
+    }
+
     fn is_tls(&self) -> bool {
         true
     }
+
+    fn alpn_protocol(&self) -> Option<Vec<u8>> {
+        self.stream.conn.alpn_protocol().map(|p| p.to_vec())
+    }
+
+    fn peer_certificates(&self) -> Option<Vec<Vec<u8>>> {
+        let certs = self.stream.conn.peer_certificates()?;
+        Some(certs.iter().map(|c| c.as_ref().to_vec()).collect())
+    }
+
+    fn tls_protocol_version(&self) -> Option<String> {
+        let version = self.stream.conn.protocol_version()?;
+        Some(version.as_str()?.to_string())
+    }
+
+    fn tls_cipher_suite(&self) -> Option<String> {
+        let suite = self.stream.conn.negotiated_cipher_suite()?;
+        Some(suite.suite().as_str()?.to_string())
+    }
+}
+
+/// Surface a failed OCSP check during the handshake as [`Error::Tls`].
+fn map_io_error(e: io::Error) -> Error {
+    let ocsp_error = e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        .and_then(|rustls_error| match rustls_error {
+            rustls::Error::InvalidCertificate(CertificateError::Other(other)) => {
+                other.0.downcast_ref::<OcspError>().copied()
+            }
+            _ => None,
+        });
+
+    match ocsp_error {
+        Some(OcspError(reason)) => Error::Tls(reason),
+        None => e.into(),
+    }
+}
+
+/// Verifier that checks the stapled OCSP response after the regular verification.
+#[derive(Debug)]
+struct OcspVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    required: bool,
+    algs: &'static [&'static dyn SignatureVerificationAlgorithm],
+}
+
+impl ServerCertVerifier for OcspVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let result = if ocsp_response.is_empty() {
+            if self.required {
+                Err(OcspError("Server did not staple an OCSP response"))
+            } else {
+                trace!("No stapled OCSP response");
+                Ok(())
+            }
+        } else {
+            ocsp::verify_staple(end_entity, intermediates, ocsp_response, now, self.algs)
+        };
+
+        result.map(|_| verified).map_err(|e| {
+            debug!("OCSP check failed: {}", e);
+            rustls::Error::InvalidCertificate(CertificateError::Other(OtherError(Arc::new(e))))
+        })
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
 }
 
 #[derive(Debug)]
@@ -271,3 +469,257 @@ impl fmt::Debug for RustlsTransport {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustls::HandshakeKind;
+
+    use crate::tls::test_server::{ocsp_staple, server_chain, test_root_certs, TlsTestServer};
+    use crate::tls::{AlpnProtocol, OcspStapling, PeerCertificates};
+    use crate::tls::{TlsConfig, TlsConfigBuilder, TlsInfo};
+    use crate::Error;
+
+    fn handshakes(session_cache_size: usize) -> Vec<Option<HandshakeKind>> {
+        let server = TlsTestServer::start();
+        let tls_config = TlsConfig::builder()
+            .root_certs(test_root_certs())
+            .session_cache_size(session_cache_size)
+            .build();
+        let agent = server.agent(tls_config);
+        let url = format!("https://localhost:{}/", server.port);
+
+        for _ in 0..2 {
+            let mut res = agent.get(&url).call().unwrap();
+            assert_eq!(res.body_mut().read_to_string().unwrap(), "ok");
+        }
+
+        server
+            .seen()
+            .into_iter()
+            .map(|s| s.handshake_kind)
+            .collect()
+    }
+
+    #[test]
+    fn second_connection_resumes_session() {
+        assert_eq!(
+            handshakes(256),
+            [Some(HandshakeKind::Full), Some(HandshakeKind::Resumed)]
+        );
+    }
+
+    #[test]
+    fn resumption_disabled() {
+        assert_eq!(
+            handshakes(0),
+            [Some(HandshakeKind::Full), Some(HandshakeKind::Full)]
+        );
+    }
+
+    fn negotiated_alpn(
+        amend: impl FnOnce(TlsConfigBuilder) -> TlsConfigBuilder,
+    ) -> (Option<AlpnProtocol>, Option<Vec<u8>>) {
+        let server = TlsTestServer::start();
+        let tls_config = amend(TlsConfig::builder().root_certs(test_root_certs())).build();
+        let agent = server.agent(tls_config);
+
+        let res = agent
+            .get(format!("https://localhost:{}/", server.port))
+            .call()
+            .unwrap();
+
+        let client_side = res.extensions().get::<AlpnProtocol>().cloned();
+        let server_side = server.seen().remove(0).alpn_protocol;
+        (client_side, server_side)
+    }
+
+    #[test]
+    fn alpn_http11_by_default() {
+        let (client, server) = negotiated_alpn(|b| b);
+        assert_eq!(client.as_ref().and_then(|p| p.as_str()), Some("http/1.1"));
+        assert_eq!(server.as_deref(), Some(&b"http/1.1"[..]));
+    }
+
+    #[test]
+    fn alpn_prefers_first_supported() {
+        let (client, _) = negotiated_alpn(|b| b.alpn_protocols(&["h2", "http/1.1"]));
+        assert_eq!(client.unwrap().as_bytes(), b"http/1.1");
+    }
+
+    #[test]
+    fn alpn_disabled() {
+        let (client, server) = negotiated_alpn(|b| b.alpn_protocols(&[]));
+        assert_eq!(client, None);
+        assert_eq!(server, None);
+    }
+
+    #[test]
+    fn peer_certificates_extension() {
+        let server = TlsTestServer::start();
+        let tls_config = TlsConfig::builder().root_certs(test_root_certs()).build();
+        let agent = server.agent(tls_config);
+
+        let res = agent
+            .get(format!("https://localhost:{}/", server.port))
+            .call()
+            .unwrap();
+
+        let certs = res.extensions().get::<PeerCertificates>().unwrap();
+        let chain = server_chain();
+        let expected: Vec<&[u8]> = chain.iter().map(|c| c.as_ref()).collect();
+        let actual: Vec<&[u8]> = certs.certificates().iter().map(|c| c.der()).collect();
+        assert_eq!(actual, expected);
+        assert_eq!(certs.end_entity().unwrap().der(), expected[0]);
+    }
+
+    #[test]
+    fn close_notify_on_connection_close() {
+        let server = TlsTestServer::start();
+        let tls_config = TlsConfig::builder().root_certs(test_root_certs()).build();
+        let agent = server.agent(tls_config);
+
+        // The server answers with connection: close.
+        let mut res = agent
+            .get(format!("https://localhost:{}/", server.port))
+            .call()
+            .unwrap();
+        assert_eq!(res.body_mut().read_to_string().unwrap(), "ok");
+
+        // The server notes the close on its own thread.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.closes().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(server.closes(), [true]);
+    }
+
+    #[test]
+    fn tls_info_extension() {
+        let server = TlsTestServer::start();
+        let tls_config = TlsConfig::builder().root_certs(test_root_certs()).build();
+        let agent = server.agent(tls_config);
+
+        let res = agent
+            .get(format!("https://localhost:{}/", server.port))
+            .call()
+            .unwrap();
+
+        let info = res.extensions().get::<TlsInfo>().unwrap();
+        assert_eq!(info.protocol_version(), Some("TLSv1_3"));
+        assert!(info.cipher_suite().unwrap().starts_with("TLS13_"));
+        assert_eq!(info.alpn_protocol().unwrap().as_bytes(), b"http/1.1");
+    }
+
+    fn sni_for(host: &str, use_sni: bool) -> Result<Option<String>, Error> {
+        let server = TlsTestServer::start();
+        let tls_config = TlsConfig::builder()
+            .root_certs(test_root_certs())
+            .use_sni(use_sni)
+            .build();
+        let agent = server.agent(tls_config);
+
+        agent
+            .get(format!("https://{}:{}/", host, server.port))
+            .call()?;
+
+        Ok(server.seen().remove(0).sni)
+    }
+
+    #[test]
+    fn sni_sent_for_dns_name() {
+        assert_eq!(
+            sni_for("localhost", true).unwrap().as_deref(),
+            Some("localhost")
+        );
+    }
+
+    #[test]
+    fn sni_disabled_still_verifies() {
+        assert_eq!(sni_for("localhost", false).unwrap(), None);
+
+        // The server certificate is not valid for this name.
+        assert!(matches!(sni_for("ureq.test", false), Err(Error::Tls(_))));
+    }
+
+    #[test]
+    fn ip_address_verified_against_ip_san() {
+        assert_eq!(sni_for("127.0.0.1", true).unwrap(), None);
+    }
+
+    #[test]
+    fn ip_server_names() {
+        use rustls_pki_types::ServerName;
+
+        use super::server_name;
+
+        assert!(matches!(
+            server_name("10.0.0.5").unwrap(),
+            ServerName::IpAddress(_)
+        ));
+        assert!(matches!(
+            server_name("[::1]").unwrap(),
+            ServerName::IpAddress(_)
+        ));
+        assert!(matches!(
+            server_name("example.com").unwrap(),
+            ServerName::DnsName(_)
+        ));
+    }
+
+    fn call_with_ocsp(staple: Vec<u8>, mode: OcspStapling) -> Result<(), Error> {
+        let server = TlsTestServer::start_with_ocsp(staple);
+        let tls_config = TlsConfig::builder()
+            .root_certs(test_root_certs())
+            .ocsp_stapling(mode)
+            .build();
+        let agent = server.agent(tls_config);
+
+        agent
+            .get(format!("https://localhost:{}/", server.port))
+            .call()
+            .map(|_| ())
+    }
+
+    #[test]
+    fn ocsp_require_good() {
+        call_with_ocsp(ocsp_staple(false), OcspStapling::Require).unwrap();
+    }
+
+    #[test]
+    fn ocsp_require_revoked() {
+        let err = call_with_ocsp(ocsp_staple(true), OcspStapling::Require).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Tls("Server certificate is revoked (OCSP)")
+        ));
+    }
+
+    #[test]
+    fn ocsp_require_missing() {
+        let err = call_with_ocsp(vec![], OcspStapling::Require).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Tls("Server did not staple an OCSP response")
+        ));
+    }
+
+    #[test]
+    fn ocsp_prefer_missing() {
+        call_with_ocsp(vec![], OcspStapling::Prefer).unwrap();
+    }
+
+    #[test]
+    fn ocsp_prefer_revoked() {
+        let err = call_with_ocsp(ocsp_staple(true), OcspStapling::Prefer).unwrap_err();
+        assert!(matches!(err, Error::Tls(_)));
+    }
+
+    #[test]
+    fn ocsp_off_ignores_revoked() {
+        call_with_ocsp(ocsp_staple(true), OcspStapling::Off).unwrap();
+    }
+}
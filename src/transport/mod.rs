@@ -24,7 +24,7 @@
 use std::fmt::Debug;
 
 use http::uri::Scheme;
-use http::Uri;
+use http::{Extensions, Uri};
 
 use crate::config::Config;
 use crate::proxy::Proto;
@@ -45,6 +45,9 @@ pub use io::TransportAdapter;
 mod chain;
 pub use chain::ChainedConnector;
 
+mod stream;
+pub(crate) use stream::{StreamConnector, StreamResolver, StreamTransport};
+
 #[cfg(feature = "_test")]
 mod test;
 #[cfg(feature = "_test")]
@@ -128,6 +131,13 @@ pub struct ConnectionDetails<'a> {
     /// The next timeout for making the connection.
     // TODO(martin): Make mechanism to lower duration for each step in the connector chain.
     pub timeout: NextTimeout,
+
+    /// Extensions of the request the connection is made for.
+    ///
+    /// Set with [`RequestBuilder::extension()`](crate::RequestBuilder::extension), or on
+    /// an [`http::Request`]. A pooled connection is reused without asking the connector,
+    /// which means only the request opening the connection is seen here.
+    pub extensions: &'a Extensions,
 }
 
 impl<'a> ConnectionDetails<'a> {
@@ -185,6 +195,38 @@ pub trait Transport: Debug + Send + Sync {
     /// If that happens the transport must return an [`Error::Timeout`] instance.
     fn transmit_output(&mut self, amount: usize, timeout: NextTimeout) -> Result<(), Error>;
 
+    /// Transmit `amount` of the output buffer followed by `data`.
+    ///
+    /// ureq uses this to send an in-memory request body together with the request
+    /// head, without first copying the body to the output buffer. Transports on top
+    /// of a socket can use vectored IO (`writev`) to send both in one syscall.
+    /// The `small_posts` example times this against separate and copied writes.
+    ///
+    /// Defaults to copying `data` to the output buffer piece by piece, and calling
+    /// [`Transport::transmit_output()`] for each piece.
+    fn transmit_output_vectored(
+        &mut self,
+        amount: usize,
+        data: &[u8],
+        timeout: NextTimeout,
+    ) -> Result<(), Error> {
+        if amount > 0 {
+            self.transmit_output(amount, timeout)?;
+        }
+
+        let mut data = data;
+
+        while !data.is_empty() {
+            let output = self.buffers().output();
+            let n = output.len().min(data.len());
+            output[..n].copy_from_slice(&data[..n]);
+            self.transmit_output(n, timeout)?;
+            data = &data[n..];
+        }
+
+        Ok(())
+    }
+
     /// Await input from the transport. The transport should internally use
     /// [`Buffers::input_append_buf()`] followed by [`Buffers::input_appended()`] to
     /// store the incoming data.
@@ -194,12 +236,52 @@ pub trait Transport: Debug + Send + Sync {
     /// for connection pooling to work.
     fn is_open(&mut self) -> bool;
 
+    /// Shut down the connection gracefully before it is closed.
+    ///
+    /// ureq calls this when closing a connection after a complete response, such as
+    /// for `Connection: close`. It is not called for connections dropped on errors.
+    /// TLS transports send `close_notify`, which tells the server the connection
+    /// ended on purpose rather than being truncated. Any failure here is ignored.
+    ///
+    /// Defaults to doing nothing, override in TLS transports.
+    fn shutdown(&mut self) {}
+
     /// Whether the transport is TLS.
     ///
     /// Defaults to `false`, override in TLS transports.
     fn is_tls(&self) -> bool {
         false
     }
+
+    /// The application protocol negotiated using ALPN, if any.
+    ///
+    /// Defaults to `None`, override in TLS transports.
+    fn alpn_protocol(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// The DER encoded certificates presented by the peer during the TLS handshake.
+    ///
+    /// The end-entity certificate comes first, followed by any intermediates.
+    ///
+    /// Defaults to `None`, override in TLS transports.
+    fn peer_certificates(&self) -> Option<Vec<Vec<u8>>> {
+        None
+    }
+
+    /// The negotiated TLS protocol version, such as `"TLSv1_3"`.
+    ///
+    /// Defaults to `None`, override in TLS transports.
+    fn tls_protocol_version(&self) -> Option<String> {
+        None
+    }
+
+    /// The negotiated TLS cipher suite, such as `"TLS13_AES_256_GCM_SHA384"`.
+    ///
+    /// Defaults to `None`, override in TLS transports.
+    fn tls_cipher_suite(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Default connector providing TCP sockets, TLS and SOCKS proxy.
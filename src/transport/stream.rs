@@ -0,0 +1,137 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Mutex;
+use std::{fmt, io};
+
+use http::Uri;
+
+use crate::config::Config;
+use crate::resolver::{ResolvedSocketAddrs, Resolver};
+use crate::util::{ArrayVec, IoResultExt, SchemeExt};
+use crate::Error;
+
+use super::{Buffers, ConnectionDetails, Connector, LazyBuffers, NextTimeout, Transport};
+
+/// Connector handing out a stream that is already connected.
+///
+/// The stream is used for the first connection. Any later connection, such as for a
+/// redirect to another host, fails.
+pub(crate) struct StreamConnector(Mutex<Option<Box<dyn Transport>>>);
+
+impl StreamConnector {
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        StreamConnector(Mutex::new(Some(Box::new(transport))))
+    }
+}
+
+impl Connector for StreamConnector {
+    fn connect(
+        &self,
+        _details: &ConnectionDetails,
+        chained: Option<Box<dyn Transport>>,
+    ) -> Result<Option<Box<dyn Transport>>, Error> {
+        if chained.is_some() {
+            return Ok(chained);
+        }
+
+        let Some(transport) = self.0.lock().unwrap().take() else {
+            debug!("Stream already used");
+            return Err(Error::ConnectionFailed);
+        };
+
+        Ok(Some(transport))
+    }
+}
+
+/// Transport over any `Read + Write`.
+///
+/// Timeouts can't be set on a generic stream, they are up to the caller.
+pub(crate) struct StreamTransport<S> {
+    stream: S,
+    buffers: LazyBuffers,
+}
+
+impl<S> StreamTransport<S> {
+    pub fn new(stream: S, buffers: LazyBuffers) -> Self {
+        StreamTransport { stream, buffers }
+    }
+}
+
+impl<S: Read + Write + Send + Sync> Transport for StreamTransport<S> {
+    fn buffers(&mut self) -> &mut dyn Buffers {
+        &mut self.buffers
+    }
+
+    fn transmit_output(&mut self, amount: usize, timeout: NextTimeout) -> Result<(), Error> {
+        let output = &self.buffers.output()[..amount];
+        match write_and_flush(&mut self.stream, output).normalize_would_block() {
+            Ok(v) => Ok(v),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => Err(Error::Timeout(timeout.reason)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn await_input(&mut self, timeout: NextTimeout) -> Result<bool, Error> {
+        if self.buffers.can_use_input() {
+            return Ok(true);
+        }
+
+        let input = self.buffers.input_append_buf();
+        let amount = match self.stream.read(input).normalize_would_block() {
+            Ok(v) => Ok(v),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => Err(Error::Timeout(timeout.reason)),
+            Err(e) => Err(e.into()),
+        }?;
+        self.buffers.input_appended(amount);
+
+        Ok(amount > 0)
+    }
+
+    fn is_open(&mut self) -> bool {
+        // There is no way of probing a generic stream.
+        true
+    }
+}
+
+fn write_and_flush(w: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    w.write_all(data)?;
+    w.flush()
+}
+
+/// Resolver for when there is nothing to resolve, since the stream is already connected.
+#[derive(Debug)]
+pub(crate) struct StreamResolver;
+
+impl Resolver for StreamResolver {
+    fn resolve(
+        &self,
+        uri: &Uri,
+        _config: &Config,
+        _timeout: NextTimeout,
+    ) -> Result<ResolvedSocketAddrs, Error> {
+        let port = uri
+            .port_u16()
+            .or_else(|| uri.scheme().and_then(|s| s.default_port()))
+            .unwrap_or_default();
+
+        // The address is never used, but the pool needs something.
+        let mut addrs = ArrayVec::from_fn(|_| "0.0.0.0:0".parse().unwrap());
+        addrs.push(SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::UNSPECIFIED,
+            port,
+        )));
+        Ok(addrs)
+    }
+}
+
+impl fmt::Debug for StreamConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamConnector").finish()
+    }
+}
+
+impl<S> fmt::Debug for StreamTransport<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamTransport").finish()
+    }
+}
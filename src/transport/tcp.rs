@@ -1,4 +1,4 @@
-use std::io::{Read, Write};
+use std::io::{IoSlice, Read, Write};
 use std::net::{SocketAddr, TcpStream};
 use std::{fmt, io, time};
 
@@ -152,6 +152,29 @@ impl Transport for TcpTransport {
         Ok(())
     }
 
+    fn transmit_output_vectored(
+        &mut self,
+        amount: usize,
+        data: &[u8],
+        timeout: NextTimeout,
+    ) -> Result<(), Error> {
+        maybe_update_timeout(
+            timeout,
+            &mut self.timeout_write,
+            &self.stream,
+            TcpStream::set_write_timeout,
+        )?;
+
+        let output = &self.buffers.output()[..amount];
+        match write_all_vectored(&mut self.stream, output, data).normalize_would_block() {
+            Ok(v) => Ok(v),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => Err(Error::Timeout(timeout.reason)),
+            Err(e) => Err(e.into()),
+        }?;
+
+        Ok(())
+    }
+
     fn await_input(&mut self, timeout: NextTimeout) -> Result<bool, Error> {
         if self.buffers.can_use_input() {
             return Ok(true);
@@ -181,6 +204,24 @@ impl Transport for TcpTransport {
     }
 }
 
+/// Like `write_all`, but for two slices using vectored writes.
+fn write_all_vectored(w: &mut impl Write, mut a: &[u8], mut b: &[u8]) -> io::Result<()> {
+    while !a.is_empty() || !b.is_empty() {
+        let n = match w.write_vectored(&[IoSlice::new(a), IoSlice::new(b)]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        let from_a = n.min(a.len());
+        a = &a[from_a..];
+        b = &b[n - from_a..];
+    }
+
+    Ok(())
+}
+
 fn probe_tcp_stream(stream: &mut TcpStream) -> Result<bool, Error> {
     // Temporary do non-blocking IO
     stream.set_nonblocking(true)?;
@@ -219,3 +260,34 @@ impl fmt::Debug for TcpTransport {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Writes at most 3 bytes per call, using the default write_vectored.
+    struct Trickle(Vec<u8>);
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_all_vectored_partial_writes() {
+        let mut w = Trickle(vec![]);
+        write_all_vectored(&mut w, b"head\r\n", b"body").unwrap();
+        assert_eq!(w.0, b"head\r\nbody");
+
+        let mut w = Trickle(vec![]);
+        write_all_vectored(&mut w, b"", b"body").unwrap();
+        assert_eq!(w.0, b"body");
+    }
+}
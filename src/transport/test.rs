@@ -2,12 +2,12 @@
 
 use std::cell::RefCell;
 use std::io::Write;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
-use std::{fmt, io, thread};
+use std::{fmt, io, mem, thread};
 
-use http::{Method, Request, Uri};
+use http::{header, HeaderMap, Method, Request, StatusCode, Uri, Version};
 
 use crate::transport::time::Duration;
 use crate::Error;
@@ -104,31 +104,140 @@ fn test_run(
     tx: mpsc::SyncSender<Vec<u8>>,
     handlers: Vec<TestHandler>,
 ) {
-    let mut reader = BufReader::new(RxRead(rx));
+    let mut reader = BufReader::new(RxRead(rx, Vec::new()));
     let mut writer = TxWrite(tx);
-    let uri_s = uri.to_string();
-
-    let req = loop {
-        let input = reader.fill_buf().expect("test fill_buf");
-        let maybe = hoot::parser::try_parse_request::<100>(input).expect("test parse request");
-        if let Some((amount, req)) = maybe {
-            reader.consume(amount);
-            break req;
-        } else {
-            continue;
+    let mut uri = uri;
+    let mut first = true;
+
+    // A connection that is kept alive is served until ureq closes it.
+    loop {
+        let req = loop {
+            let input = reader.fill_buf().expect("test fill_buf");
+            if input.is_empty() {
+                // ureq closed the connection.
+                return;
+            }
+            let maybe = hoot::parser::try_parse_request::<100>(input).expect("test parse request");
+            if let Some((amount, mut req)) = maybe {
+                req.extensions_mut()
+                    .insert(RawHead(input[..amount].to_vec()));
+                reader.consume(amount);
+                break req;
+            } else {
+                continue;
+            }
+        };
+
+        // The parser drops the request target, later requests take it from the head.
+        if !mem::take(&mut first) {
+            uri = later_uri(&uri, &req);
         }
-    };
 
-    for handler in handlers {
-        if uri_s.contains(handler.pattern) {
-            (handler.handler)(uri, req, &mut writer).expect("test handler to not fail");
+        let uri_s = uri.to_string();
+        let Some(handler) = handlers.iter().find(|h| uri_s.contains(h.pattern)) else {
+            panic!("test server unhandled url: {}", uri);
+        };
+
+        let request_keeps_alive = request_keeps_alive(&req);
+        let head = req.method() == Method::HEAD;
+        let body_len = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        let expect = req.headers().get(header::EXPECT);
+        if expect.map(|v| v == "100-continue").unwrap_or(false) {
+            // Like httpbin, which lets the body be sent before answering.
+            write!(writer, "HTTP/1.1 100 Continue\r\n\r\n").expect("test write 100");
+        }
+
+        let mut tee = TeeWrite(&mut writer, Vec::new());
+        (handler.handler)(uri.clone(), req, &mut tee).expect("test handler to not fail");
+
+        if !request_keeps_alive || !response_keeps_alive(&tee.1, head) {
             return;
         }
+
+        // The handlers don't read the request body.
+        io::copy(&mut (&mut reader).take(body_len), &mut io::sink()).expect("test read body");
+    }
+}
+
+/// The uri of a later request on the connection, from the target in the request line.
+fn later_uri(uri: &Uri, req: &Request<()>) -> Uri {
+    let RawHead(head) = req.extensions().get().unwrap();
+    let line = head.split(|b| *b == b'\r').next().unwrap_or_default();
+    let target = line.split(|b| *b == b' ').nth(1).unwrap_or_default();
+    let target = String::from_utf8_lossy(target);
+
+    if !target.starts_with('/') {
+        // Absolute form, as sent to a proxy.
+        return target.parse().expect("test request target");
     }
 
-    panic!("test server unhandled url: {}", uri);
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(target.parse().expect("test request target"));
+    Uri::from_parts(parts).expect("test request uri")
+}
+
+/// Whether the request leaves the connection usable for another one.
+///
+/// Bodies that aren't delimited by content-length, and 100-continue, end the connection.
+fn request_keeps_alive(req: &Request<()>) -> bool {
+    let headers = req.headers();
+    req.version() == Version::HTTP_11
+        && !has_close(headers)
+        && !headers.contains_key(header::EXPECT)
+        && !headers.contains_key(header::TRANSFER_ENCODING)
+}
+
+/// Whether the response, as written by the handler, keeps the connection alive.
+///
+/// A body that is longer or shorter than its framing ends the connection, which is how
+/// the handlers test truncated responses.
+fn response_keeps_alive(response: &[u8], head: bool) -> bool {
+    let Ok(Some((head_len, res))) = hoot::parser::try_parse_response::<100>(response) else {
+        return false;
+    };
+    let headers = res.headers();
+    let status = res.status();
+    let body = &response[head_len..];
+
+    let length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    let chunked = headers.contains_key(header::TRANSFER_ENCODING);
+
+    let complete = if head || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED
+    {
+        body.is_empty()
+    } else if let Some(length) = length {
+        body.len() == length
+    } else {
+        chunked && body.ends_with(b"0\r\n\r\n")
+    };
+
+    res.version() == Version::HTTP_11
+        && !status.is_informational()
+        && !has_close(headers)
+        && complete
+}
+
+fn has_close(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case("close")))
 }
 
+/// The request head as received, before parsing normalizes it.
+#[derive(Clone)]
+struct RawHead(Vec<u8>);
+
 fn setup_default_handlers(handlers: &mut Vec<TestHandler>) {
     fn maybe_add(handler: TestHandler, handlers: &mut Vec<TestHandler>) {
         let already_declared = handlers.iter().any(|h| h.pattern == handler.pattern);
@@ -185,6 +294,23 @@ fn setup_default_handlers(handlers: &mut Vec<TestHandler>) {
         handlers,
     );
 
+    maybe_add(
+        TestHandler::new("/stalled-body", |_uri, _req, w| {
+            write!(
+                w,
+                "HTTP/1.1 200 OK\r\n\
+                Content-Type: application/octet-stream\r\n\
+                Content-Length: 200\r\n\
+                \r\n"
+            )?;
+            write!(w, "{}", "1".repeat(100))?;
+            // Hold on to the connection without sending the rest.
+            thread::sleep(std::time::Duration::from_secs(5));
+            Ok(())
+        }),
+        handlers,
+    );
+
     maybe_add(
         TestHandler::new("/get", |_uri, req, w| {
             write!(
@@ -247,6 +373,27 @@ fn setup_default_handlers(handlers: &mut Vec<TestHandler>) {
         handlers,
     );
 
+    maybe_add(
+        TestHandler::new("/patch", |_uri, req, w| {
+            let minimal = req
+                .headers()
+                .get_all("prefer")
+                .iter()
+                .any(|v| v == "return=minimal");
+            if minimal {
+                write!(
+                    w,
+                    "HTTP/1.1 204 No Content\r\n\
+                    Preference-Applied: return=minimal\r\n\
+                    \r\n"
+                )
+            } else {
+                write!(w, "HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\npatched")
+            }
+        }),
+        handlers,
+    );
+
     maybe_add(
         TestHandler::new("/delete", |_uri, _req, w| {
             write!(w, "HTTP/1.1 200 OK\r\n\r\ndeleted\n")
@@ -284,6 +431,23 @@ fn setup_default_handlers(handlers: &mut Vec<TestHandler>) {
         handlers,
     );
 
+    maybe_add(
+        TestHandler::new("/encoding/utf8", |_uri, _req, w| {
+            let body = "<h1>Unicode Demo</h1>\n∮ E⋅da = Q\n";
+            write!(
+                w,
+                "HTTP/1.1 200 OK\r\n\
+                Content-Type: text/html; charset=utf-8\r\n\
+                Content-Length: {}\r\n\
+                \r\n\
+                {}",
+                body.len(),
+                body
+            )
+        }),
+        handlers,
+    );
+
     maybe_add(
         TestHandler::new("/redirect-to", |_uri, _req, w| {
             write!(
@@ -299,6 +463,108 @@ fn setup_default_handlers(handlers: &mut Vec<TestHandler>) {
         handlers,
     );
 
+    maybe_add(
+        TestHandler::new("/echo-method", |_uri, req, w| {
+            let method = req.method().as_str();
+            write!(
+                w,
+                "HTTP/1.1 200 OK\r\n\
+                Content-Length: {}\r\n\
+                \r\n\
+                {}",
+                method.len(),
+                method
+            )
+        }),
+        handlers,
+    );
+
+    maybe_add(
+        TestHandler::new("/echo-framing", |_uri, req, w| {
+            let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+            let framing = match (header("content-length"), header("transfer-encoding")) {
+                (Some(v), _) => format!("content-length: {}", v),
+                (None, Some(v)) => format!("transfer-encoding: {}", v),
+                (None, None) => "none".to_string(),
+            };
+            write!(
+                w,
+                "HTTP/1.1 200 OK\r\n\
+                Content-Length: {}\r\n\
+                \r\n\
+                {}",
+                framing.len(),
+                framing
+            )
+        }),
+        handlers,
+    );
+
+    maybe_add(
+        TestHandler::new("/echo-head", |_uri, req, w| {
+            let head = &req.extensions().get::<RawHead>().unwrap().0;
+            write!(
+                w,
+                "HTTP/1.1 200 OK\r\n\
+                Content-Length: {}\r\n\
+                \r\n",
+                head.len()
+            )?;
+            w.write_all(head)
+        }),
+        handlers,
+    );
+
+    maybe_add(
+        TestHandler::new("/http10", |_uri, req, w| {
+            // Like a 1.0 server, only keep the connection if the client asks for it.
+            let keep_alive = req
+                .headers()
+                .get("connection")
+                .map(|v| v.as_bytes().eq_ignore_ascii_case(b"keep-alive"))
+                .unwrap_or(false);
+            let connection = if keep_alive {
+                "Connection: keep-alive\r\n"
+            } else {
+                ""
+            };
+            write!(
+                w,
+                "HTTP/1.0 200 OK\r\n\
+                Content-Length: 2\r\n\
+                {}\
+                \r\n\
+                ok",
+                connection
+            )
+        }),
+        handlers,
+    );
+
+    maybe_add(
+        TestHandler::new("/basic-auth", |_uri, req, w| {
+            // user:pass
+            let authorized = req
+                .headers()
+                .get("authorization")
+                .map(|v| v == "Basic dXNlcjpwYXNz")
+                .unwrap_or(false);
+            if authorized {
+                write!(w, "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+            } else {
+                write!(
+                    w,
+                    "HTTP/1.1 401 Unauthorized\r\n\
+                    WWW-Authenticate: Basic realm=\"test\"\r\n\
+                    Content-Length: 12\r\n\
+                    \r\n\
+                    unauthorized"
+                )
+            }
+        }),
+        handlers,
+    );
+
     maybe_add(
         TestHandler::new("/cookie-test", |_uri, req, w| {
             let mut all: Vec<_> = req
@@ -394,21 +660,39 @@ const HTTPBIN_JSON: &str = r#"
   }
 }"#;
 
-struct RxRead(Receiver<Vec<u8>>);
+/// Receives the data sent by ureq, keeping what doesn't fit for the next read.
+struct RxRead(Receiver<Vec<u8>>, Vec<u8>);
 
 impl io::Read for RxRead {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let v = match self.0.recv() {
-            Ok(v) => v,
-            Err(_) => return Ok(0), // remote side is gone
-        };
-        assert!(buf.len() > v.len());
-        let max = buf.len().min(v.len());
-        buf[..max].copy_from_slice(&v[..]);
+        while self.1.is_empty() {
+            match self.0.recv() {
+                Ok(v) => self.1 = v,
+                Err(_) => return Ok(0), // remote side is gone
+            }
+        }
+        let max = buf.len().min(self.1.len());
+        buf[..max].copy_from_slice(&self.1[..max]);
+        self.1.drain(..max);
         Ok(max)
     }
 }
 
+/// Keeps a copy of the response, to tell whether the connection is kept alive.
+struct TeeWrite<'a>(&'a mut TxWrite, Vec<u8>);
+
+impl io::Write for TeeWrite<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(buf)?;
+        self.1.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 struct TxWrite(mpsc::SyncSender<Vec<u8>>);
 
 impl io::Write for TxWrite {
@@ -0,0 +1,433 @@
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use http::Uri;
+
+use crate::Error;
+
+/// Parse a URI, percent-encoding characters not allowed in the path and query.
+///
+/// [`Uri`] only accepts URIs that are already correctly encoded. When building
+/// URIs from user input, this function fixes up spaces, non-ASCII and other
+/// disallowed characters as per RFC 3986. Sequences that are already percent-encoded
+/// are left intact, while a `%` that doesn't start such a sequence is encoded as `%25`.
+///
+/// The scheme and host are not percent-encoded. A fragment (`#...`) is never sent in a request
+/// and is removed.
+///
+/// With the **idna** feature, an internationalized host such as `bücher.example` is
+/// converted to its ASCII form `xn--bcher-kva.example`, which is what is resolved and
+/// sent as TLS SNI and `Host` header.
+///
+/// Fails with [`Error::BadUri`] if the URI can't be fixed, such as when the scheme
+/// or host is missing or invalid.
+///
+/// ```
+/// let uri = ureq::normalize_uri("https://example.com/my files/åäö?q=a b&r=100%25")?;
+/// assert_eq!(
+///     uri,
+///     "https://example.com/my%20files/%C3%A5%C3%A4%C3%B6?q=a%20b&r=100%25"
+/// );
+///
+/// let res = ureq::get(uri).call();
+/// # Ok::<_, ureq::Error>(())
+/// ```
+pub fn normalize_uri(uri: &str) -> Result<Uri, Error> {
+    let uri = uri.trim();
+
+    let Some(scheme_end) = uri.find("://") else {
+        return Err(Error::BadUri(format!("{} is missing scheme", uri)));
+    };
+
+    let authority_start = scheme_end + 3;
+    let authority_end = authority_end(uri, authority_start);
+
+    let (scheme_authority, rest) = uri.split_at(authority_end);
+
+    if authority_end == authority_start {
+        return Err(Error::BadUri(format!("{} is missing host", uri)));
+    }
+
+    #[cfg(feature = "idna")]
+    let ascii = host_to_ascii(uri, scheme_authority, authority_start)?;
+    #[cfg(feature = "idna")]
+    let scheme_authority = &*ascii;
+
+    if let Some(c) = scheme_authority.chars().find(|c| !is_uri_char(*c)) {
+        return Err(Error::BadUri(format!(
+            "{} has invalid character in scheme or host: {:?}",
+            uri, c
+        )));
+    }
+
+    // The fragment is client side only.
+    let rest = rest.split_once('#').map(|(r, _)| r).unwrap_or(rest);
+    let (path, query) = match rest.split_once('?') {
+        Some((p, q)) => (p, Some(q)),
+        None => (rest, None),
+    };
+
+    let mut normalized = String::with_capacity(uri.len() + 16);
+    normalized.push_str(scheme_authority);
+    encode_into(&mut normalized, path, is_path_char);
+    if let Some(query) = query {
+        normalized.push('?');
+        encode_into(&mut normalized, query, is_query_char);
+    }
+
+    Uri::try_from(&normalized).map_err(|e| Error::BadUri(format!("{}: {}", uri, e)))
+}
+
+/// Something to make a request to, such as a `&str`, `String` or [`Uri`].
+///
+/// Implemented for the types [`Uri`] converts from. With the **idna** feature, an
+/// international host in a string, such as `https://exämple.com/`, is converted to its
+/// ASCII form, which fails with [`Error::BadUri`] for an invalid host. Unlike
+/// [`normalize_uri()`], nothing else of the uri is changed.
+pub trait IntoUri {
+    /// Convert into a [`Uri`].
+    fn into_uri(self) -> Result<Uri, Error>;
+}
+
+impl IntoUri for Uri {
+    fn into_uri(self) -> Result<Uri, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoUri for &Uri {
+    fn into_uri(self) -> Result<Uri, Error> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoUri for &str {
+    fn into_uri(self) -> Result<Uri, Error> {
+        parse_uri(self)
+    }
+}
+
+impl IntoUri for &String {
+    fn into_uri(self) -> Result<Uri, Error> {
+        parse_uri(self)
+    }
+}
+
+impl IntoUri for String {
+    fn into_uri(self) -> Result<Uri, Error> {
+        parse_uri(&self)
+    }
+}
+
+impl IntoUri for &[u8] {
+    fn into_uri(self) -> Result<Uri, Error> {
+        Uri::try_from(self).map_err(|e| Error::Http(e.into()))
+    }
+}
+
+impl IntoUri for Vec<u8> {
+    fn into_uri(self) -> Result<Uri, Error> {
+        Uri::try_from(self).map_err(|e| Error::Http(e.into()))
+    }
+}
+
+impl IntoUri for http::uri::Parts {
+    fn into_uri(self) -> Result<Uri, Error> {
+        Uri::try_from(self).map_err(|e| Error::Http(e.into()))
+    }
+}
+
+/// Parse a uri, with an international host converted to ASCII.
+fn parse_uri(uri: &str) -> Result<Uri, Error> {
+    #[cfg(feature = "idna")]
+    if let (false, Some(scheme_end)) = (uri.is_ascii(), uri.find("://")) {
+        let authority_start = scheme_end + 3;
+        let authority_end = authority_end(uri, authority_start);
+        let ascii = host_to_ascii(uri, &uri[..authority_end], authority_start)?;
+        let uri = format!("{}{}", ascii, &uri[authority_end..]);
+        return Uri::try_from(uri).map_err(|e| Error::Http(e.into()));
+    }
+
+    Uri::try_from(uri).map_err(|e| Error::Http(e.into()))
+}
+
+/// Where the authority that starts at `authority_start` ends.
+fn authority_end(uri: &str, authority_start: usize) -> usize {
+    uri[authority_start..]
+        .find(['/', '?', '#'])
+        .map(|i| authority_start + i)
+        .unwrap_or(uri.len())
+}
+
+/// Convert a non-ASCII host to punycode, leaving scheme, userinfo and port as is.
+#[cfg(feature = "idna")]
+fn host_to_ascii<'a>(
+    uri: &str,
+    scheme_authority: &'a str,
+    authority_start: usize,
+) -> Result<std::borrow::Cow<'a, str>, Error> {
+    use std::borrow::Cow;
+
+    let (scheme, authority) = scheme_authority.split_at(authority_start);
+
+    if authority.is_ascii() {
+        return Ok(Cow::Borrowed(scheme_authority));
+    }
+
+    let (userinfo, host_port) = match authority.rfind('@') {
+        Some(i) => authority.split_at(i + 1),
+        None => ("", authority),
+    };
+
+    let (host, port) = match host_port.rfind(':') {
+        Some(i) if host_port[i + 1..].bytes().all(|b| b.is_ascii_digit()) => host_port.split_at(i),
+        _ => (host_port, ""),
+    };
+
+    let ascii = idna::domain_to_ascii(host)
+        .map_err(|_| Error::BadUri(format!("{} has invalid international host: {}", uri, host)))?;
+
+    Ok(Cow::Owned(format!(
+        "{}{}{}{}",
+        scheme, userinfo, ascii, port
+    )))
+}
+
+/// Split the userinfo off a uri, into percent-decoded username and password.
+///
+/// Returns the uri without the userinfo, since that must not be sent in a request.
+pub(crate) fn split_userinfo(uri: &Uri) -> Option<(Uri, String, String)> {
+    let authority = uri.authority()?.as_str();
+    let (userinfo, host_port) = authority.rsplit_once('@')?;
+
+    // A username can't contain `:` unless encoded, while a password can.
+    let (username, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+
+    let mut parts = uri.clone().into_parts();
+    parts.authority = Some(host_port.parse().ok()?);
+    let uri = Uri::from_parts(parts).ok()?;
+
+    Some((uri, percent_decode(username), percent_decode(password)))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if is_percent_encoded(&bytes[i..]) {
+            // unwraps are ok, is_percent_encoded checked for two hex digits.
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+            out.push(u8::from_str_radix(hex, 16).unwrap());
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn encode_into(out: &mut String, s: &str, allowed: fn(u8) -> bool) {
+    let bytes = s.as_bytes();
+
+    for (i, &b) in bytes.iter().enumerate() {
+        let keep = if b == b'%' {
+            is_percent_encoded(&bytes[i..])
+        } else {
+            allowed(b)
+        };
+
+        if keep {
+            out.push(b as char);
+        } else {
+            // unwrap is ok, since writing to a String can't fail.
+            write!(out, "%{:02X}", b).unwrap();
+        }
+    }
+}
+
+fn is_percent_encoded(s: &[u8]) -> bool {
+    matches!(s, [b'%', a, b, ..] if a.is_ascii_hexdigit() && b.is_ascii_hexdigit())
+}
+
+fn is_uri_char(c: char) -> bool {
+    c.is_ascii_graphic() && !matches!(c, '"' | '<' | '>' | '\\' | '^' | '`' | '{' | '|' | '}')
+}
+
+/// RFC 3986 pchar, and `/` to separate segments.
+fn is_path_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            // unreserved
+            b'-' | b'.' | b'_' | b'~'
+            // sub-delims
+            | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+            // pchar
+            | b':' | b'@'
+            | b'/'
+        )
+}
+
+fn is_query_char(b: u8) -> bool {
+    is_path_char(b) || b == b'?'
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn normalize(s: &str) -> String {
+        normalize_uri(s).unwrap().to_string()
+    }
+
+    #[test]
+    fn already_valid() {
+        let s = "https://user@example.com:8080/a/b;c=d?e=f&g=h";
+        assert_eq!(normalize(s), s);
+    }
+
+    #[test]
+    fn space_and_unicode() {
+        assert_eq!(
+            normalize("http://example.com/a b/ö?q=x y"),
+            "http://example.com/a%20b/%C3%B6?q=x%20y"
+        );
+    }
+
+    #[test]
+    fn keep_encoded_sequences() {
+        assert_eq!(
+            normalize("http://example.com/a%20b%2f?q=%C3%B6"),
+            "http://example.com/a%20b%2f?q=%C3%B6"
+        );
+    }
+
+    #[test]
+    fn stray_percent() {
+        assert_eq!(
+            normalize("http://example.com/100%?q=%zz&r=%"),
+            "http://example.com/100%25?q=%25zz&r=%25"
+        );
+    }
+
+    #[test]
+    fn disallowed_ascii() {
+        assert_eq!(
+            normalize("http://example.com/\"a\"/{b}|c?d=<e>"),
+            "http://example.com/%22a%22/%7Bb%7D%7Cc?d=%3Ce%3E"
+        );
+    }
+
+    #[test]
+    fn question_mark_in_query() {
+        assert_eq!(
+            normalize("http://example.com/a?b=c?d"),
+            "http://example.com/a?b=c?d"
+        );
+    }
+
+    #[test]
+    fn fragment_removed() {
+        assert_eq!(
+            normalize("http://example.com/a b#frag ment"),
+            "http://example.com/a%20b"
+        );
+    }
+
+    #[test]
+    fn no_path() {
+        assert_eq!(normalize("http://example.com"), "http://example.com/");
+        assert_eq!(
+            normalize("http://example.com?a b"),
+            "http://example.com/?a%20b"
+        );
+    }
+
+    #[test]
+    fn split_userinfo_decodes() {
+        let uri = Uri::from_static("https://us%40er:p%3Ass:word@example.com:8080/a?b=c");
+        let (uri, username, password) = split_userinfo(&uri).unwrap();
+        assert_eq!(uri, "https://example.com:8080/a?b=c");
+        assert_eq!(username, "us@er");
+        assert_eq!(password, "p:ss:word");
+
+        let uri = Uri::from_static("http://user@example.com/");
+        let (_, username, password) = split_userinfo(&uri).unwrap();
+        assert_eq!(username, "user");
+        assert_eq!(password, "");
+
+        assert!(split_userinfo(&Uri::from_static("http://example.com/")).is_none());
+    }
+
+    #[test]
+    fn missing_scheme() {
+        let err = normalize_uri("example.com/a b").unwrap_err();
+        assert!(matches!(err, Error::BadUri(_)));
+        assert_eq!(
+            err.to_string(),
+            "bad uri: example.com/a b is missing scheme"
+        );
+    }
+
+    #[test]
+    fn missing_host() {
+        let err = normalize_uri("http:///a").unwrap_err();
+        assert_eq!(err.to_string(), "bad uri: http:///a is missing host");
+    }
+
+    #[test]
+    fn invalid_host() {
+        let err = normalize_uri("http://exa mple.com/").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad uri: http://exa mple.com/ has invalid character in scheme or host: ' '"
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "idna"))]
+    fn international_host_without_idna() {
+        let err = normalize_uri("http://bücher.example/").unwrap_err();
+        assert!(matches!(err, Error::BadUri(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "idna")]
+    fn international_host_into_uri() {
+        let uri = "https://exämple.com/a%20b".into_uri().unwrap();
+        assert_eq!(uri, "https://xn--exmple-cua.com/a%20b");
+
+        let err = "https://xn--ä.com/".into_uri().unwrap_err();
+        assert!(matches!(err, Error::BadUri(_)));
+
+        // Only the host is converted.
+        assert!(matches!(
+            "https://exämple.com/ö".into_uri(),
+            Err(Error::Http(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "idna")]
+    fn international_host() {
+        assert_eq!(
+            normalize("https://exämple.com/ö"),
+            "https://xn--exmple-cua.com/%C3%B6"
+        );
+        assert_eq!(
+            normalize("http://user@Bücher.example:8080/"),
+            "http://user@xn--bcher-kva.example:8080/"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "idna")]
+    fn invalid_international_host() {
+        let err = normalize_uri("http://bad\u{FFFD}.example/").unwrap_err();
+        assert!(matches!(err, Error::BadUri(_)));
+    }
+}
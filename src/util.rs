@@ -270,7 +270,17 @@ impl<'a> fmt::Debug for DebugAuthority<'a> {
             write!(f, "@")?;
         }
 
-        write!(f, "{}", self.0.host())?;
+        let host = self.0.host();
+
+        // An international host is shown as given, not in its ASCII form.
+        #[cfg(feature = "idna")]
+        if host.split('.').any(|l| l.starts_with("xn--")) {
+            write!(f, "{}", idna::domain_to_unicode(host).0)?;
+        } else {
+            write!(f, "{}", host)?;
+        }
+        #[cfg(not(feature = "idna"))]
+        write!(f, "{}", host)?;
 
         if let Some(p) = self.0.port_u16() {
             write!(f, ":{}", p)?;
@@ -327,6 +337,7 @@ pub(crate) trait HeaderMapExt {
     }
     fn has_accept(&self) -> bool;
     fn has_content_type(&self) -> bool;
+    fn has_connection(&self) -> bool;
 }
 
 impl HeaderMapExt for HeaderMap {
@@ -362,4 +373,8 @@ impl HeaderMapExt for HeaderMap {
     fn has_content_type(&self) -> bool {
         self.contains_key("content-type")
     }
+
+    fn has_connection(&self) -> bool {
+        self.contains_key("connection")
+    }
 }